@@ -9,9 +9,10 @@ extern CommandStorage command;
 #define SERIAL_BAUD 115200
 
 #define MAX_PWM_CHANNEL_INDEX 15
-#define PWM_RESOLUTION 10
 #define OUTPUT_GPIO 25 //output led GPIO for onboard commands e.g. format storage
 #define INPUT_GPIO 0 //input GPIO for onboard commands e.g. format storage
+#define TACH_PULSES_PER_REVOLUTION 2 //standard PC fans pull the tach line low twice per turn
+#define TACH_SAMPLE_TIME 1000
 
 void setupBoard();
 void boardLoop();
@@ -21,5 +22,13 @@ void writeOutputs();
 
 void reloadOutputs(); // Update pins that will be used as output
 
+int getPortRpm(int port); // -1 when the port has no tach input
+float readTemperature(int input); // °C, NAN when the thermistor is open or shorted
+
+void rebootToBootloader(); // shows up as the UF2 mass-storage volume, doesn't return
+void reboot(); // watchdog reboot, the storage is kept and read again, doesn't return
+
+void startIdentify(int seconds); // blinks OUTPUT_GPIO from boardLoop, even when it is a port
+
 
 JsonDocument getBoardInfo();
\ No newline at end of file
@@ -1,8 +1,13 @@
 #pragma onece
 
 #include <Arduino.h>
+#include <ArduinoJson.h>
+
+#define PROTOCOL_VERSION 1
+#define FIRMWARE_VERSION F("0.2.0")
 
 #define SET_VALUE_COMMAND F("set_value")
+#define SET_VALUES_COMMAND F("set_values")
 #define PORTS_SETUP_COMMAND F("ports_setup")
 #define GET_VALUE_COMMAND F("get_value")
 #define GET_CONFIG_COMMAND F("get_config")
@@ -13,9 +18,27 @@
 #define SET_UPDATE_TIME_COMMAND F("set_update_time")
 #define BOARD_INFO_COMMAND F("board_info")
 #define PING_COMMAND F("ping")
+#define GET_RPM_COMMAND F("get_rpm")
+#define BOOTLOADER_COMMAND F("bootloader")
+#define HEARTBEAT_COMMAND F("heartbeat")
+#define SET_HEARTBEAT_TIMEOUT_COMMAND F("set_heartbeat_timeout")
+#define SET_VALUES_PERMILLE_COMMAND F("set_values_permille")
+#define GET_VALUE_PERMILLE_COMMAND F("get_value_permille")
+#define REBOOT_COMMAND F("reboot")
+#define FACTORY_RESET_COMMAND F("factory_reset")
+#define IDENTIFY_COMMAND F("identify")
+#define SET_TEMPERATURE_INPUTS_COMMAND F("set_temperature_inputs")
+#define GET_TEMPERATURE_INPUTS_COMMAND F("get_temperature_inputs")
+#define GET_TEMPERATURES_COMMAND F("get_temperatures")
+#define SET_STANDALONE_CURVES_COMMAND F("set_standalone_curves")
+#define GET_STANDALONE_CURVES_COMMAND F("get_standalone_curves")
+#define GET_CONTROL_MODE_COMMAND F("get_control_mode")
+
+#define PORT_SETTINGS_CAPABILITY F("port_settings") // set_config takes per-port PWM settings
 
 enum Command {
     SET_VALUE_CMD,
+    SET_VALUES_CMD,
     PORTS_SETUP_CMD,
     GET_VALUE_CMD,
     GET_CONFIG_CMD,
@@ -25,7 +48,24 @@ enum Command {
     SET_DEFAULT_VALUE_CMD,
     SET_UPDATE_TIME_CMD,
     BOARD_INFO_CMD,
-    PING_CMD
+    PING_CMD,
+    GET_RPM_CMD,
+    BOOTLOADER_CMD,
+    HEARTBEAT_CMD,
+    SET_HEARTBEAT_TIMEOUT_CMD,
+    SET_VALUES_PERMILLE_CMD,
+    GET_VALUE_PERMILLE_CMD,
+    REBOOT_CMD,
+    FACTORY_RESET_CMD,
+    IDENTIFY_CMD,
+    SET_TEMPERATURE_INPUTS_CMD,
+    GET_TEMPERATURE_INPUTS_CMD,
+    GET_TEMPERATURES_CMD,
+    SET_STANDALONE_CURVES_CMD,
+    GET_STANDALONE_CURVES_CMD,
+    GET_CONTROL_MODE_CMD
 };
 
-Command stringToCommand(String cmd);
\ No newline at end of file
+Command stringToCommand(String cmd);
+bool isPassiveCommand(Command cmd); // doesn't count as the host taking control
+void fillCapabilities(JsonArray capabilities);
\ No newline at end of file
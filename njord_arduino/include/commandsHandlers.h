@@ -6,6 +6,7 @@ extern GlobalStorage data;
 extern CommandStorage command;
 
 void setValueHandler();
+void setValuesHandler();
 void portsSetupHandler();
 void getValueHandler();
 void getConfigHandler();
@@ -16,5 +17,22 @@ void setDefaultValueHandler();
 void setUpdateTimeHandler();
 void boardInfoHandler();
 void pingHandler();
+void getRpmHandler();
+void bootloaderHandler();
+void heartbeatHandler();
+void setHeartbeatTimeoutHandler();
+void setValuesPermilleHandler();
+void getValuePermilleHandler();
+void rebootHandler();
+void factoryResetHandler();
+void identifyHandler();
+void setTemperatureInputsHandler();
+void getTemperatureInputsHandler();
+void getTemperaturesHandler();
+void setStandaloneCurvesHandler();
+void getStandaloneCurvesHandler();
+void getControlModeHandler();
 
-void handleCommand();
\ No newline at end of file
+void handleCommand();
+void checkFailsafe(); // called from the main loop
+void standaloneLoop(); // runs the stored curves while the host is away, called from the main loop
\ No newline at end of file
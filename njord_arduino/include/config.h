@@ -6,6 +6,33 @@
 
 #define DEFAULT_CONFIG F("{\"ports\":[25], \"default_values\":[100], \"update_time\":120}")
 #define INITIAL_VALUE 0
+#define MAX_PERCENT_VALUE 100
+#define MAX_PERMILLE_VALUE 1000
+#define PERCENT_SCALE 10 // port values are kept in permille, percent commands and default_values get scaled
+#define NO_TACH_INPUT -1
+
+#define DEFAULT_PWM_FREQUENCY 25000
+#define DEFAULT_PWM_RESOLUTION 10
+#define MIN_PWM_FREQUENCY 10
+#define MAX_PWM_FREQUENCY 100000
+#define MAX_PWM_RESOLUTION 16
+
+#define OUTPUT_MODE_PWM 0
+#define OUTPUT_MODE_ON_OFF 1
+
+#define MAX_TEMPERATURE_INPUTS 4
+#define INTERNAL_TEMPERATURE_GPIO -1 // the on-chip sensor instead of a thermistor
+#define THERMISTOR_MODEL_BETA 0
+#define THERMISTOR_MODEL_STEINHART_HART 1
+#define ADC_MAX_VALUE 4095 // 12 bit reads
+#define ADC_SAMPLES 8
+#define KELVIN_OFFSET 273.15f
+
+#define MAX_CURVE_POINTS 8
+#define STANDALONE_UPDATE_TIME 1000 // how often curves are evaluated while the host is away
+
+#define MAX_IDENTIFY_TIME 60 // seconds
+#define IDENTIFY_BLINK_TIME 250
 
 #define UPDATE_TIME 200
 
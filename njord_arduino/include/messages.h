@@ -11,6 +11,7 @@ extern bool newCommand;
 #define PONG_MSG F("pong")
 #define CLEAR_OK F("clear-ok")
 #define AFTER_HW_RESET F("after-hw-reset")
+#define FAILSAFE_MSG F("failsafe")
 
 #define OK_CODE F("ok")
 #define INFO_CODE F("info")
@@ -4,17 +4,48 @@
 #include <Arduino.h>  
 #include <stdlib.h>
 #include <vector>
+#include <config.h>
+
+struct TemperatureInput {
+  String name;
+  int gpio; // INTERNAL_TEMPERATURE_GPIO for the on-chip sensor
+  float series_resistance;
+  int model; // THERMISTOR_MODEL_BETA or THERMISTOR_MODEL_STEINHART_HART
+  float params[3]; // beta, nominal resistance, nominal temperature or a, b, c
+};
+
+// A port curve the board runs by itself while no host is around, values in permille
+struct StandaloneCurve {
+  bool enabled = false;
+  String temperature_input; // name of the input it follows
+  std::vector<float> temps; // ascending
+  std::vector<int> values;
+  int min_value = 0;
+  int max_value = MAX_PERMILLE_VALUE;
+};
 
 struct GlobalStorage {
   std::vector<int> port_config;
   std::vector<int> values;
   std::vector<int> channels;
   std::vector<int> default_values;
+  std::vector<int> tach_inputs; // GPIO per port, NO_TACH_INPUT when the fan has no tach wire
+  std::vector<int> pwm_frequency; // Hz per port
+  std::vector<int> pwm_resolution; // bits per port
+  std::vector<bool> inverted; // active-low ports
+  std::vector<int> output_mode; // OUTPUT_MODE_PWM or OUTPUT_MODE_ON_OFF per port
   int update_time = 0;
+  int heartbeat_timeout = 0; // ms without host commands before reverting to default_values, 0 disables it
+  std::vector<TemperatureInput> temperature_inputs; // kept apart from the ports, set_config leaves them alone
+  std::vector<StandaloneCurve> standalone_curves; // one per port, disabled ones keep default_values
 
   void dumpFile();
   bool loadFile();
   bool loadJson(JsonDocument doc);
+  bool loadTemperatureInputs(JsonArray inputs);
+  void fillTemperatureInputs(JsonArray inputs);
+  bool loadStandaloneCurves(JsonArray curves);
+  void fillStandaloneCurves(JsonArray curves);
   void loadDefault();
   JsonDocument getJson();
 };
@@ -24,6 +55,7 @@ class CommandStorage {
   private:
     String com;
     std::vector<String> data;
+    long id = -1;
   
   public:
     ~CommandStorage();
@@ -35,6 +67,9 @@ class CommandStorage {
     String getDataElement(int index);
     void addDataElement(String addData);
     int getDataLength();
+
+    bool hasId();
+    long getId();
     
     bool setFromJson(JsonDocument doc);
 
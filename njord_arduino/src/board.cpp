@@ -5,8 +5,99 @@
 #include <ArduinoJson.h>
 #include <messages.h>
 #include <errors.h>
+#include <commands.hpp>
 #include <hardware/pwm.h>
 #include <Adafruit_TinyUSB.h>
+#include <pico/unique_id.h>
+#include <config.h>
+
+volatile uint32_t tachPulses[MAX_PWM_CHANNEL_INDEX + 1];
+std::vector<int> tachPins;
+std::vector<int> portsRpm;
+unsigned long lastTachSample = 0;
+bool identifying = false;
+unsigned long identifyUntil = 0;
+
+void onTachPulse(void *port) {
+    tachPulses[(intptr_t)port]++;
+}
+
+void setupTach() {
+    portsRpm.assign(data.tach_inputs.size(), -1);
+    for (int port = 0; port < data.tach_inputs.size() && port <= MAX_PWM_CHANNEL_INDEX; port++) {
+        int pin = data.tach_inputs[port];
+        if (pin == NO_TACH_INPUT) continue;
+
+        tachPulses[port] = 0;
+        portsRpm[port] = 0;
+        pinMode(pin, INPUT_PULLUP);
+        attachInterruptParam(pin, onTachPulse, FALLING, (void *)(intptr_t)port);
+        tachPins.push_back(pin);
+    }
+    lastTachSample = millis();
+}
+
+void releaseTach() {
+    for (int pin : tachPins) {
+        detachInterrupt(pin);
+    }
+    tachPins.clear();
+}
+
+void sampleTach() {
+    unsigned long elapsed = millis() - lastTachSample;
+    if (elapsed < TACH_SAMPLE_TIME) return;
+
+    for (int port = 0; port < portsRpm.size(); port++) {
+        if (portsRpm[port] < 0) continue;
+
+        noInterrupts();
+        uint32_t pulses = tachPulses[port];
+        tachPulses[port] = 0;
+        interrupts();
+        portsRpm[port] = pulses * 60000 / (elapsed * TACH_PULSES_PER_REVOLUTION);
+    }
+    lastTachSample = millis();
+}
+
+int getPortRpm(int port) {
+    if (port < 0 || port >= portsRpm.size()) return -1;
+    return portsRpm[port];
+}
+
+// The series resistor sits between 3.3 V and the pin, the thermistor between the pin and ground
+float readThermistorResistance(TemperatureInput &input) {
+    uint32_t sum = 0;
+    for (int i = 0; i < ADC_SAMPLES; i++) {
+        sum += analogRead(input.gpio);
+    }
+    float adc = (float)sum / ADC_SAMPLES;
+    if (adc < 1 || adc > ADC_MAX_VALUE - 1) return NAN;
+    return input.series_resistance * adc / (ADC_MAX_VALUE - adc);
+}
+
+float readTemperature(int input) {
+    if (input < 0 || input >= data.temperature_inputs.size()) return NAN;
+
+    TemperatureInput &temperatureInput = data.temperature_inputs[input];
+    if (temperatureInput.gpio == INTERNAL_TEMPERATURE_GPIO) {
+        return analogReadTemp();
+    }
+
+    float resistance = readThermistorResistance(temperatureInput);
+    if (isnan(resistance)) return NAN;
+
+    float inverseKelvin;
+    float *params = temperatureInput.params;
+    if (temperatureInput.model == THERMISTOR_MODEL_BETA) {
+        inverseKelvin = 1.0f / (params[2] + KELVIN_OFFSET) + logf(resistance / params[1]) / params[0];
+    } else {
+        float lnR = logf(resistance);
+        inverseKelvin = params[0] + params[1] * lnR + params[2] * lnR * lnR * lnR;
+    }
+    if (inverseKelvin <= 0) return NAN;
+    return 1.0f / inverseKelvin - KELVIN_OFFSET;
+}
 
 void tud_suspend_cb(bool remote_wakeup_en) {
     for (int index = 0; index < data.values.size(); index++) {
@@ -43,46 +134,86 @@ void setupBoard(){
     }
 
     watchdog_disable();
+    analogReadResolution(12);
     beginStorage();
 }
 
+void startIdentify(int seconds) {
+    identifying = true;
+    identifyUntil = millis() + seconds * 1000UL;
+    gpio_init(OUTPUT_GPIO);
+    gpio_set_dir(OUTPUT_GPIO, GPIO_OUT);
+}
+
+void identifyLoop() {
+    if (!identifying) return;
+
+    if ((long)(millis() - identifyUntil) >= 0) {
+        identifying = false;
+        gpio_put(OUTPUT_GPIO, LOW);
+        reloadOutputs(); // hands the LED back when it is a port
+        return;
+    }
+    gpio_put(OUTPUT_GPIO, (millis() / IDENTIFY_BLINK_TIME) % 2);
+}
+
 void boardLoop(){
+    sampleTach();
+    identifyLoop();
 }
 
 void setupOutputs(){
     int currentPwm = 0;
 
-    uint16_t wrap = (1 << PWM_RESOLUTION) - 1;
-    float divider = 125000000.0 / (clock_get_hz(clk_sys) * (wrap+1));
+    for (int port = 0; port < data.port_config.size(); port++){
+        int gpio = data.port_config[port];
+        gpio_set_outover(gpio, data.inverted[port] ? GPIO_OVERRIDE_INVERT : GPIO_OVERRIDE_NORMAL);
+
+        if (data.output_mode[port] == OUTPUT_MODE_ON_OFF) {
+            gpio_init(gpio);
+            gpio_set_dir(gpio, GPIO_OUT);
+        } else {
+            gpio_set_function(gpio, GPIO_FUNC_PWM);
+            // slice and channel conflicts are refused by the host before set_config gets here
+            uint slice_num = pwm_gpio_to_slice_num(gpio);
 
-    for (int gpio : data.port_config){
-        gpio_set_function(gpio, GPIO_FUNC_PWM);
-        //TODO: implement checking of channels in use
-        uint slice_num = pwm_gpio_to_slice_num(gpio);
+            // the divider is 8.4 fixed point, low frequencies at high resolutions get clamped
+            uint32_t wrap = (1 << data.pwm_resolution[port]) - 1;
+            float divider = (float)clock_get_hz(clk_sys) / ((float)data.pwm_frequency[port] * (wrap + 1));
+            divider = constrain(divider, 1.0f, 255.9375f);
 
-        pwm_set_clkdiv(slice_num, divider);
-        pwm_set_wrap(slice_num, wrap);
+            pwm_set_clkdiv(slice_num, divider);
+            pwm_set_wrap(slice_num, wrap);
 
-        pwm_set_enabled(slice_num, true);
+            pwm_set_enabled(slice_num, true);
+        }
         data.channels.push_back(gpio);
         if (currentPwm == MAX_PWM_CHANNEL_INDEX){
             break;
         }
         currentPwm++;
     }
+    setupTach();
 }
 
 void writeOutputs(){
     if (data.channels.size() > 0) {
         for (int i =0 ; i< data.channels.size(); i++){
-            int value = map(data.values[0], 0, 100, 0, (1 << PWM_RESOLUTION) - 1);
+            if (identifying && data.channels[i] == OUTPUT_GPIO) continue;
+            if (data.output_mode[i] == OUTPUT_MODE_ON_OFF) {
+                gpio_put(data.channels[i], data.values[i] > 0);
+                continue;
+            }
+            int value = map(data.values[i], 0, MAX_PERMILLE_VALUE, 0, (1 << data.pwm_resolution[i]) - 1);
             pwm_set_gpio_level(data.channels[i], value);
         }
     }
 }
 
 void reloadOutputs(){
+    releaseTach();
     for (int gpio: data.port_config){
+        gpio_set_outover(gpio, GPIO_OVERRIDE_NORMAL);
         gpio_set_function(gpio, GPIO_FUNC_NULL);
     }
     reset_block_num(RESET_PWM);
@@ -91,11 +222,30 @@ void reloadOutputs(){
     setupOutputs();
 }
 
+void rebootToBootloader() {
+    Serial.flush();
+    delay(100); // lets the host read the reply before the port goes away
+    rp2040.rebootToBootloader();
+}
+
+void reboot() {
+    Serial.flush();
+    delay(100); // lets the host read the reply before the port goes away
+    rp2040.reboot();
+}
+
 JsonDocument getBoardInfo() {
     JsonDocument doc;
 
     doc[F("max_ports")] = MAX_PWM_CHANNEL_INDEX + 1;
     doc[F("board_name")] = BOARD_NAME;
+    doc[F("protocol_version")] = PROTOCOL_VERSION;
+    doc[F("firmware_version")] = FIRMWARE_VERSION;
+
+    char uid[2 * PICO_UNIQUE_BOARD_ID_SIZE_BYTES + 1];
+    pico_get_unique_board_id_string(uid, sizeof(uid));
+    doc[F("device_uid")] = uid;
+    fillCapabilities(doc[F("capabilities")].to<JsonArray>());
 
     return doc;
 }
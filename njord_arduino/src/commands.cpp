@@ -3,6 +3,7 @@
 
 Command stringToCommand(String cmd) {
     if (cmd == SET_VALUE_COMMAND) return SET_VALUE_CMD;
+    if (cmd == SET_VALUES_COMMAND) return SET_VALUES_CMD;
     if (cmd == PORTS_SETUP_COMMAND) return PORTS_SETUP_CMD;
     if (cmd == GET_VALUE_COMMAND) return GET_VALUE_CMD;
     if (cmd == GET_CONFIG_COMMAND) return GET_CONFIG_CMD;
@@ -13,5 +14,57 @@ Command stringToCommand(String cmd) {
     if (cmd == SET_UPDATE_TIME_COMMAND) return SET_UPDATE_TIME_CMD;
     if (cmd == PING_COMMAND) return PING_CMD;
     if (cmd == BOARD_INFO_COMMAND) return BOARD_INFO_CMD;
+    if (cmd == GET_RPM_COMMAND) return GET_RPM_CMD;
+    if (cmd == BOOTLOADER_COMMAND) return BOOTLOADER_CMD;
+    if (cmd == HEARTBEAT_COMMAND) return HEARTBEAT_CMD;
+    if (cmd == SET_HEARTBEAT_TIMEOUT_COMMAND) return SET_HEARTBEAT_TIMEOUT_CMD;
+    if (cmd == SET_VALUES_PERMILLE_COMMAND) return SET_VALUES_PERMILLE_CMD;
+    if (cmd == GET_VALUE_PERMILLE_COMMAND) return GET_VALUE_PERMILLE_CMD;
+    if (cmd == REBOOT_COMMAND) return REBOOT_CMD;
+    if (cmd == FACTORY_RESET_COMMAND) return FACTORY_RESET_CMD;
+    if (cmd == IDENTIFY_COMMAND) return IDENTIFY_CMD;
+    if (cmd == SET_TEMPERATURE_INPUTS_COMMAND) return SET_TEMPERATURE_INPUTS_CMD;
+    if (cmd == GET_TEMPERATURE_INPUTS_COMMAND) return GET_TEMPERATURE_INPUTS_CMD;
+    if (cmd == GET_TEMPERATURES_COMMAND) return GET_TEMPERATURES_CMD;
+    if (cmd == SET_STANDALONE_CURVES_COMMAND) return SET_STANDALONE_CURVES_CMD;
+    if (cmd == GET_STANDALONE_CURVES_COMMAND) return GET_STANDALONE_CURVES_CMD;
+    if (cmd == GET_CONTROL_MODE_COMMAND) return GET_CONTROL_MODE_CMD;
     return PING_CMD;
+}
+
+// the connection handshake and discovery probes shouldn't take a standalone board over,
+// a host keeps control with heartbeat or any other command
+bool isPassiveCommand(Command cmd) {
+    return cmd == PING_CMD || cmd == BOARD_INFO_CMD || cmd == GET_CONTROL_MODE_CMD;
+}
+
+void fillCapabilities(JsonArray capabilities) {
+    capabilities.add(SET_VALUE_COMMAND);
+    capabilities.add(SET_VALUES_COMMAND);
+    capabilities.add(PORTS_SETUP_COMMAND);
+    capabilities.add(GET_VALUE_COMMAND);
+    capabilities.add(GET_CONFIG_COMMAND);
+    capabilities.add(SET_CONFIG_COMMAND);
+    capabilities.add(GET_DEFAULT_CONFIG_COMMAND);
+    capabilities.add(LOAD_DEFAULT_CONFIG_COMMAND);
+    capabilities.add(SET_DEFAULT_VALUE_COMMAND);
+    capabilities.add(SET_UPDATE_TIME_COMMAND);
+    capabilities.add(BOARD_INFO_COMMAND);
+    capabilities.add(PING_COMMAND);
+    capabilities.add(GET_RPM_COMMAND);
+    capabilities.add(BOOTLOADER_COMMAND);
+    capabilities.add(HEARTBEAT_COMMAND);
+    capabilities.add(SET_HEARTBEAT_TIMEOUT_COMMAND);
+    capabilities.add(SET_VALUES_PERMILLE_COMMAND);
+    capabilities.add(GET_VALUE_PERMILLE_COMMAND);
+    capabilities.add(REBOOT_COMMAND);
+    capabilities.add(FACTORY_RESET_COMMAND);
+    capabilities.add(IDENTIFY_COMMAND);
+    capabilities.add(SET_TEMPERATURE_INPUTS_COMMAND);
+    capabilities.add(GET_TEMPERATURE_INPUTS_COMMAND);
+    capabilities.add(GET_TEMPERATURES_COMMAND);
+    capabilities.add(SET_STANDALONE_CURVES_COMMAND);
+    capabilities.add(GET_STANDALONE_CURVES_COMMAND);
+    capabilities.add(GET_CONTROL_MODE_COMMAND);
+    capabilities.add(PORT_SETTINGS_CAPABILITY);
 }
\ No newline at end of file
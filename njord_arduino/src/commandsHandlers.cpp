@@ -6,13 +6,39 @@
 #include <board.h>
 #include <messages.h>
 #include <errors.h>
+#include <saver.h>
+
+// every command counts as a sign of life from the host, not just heartbeat
+unsigned long lastHostContact = 0;
+bool hostSeen = false;
+bool inFailsafe = false;
+unsigned long controlModeSince = 0; // millis of the last switch between host and standalone control
+unsigned long lastStandaloneUpdate = 0;
+
+bool isStandalone(){
+    return !hostSeen || inFailsafe;
+}
 
 void handleCommand(){
-    switch (stringToCommand(command.getCom())) {
+    Command cmd = stringToCommand(command.getCom());
+    if (!isPassiveCommand(cmd)) {
+        if (isStandalone()) {
+            controlModeSince = millis();
+        }
+        lastHostContact = millis();
+        hostSeen = true;
+        inFailsafe = false;
+    }
+
+    switch (cmd) {
         case SET_VALUE_CMD:
             setValueHandler();
             break;
 
+        case SET_VALUES_CMD:
+            setValuesHandler();
+            break;
+
         case PORTS_SETUP_CMD:
             portsSetupHandler();
             break;
@@ -53,6 +79,66 @@ void handleCommand(){
             boardInfoHandler();
             break;
 
+        case GET_RPM_CMD:
+            getRpmHandler();
+            break;
+
+        case BOOTLOADER_CMD:
+            bootloaderHandler();
+            break;
+
+        case HEARTBEAT_CMD:
+            heartbeatHandler();
+            break;
+
+        case SET_HEARTBEAT_TIMEOUT_CMD:
+            setHeartbeatTimeoutHandler();
+            break;
+
+        case SET_VALUES_PERMILLE_CMD:
+            setValuesPermilleHandler();
+            break;
+
+        case GET_VALUE_PERMILLE_CMD:
+            getValuePermilleHandler();
+            break;
+
+        case REBOOT_CMD:
+            rebootHandler();
+            break;
+
+        case FACTORY_RESET_CMD:
+            factoryResetHandler();
+            break;
+
+        case IDENTIFY_CMD:
+            identifyHandler();
+            break;
+
+        case SET_TEMPERATURE_INPUTS_CMD:
+            setTemperatureInputsHandler();
+            break;
+
+        case GET_TEMPERATURE_INPUTS_CMD:
+            getTemperatureInputsHandler();
+            break;
+
+        case GET_TEMPERATURES_CMD:
+            getTemperaturesHandler();
+            break;
+
+        case SET_STANDALONE_CURVES_CMD:
+            setStandaloneCurvesHandler();
+            break;
+
+        case GET_STANDALONE_CURVES_CMD:
+            getStandaloneCurvesHandler();
+            break;
+
+        case GET_CONTROL_MODE_CMD:
+            getControlModeHandler();
+            break;
+
         default:
             pingHandler();
             break;
@@ -64,8 +150,8 @@ bool checkPortIndex(int port){
  return false;
 }
 
-bool checkValueInput(int value) {
-    if ( value >= 0 && value <= 100 ) return true;
+bool checkValueInput(int value, int maxValue = MAX_PERCENT_VALUE) {
+    if ( value >= 0 && value <= maxValue ) return true;
     return false;
 }
 
@@ -74,7 +160,7 @@ void setValueHandler(){
         int port = command[1].toInt();
         int value = command[2].toInt();
         if (checkPortIndex(port) && checkValueInput(value)){
-            data.values[port] = value;
+            data.values[port] = value * PERCENT_SCALE;
             sendStringResponse(OK_CODE, OK_CODE);
             return;
         }
@@ -84,15 +170,58 @@ void setValueHandler(){
     sendStringResponse(ERR_CODE, BAD_ARGS_COUNT);
 }
 
+// data is flat index/value pairs, every pair gets its own result
+void setValuePairs(int maxValue, int scale){
+    int length = command.getDataLength();
+    if (length == 0 || length % 2 != 0) {
+        sendStringResponse(ERR_CODE, BAD_ARGS_COUNT);
+        return;
+    }
+
+    JsonDocument doc;
+    JsonArray results = doc[F("results")].to<JsonArray>();
+    for (int i = 0; i < length; i += 2){
+        int port = command.getDataElement(i).toInt();
+        int value = command.getDataElement(i + 1).toInt();
+        if (checkPortIndex(port) && checkValueInput(value, maxValue)){
+            data.values[port] = value * scale;
+            results.add(OK_CODE);
+        } else {
+            results.add(BAD_ARGS);
+        }
+    }
+    sendDocResponse(OK_CODE, doc);
+}
+
+void setValuesHandler(){
+    setValuePairs(MAX_PERCENT_VALUE, PERCENT_SCALE);
+}
+
+void setValuesPermilleHandler(){
+    setValuePairs(MAX_PERMILLE_VALUE, 1);
+}
+
 void portsSetupHandler(){
     if (command.getDataLength() > 0){
         data.port_config.clear();
         data.default_values.clear();
         data.values.clear();
+        data.tach_inputs.clear();
+        data.pwm_frequency.clear();
+        data.pwm_resolution.clear();
+        data.inverted.clear();
+        data.output_mode.clear();
+        data.standalone_curves.clear();
         for (int i =0; i < command.getDataLength(); i++){
             data.port_config.push_back(command.getDataElement(i).toInt());
             data.default_values.push_back(INITIAL_VALUE);
             data.values.push_back(INITIAL_VALUE);
+            data.tach_inputs.push_back(NO_TACH_INPUT);
+            data.pwm_frequency.push_back(DEFAULT_PWM_FREQUENCY);
+            data.pwm_resolution.push_back(DEFAULT_PWM_RESOLUTION);
+            data.inverted.push_back(false);
+            data.output_mode.push_back(OUTPUT_MODE_PWM);
+            data.standalone_curves.push_back(StandaloneCurve());
         }
         data.dumpFile();
         sendStringResponse(OK_CODE, OK_CODE);
@@ -101,15 +230,23 @@ void portsSetupHandler(){
     sendStringResponse(ERR_CODE, BAD_ARGS_COUNT);
 }
 
-void getValueHandler(){
+void sendValues(int scale){
     JsonDocument doc;
     JsonArray arr = doc[F("values")].to<JsonArray>();
     for (int i : data.values){
-        arr.add(i);
+        arr.add((i + scale / 2) / scale);
     }
     sendDocResponse(OK_CODE, doc);
 }
 
+void getValueHandler(){
+    sendValues(PERCENT_SCALE);
+}
+
+void getValuePermilleHandler(){
+    sendValues(1);
+}
+
 void getConfigHandler(){
     sendDocResponse(OK_CODE, data.getJson());
 }
@@ -184,6 +321,205 @@ void setUpdateTimeHandler(){
 void boardInfoHandler(){
     sendDocResponse(OK_CODE, getBoardInfo());
 }
+void getRpmHandler(){
+    JsonDocument doc;
+    JsonArray arr = doc[F("rpm")].to<JsonArray>();
+    for (int i = 0; i < data.values.size(); i++){
+        int rpm = getPortRpm(i);
+        if (rpm < 0) {
+            arr.add(nullptr);
+        } else {
+            arr.add(rpm);
+        }
+    }
+    sendDocResponse(OK_CODE, doc);
+}
+
+void bootloaderHandler(){
+    sendStringResponse(OK_CODE, OK_CODE);
+    rebootToBootloader();
+}
+
+void rebootHandler(){
+    sendStringResponse(OK_CODE, OK_CODE);
+    reboot();
+}
+
+// Same as holding INPUT_GPIO at boot, the board comes back on DEFAULT_CONFIG
+void factoryResetHandler(){
+    resetStorage();
+    sendStringResponse(OK_CODE, OK_CODE);
+    reboot();
+}
+
+void identifyHandler(){
+    if (!command.hasIndex(1)){
+        sendStringResponse(ERR_CODE, BAD_ARGS_COUNT);
+        return;
+    }
+
+    int seconds = command[1].toInt();
+    if (seconds <= 0 || seconds > MAX_IDENTIFY_TIME){
+        sendStringResponse(ERR_CODE, BAD_ARGS);
+        return;
+    }
+
+    startIdentify(seconds);
+    sendStringResponse(OK_CODE, OK_CODE);
+}
+
+void setTemperatureInputsHandler(){
+    if (!command.hasIndex(1)) {
+        sendStringResponse(ERR_CODE, BAD_ARGS_COUNT);
+        return;
+    }
+
+    JsonDocument doc;
+    DeserializationError err = deserializeJson(doc, command[1]);
+    if (err.code() != DeserializationError::Ok || !doc.is<JsonArray>()){
+        sendStringResponse(ERR_CODE, BAD_ARGS);
+        return;
+    }
+
+    if (!data.loadTemperatureInputs(doc.as<JsonArray>())){
+        sendStringResponse(ERR_CODE, BAD_ARGS);
+        return;
+    }
+
+    data.dumpFile();
+    sendStringResponse(OK_CODE, OK_CODE);
+}
+
+void getTemperatureInputsHandler(){
+    JsonDocument doc;
+    data.fillTemperatureInputs(doc[F("temperature_inputs")].to<JsonArray>());
+    sendDocResponse(OK_CODE, doc);
+}
+
+void getTemperaturesHandler(){
+    JsonDocument doc;
+    JsonArray arr = doc[F("temperatures")].to<JsonArray>();
+    for (int i = 0; i < data.temperature_inputs.size(); i++){
+        float temperature = readTemperature(i);
+        if (isnan(temperature)) {
+            arr.add(nullptr);
+        } else {
+            arr.add(roundf(temperature * 10) / 10);
+        }
+    }
+    sendDocResponse(OK_CODE, doc);
+}
+
+void setStandaloneCurvesHandler(){
+    if (!command.hasIndex(1)) {
+        sendStringResponse(ERR_CODE, BAD_ARGS_COUNT);
+        return;
+    }
+
+    JsonDocument doc;
+    DeserializationError err = deserializeJson(doc, command[1]);
+    if (err.code() != DeserializationError::Ok || !doc.is<JsonArray>()){
+        sendStringResponse(ERR_CODE, BAD_ARGS);
+        return;
+    }
+
+    if (!data.loadStandaloneCurves(doc.as<JsonArray>())){
+        sendStringResponse(ERR_CODE, BAD_ARGS);
+        return;
+    }
+
+    data.dumpFile();
+    sendStringResponse(OK_CODE, OK_CODE);
+}
+
+void getStandaloneCurvesHandler(){
+    JsonDocument doc;
+    data.fillStandaloneCurves(doc[F("standalone_curves")].to<JsonArray>());
+    sendDocResponse(OK_CODE, doc);
+}
+
+// Passive, asking doesn't end standalone control
+void getControlModeHandler(){
+    JsonDocument doc;
+    doc[F("control_mode")] = isStandalone() ? F("standalone") : F("host");
+    doc[F("mode_time")] = millis() - controlModeSince;
+    sendDocResponse(OK_CODE, doc);
+}
+
+void heartbeatHandler(){
+    sendStringResponse(OK_CODE, OK_CODE);
+}
+
+void setHeartbeatTimeoutHandler(){
+    if (!command.hasIndex(1)){
+        sendStringResponse(ERR_CODE, BAD_ARGS_COUNT);
+        return;
+    }
+
+    int timeout = command[1].toInt();
+    if (timeout < 0){
+        sendStringResponse(ERR_CODE, BAD_ARGS);
+        return;
+    }
+
+    data.heartbeat_timeout = timeout;
+    data.dumpFile();
+    sendStringResponse(OK_CODE, OK_CODE);
+}
+
+// Nothing happens before the host showed up once, the board runs on default_values then anyway
+void checkFailsafe(){
+    if (data.heartbeat_timeout <= 0 || !hostSeen || inFailsafe) return;
+    if (millis() - lastHostContact < (unsigned long)data.heartbeat_timeout) return;
+
+    inFailsafe = true;
+    controlModeSince = millis();
+    for (int i = 0; i < data.values.size() && i < data.default_values.size(); i++){
+        data.values[i] = data.default_values[i] * PERCENT_SCALE;
+    }
+    sendStringResponse(INFO_CODE, FAILSAFE_MSG);
+}
+
+int findTemperatureInput(String name){
+    for (int i = 0; i < data.temperature_inputs.size(); i++){
+        if (data.temperature_inputs[i].name == name) return i;
+    }
+    return -1;
+}
+
+int evaluateCurve(StandaloneCurve &curve, float temp){
+    int last = curve.temps.size() - 1;
+    if (temp <= curve.temps[0]) return curve.values[0];
+    if (temp >= curve.temps[last]) return curve.values[last];
+
+    for (int i = 0; i < last; i++){
+        if (temp <= curve.temps[i + 1]) {
+            float t = (temp - curve.temps[i]) / (curve.temps[i + 1] - curve.temps[i]);
+            return roundf(curve.values[i] + t * (curve.values[i + 1] - curve.values[i]));
+        }
+    }
+    return curve.values[last];
+}
+
+// Ports whose input can't be read fall back to their default value until it can
+void standaloneLoop(){
+    if (!isStandalone()) return;
+    if (millis() - lastStandaloneUpdate < STANDALONE_UPDATE_TIME) return;
+    lastStandaloneUpdate = millis();
+
+    for (int port = 0; port < data.standalone_curves.size() && port < data.values.size(); port++){
+        StandaloneCurve &curve = data.standalone_curves[port];
+        if (!curve.enabled) continue;
+
+        float temp = readTemperature(findTemperatureInput(curve.temperature_input));
+        if (isnan(temp)) {
+            data.values[port] = data.default_values[port] * PERCENT_SCALE;
+            continue;
+        }
+        data.values[port] = constrain(evaluateCurve(curve, temp), curve.min_value, curve.max_value);
+    }
+}
+
 void pingHandler(){
     sendStringResponse(OK_CODE, PONG_MSG);
 }
\ No newline at end of file
@@ -24,5 +24,7 @@ void loop() {
     handleCommand();
     command.clear();
   }
+  checkFailsafe();
+  standaloneLoop();
   boardLoop();
 }
\ No newline at end of file
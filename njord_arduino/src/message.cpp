@@ -8,6 +8,9 @@ void sendStringResponse(String code, String message) {
 
     doc[F("code")] = code;
     doc[F("message")] = message;
+    if (command.hasId()) {
+        doc[F("id")] = command.getId();
+    }
 
     String res;
     serializeJson(doc, res);
@@ -20,6 +23,9 @@ void sendDocResponse(String code, JsonDocument data) {
 
     doc[F("code")] = code;
     doc[F("data")] = data;
+    if (command.hasId()) {
+        doc[F("id")] = command.getId();
+    }
 
     String res;
     serializeJson(doc, res);
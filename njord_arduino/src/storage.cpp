@@ -21,11 +21,206 @@ bool GlobalStorage::loadFile(){
     return loadJson(doc);
 }
 
+int parseOutputMode(String mode){
+    if (mode == F("pwm")) return OUTPUT_MODE_PWM;
+    if (mode == F("on_off")) return OUTPUT_MODE_ON_OFF;
+    return -1;
+}
+
+bool validPortSettings(JsonArray settings, int portsCount){
+    if (settings.size() > portsCount) {
+        return false;
+    }
+    for (JsonObject port : settings){
+        int mode = parseOutputMode(port[F("output_mode")] | "pwm");
+        int frequency = port[F("pwm_frequency")] | DEFAULT_PWM_FREQUENCY;
+        int resolution = port[F("pwm_resolution")] | DEFAULT_PWM_RESOLUTION;
+
+        if (mode < 0) {
+            return false;
+        }
+        if (mode == OUTPUT_MODE_PWM && (frequency < MIN_PWM_FREQUENCY || frequency > MAX_PWM_FREQUENCY
+            || resolution < 1 || resolution > MAX_PWM_RESOLUTION)) {
+            return false;
+        }
+    }
+    return true;
+}
+
+bool parseTemperatureInput(JsonObject object, TemperatureInput &input){
+    input.name = object[F("name")] | "";
+    if (input.name.length() == 0) {
+        return false;
+    }
+
+    String source = object[F("source")] | "";
+    if (source == F("internal")) {
+        input.gpio = INTERNAL_TEMPERATURE_GPIO;
+        input.series_resistance = 0;
+        input.model = THERMISTOR_MODEL_BETA;
+        input.params[0] = input.params[1] = input.params[2] = 0;
+        return true;
+    }
+    if (source != F("thermistor")) {
+        return false;
+    }
+
+    input.gpio = object[F("gpio")] | -1;
+    input.series_resistance = object[F("series_resistance")] | 0.0f;
+    if (input.gpio < A0 || input.gpio > A2 || input.series_resistance <= 0) {
+        return false;
+    }
+
+    JsonObject model = object[F("model")];
+    String kind = model[F("kind")] | "";
+    if (kind == F("beta")) {
+        input.model = THERMISTOR_MODEL_BETA;
+        input.params[0] = model[F("beta")] | 0.0f;
+        input.params[1] = model[F("nominal_resistance")] | 0.0f;
+        input.params[2] = model[F("nominal_temperature")] | 25.0f;
+        return input.params[0] > 0 && input.params[1] > 0;
+    }
+    if (kind == F("steinhart_hart")) {
+        input.model = THERMISTOR_MODEL_STEINHART_HART;
+        input.params[0] = model[F("a")] | 0.0f;
+        input.params[1] = model[F("b")] | 0.0f;
+        input.params[2] = model[F("c")] | 0.0f;
+        return true;
+    }
+    return false;
+}
+
+// All or nothing, the stored inputs stay as they were when one of them is bad
+bool GlobalStorage::loadTemperatureInputs(JsonArray inputs){
+    if (inputs.size() > MAX_TEMPERATURE_INPUTS) {
+        return false;
+    }
+
+    std::vector<TemperatureInput> parsed;
+    for (JsonObject object : inputs){
+        TemperatureInput input;
+        if (!parseTemperatureInput(object, input)) {
+            return false;
+        }
+        parsed.push_back(input);
+    }
+    temperature_inputs = parsed;
+    return true;
+}
+
+void GlobalStorage::fillTemperatureInputs(JsonArray inputs){
+    for (TemperatureInput &input : temperature_inputs){
+        JsonObject object = inputs.add<JsonObject>();
+        object[F("name")] = input.name;
+        if (input.gpio == INTERNAL_TEMPERATURE_GPIO) {
+            object[F("source")] = F("internal");
+            continue;
+        }
+        object[F("source")] = F("thermistor");
+        object[F("gpio")] = input.gpio;
+        object[F("series_resistance")] = input.series_resistance;
+
+        JsonObject model = object[F("model")].to<JsonObject>();
+        if (input.model == THERMISTOR_MODEL_BETA) {
+            model[F("kind")] = F("beta");
+            model[F("beta")] = input.params[0];
+            model[F("nominal_resistance")] = input.params[1];
+            model[F("nominal_temperature")] = input.params[2];
+        } else {
+            model[F("kind")] = F("steinhart_hart");
+            model[F("a")] = input.params[0];
+            model[F("b")] = input.params[1];
+            model[F("c")] = input.params[2];
+        }
+    }
+}
+
+bool hasTemperatureInput(std::vector<TemperatureInput> &inputs, String name){
+    for (TemperatureInput &input : inputs){
+        if (input.name == name) return true;
+    }
+    return false;
+}
+
+bool parseStandaloneCurve(JsonObject object, StandaloneCurve &curve, std::vector<TemperatureInput> &inputs){
+    curve.enabled = true;
+    curve.temperature_input = object[F("temperature_input")] | "";
+    curve.min_value = object[F("min_value")] | 0;
+    curve.max_value = object[F("max_value")] | MAX_PERMILLE_VALUE;
+    if (!hasTemperatureInput(inputs, curve.temperature_input)) {
+        return false;
+    }
+    if (curve.min_value < 0 || curve.max_value > MAX_PERMILLE_VALUE || curve.min_value > curve.max_value) {
+        return false;
+    }
+
+    JsonArray points = object[F("curve")].as<JsonArray>();
+    if (points.size() == 0 || points.size() > MAX_CURVE_POINTS) {
+        return false;
+    }
+    for (JsonObject point : points){
+        float temp = point[F("temp")] | 0.0f;
+        int value = point[F("value")] | -1;
+        if (value < 0 || value > MAX_PERMILLE_VALUE || (curve.temps.size() > 0 && temp <= curve.temps.back())) {
+            return false;
+        }
+        curve.temps.push_back(temp);
+        curve.values.push_back(value);
+    }
+    return true;
+}
+
+// All or nothing like the temperature inputs, null entries leave the port on default_values
+bool GlobalStorage::loadStandaloneCurves(JsonArray curves){
+    if (curves.size() > port_config.size()) {
+        return false;
+    }
+
+    std::vector<StandaloneCurve> parsed(port_config.size());
+    for (int port = 0; port < curves.size(); port++){
+        if (curves[port].isNull()) continue;
+        if (!parseStandaloneCurve(curves[port].as<JsonObject>(), parsed[port], temperature_inputs)) {
+            return false;
+        }
+    }
+    standalone_curves = parsed;
+    return true;
+}
+
+void GlobalStorage::fillStandaloneCurves(JsonArray curves){
+    for (StandaloneCurve &curve : standalone_curves){
+        if (!curve.enabled) {
+            curves.add(nullptr);
+            continue;
+        }
+        JsonObject object = curves.add<JsonObject>();
+        object[F("temperature_input")] = curve.temperature_input;
+        JsonArray points = object[F("curve")].to<JsonArray>();
+        for (int i = 0; i < curve.temps.size(); i++){
+            JsonObject point = points.add<JsonObject>();
+            point[F("temp")] = curve.temps[i];
+            point[F("value")] = curve.values[i];
+        }
+        object[F("min_value")] = curve.min_value;
+        object[F("max_value")] = curve.max_value;
+    }
+}
+
 bool GlobalStorage::loadJson(JsonDocument doc){
     if(!doc.containsKey(F("ports")) && !doc.containsKey(F("update_time"))){
       return false;
     }
 
+    JsonArray settings = doc[F("port_settings")].as<JsonArray>();
+    if (!validPortSettings(settings, doc[F("ports")].size())) {
+        return false;
+    }
+
+    // only the stored file carries them, configs from the host don't
+    if (doc.containsKey(F("temperature_inputs")) && !loadTemperatureInputs(doc[F("temperature_inputs")].as<JsonArray>())) {
+        return false;
+    }
+
     port_config.clear();
     default_values.clear();
     values.clear();
@@ -48,10 +243,35 @@ bool GlobalStorage::loadJson(JsonDocument doc){
     }
 
     for (int i : default_values){
-        values.push_back(i);
+        values.push_back(i * PERCENT_SCALE);
+    }
+
+    tach_inputs.clear();
+    for (JsonVariant pin : doc[F("tach_inputs")].as<JsonArray>()){
+        tach_inputs.push_back(pin.isNull() ? NO_TACH_INPUT : pin.as<int>());
+    }
+    tach_inputs.resize(port_config.size(), NO_TACH_INPUT);
+
+    pwm_frequency.assign(port_config.size(), DEFAULT_PWM_FREQUENCY);
+    pwm_resolution.assign(port_config.size(), DEFAULT_PWM_RESOLUTION);
+    inverted.assign(port_config.size(), false);
+    output_mode.assign(port_config.size(), OUTPUT_MODE_PWM);
+
+    for (int i = 0; i < settings.size(); i++){
+        JsonObject port = settings[i];
+        pwm_frequency[i] = port[F("pwm_frequency")] | DEFAULT_PWM_FREQUENCY;
+        pwm_resolution[i] = port[F("pwm_resolution")] | DEFAULT_PWM_RESOLUTION;
+        inverted[i] = port[F("inverted")] | false;
+        output_mode[i] = parseOutputMode(port[F("output_mode")] | "pwm");
     }
 
     update_time = doc[F("update_time")];
+    heartbeat_timeout = doc[F("heartbeat_timeout")] | 0;
+
+    // the stored file carries them, a config from the host keeps the ones that still have a port
+    if (!doc.containsKey(F("standalone_curves")) || !loadStandaloneCurves(doc[F("standalone_curves")].as<JsonArray>())) {
+        standalone_curves.resize(port_config.size());
+    }
     return true;  
 }
 
@@ -74,6 +294,28 @@ JsonDocument GlobalStorage::getJson(){
     }
 
     doc["update_time"] = update_time;
+    doc[F("heartbeat_timeout")] = heartbeat_timeout;
+
+    JsonArray tach_inputs_arr = doc[F("tach_inputs")].to<JsonArray>();
+    for (int pin : tach_inputs){
+        if (pin == NO_TACH_INPUT) {
+            tach_inputs_arr.add(nullptr);
+        } else {
+            tach_inputs_arr.add(pin);
+        }
+    }
+
+    JsonArray settings = doc[F("port_settings")].to<JsonArray>();
+    for (int i = 0; i < port_config.size(); i++){
+        JsonObject port = settings.add<JsonObject>();
+        port[F("pwm_frequency")] = pwm_frequency[i];
+        port[F("pwm_resolution")] = pwm_resolution[i];
+        port[F("inverted")] = (bool)inverted[i];
+        port[F("output_mode")] = output_mode[i] == OUTPUT_MODE_ON_OFF ? F("on_off") : F("pwm");
+    }
+
+    fillTemperatureInputs(doc[F("temperature_inputs")].to<JsonArray>());
+    fillStandaloneCurves(doc[F("standalone_curves")].to<JsonArray>());
     
     return doc;
 }
@@ -109,9 +351,21 @@ int CommandStorage::getDataLength() {
     return data.size();
 }
 
+bool CommandStorage::hasId() {
+    return id >= 0;
+}
+
+long CommandStorage::getId() {
+    return id;
+}
+
 bool CommandStorage::setFromJson(JsonDocument doc) {
     if (doc.containsKey(F("command"))) {
         setCom(doc[F("command")].as<String>());
+
+        if (doc.containsKey(F("id"))) {
+            id = doc[F("id")].as<long>();
+        }
         
         if (doc.containsKey(F("data"))) {
             JsonArray arr = doc[F("data")].as<JsonArray>();
@@ -128,6 +382,7 @@ bool CommandStorage::setFromJson(JsonDocument doc) {
 void CommandStorage::clear() {
     com.reserve(0);
     data.clear();
+    id = -1;
 }
 
 String CommandStorage::operator[] (int index) {
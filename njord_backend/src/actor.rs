@@ -0,0 +1,364 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use crate::board::{BoardProfile, ConfigReport};
+use crate::device::{
+    ControlStatus, Device, DeviceConfig, DeviceError, DeviceEvent, DeviceInfo, DeviceState, DeviceSummary, PortValue,
+    StandaloneCurve, TemperatureInput, TemperatureReading,
+};
+use crate::discovery::DiscoveredDevice;
+use crate::firmware::{FirmwareUpdateOptions, update_firmware};
+use crate::reconnect::ReconnectPolicy;
+
+const CONTROL_QUEUE_SIZE: usize = 16;
+const QUERY_QUEUE_SIZE: usize = 16;
+
+type Reply<R> = oneshot::Sender<Result<R, DeviceError>>;
+
+// What the device looked like after the last request, readable without waiting for the actor
+#[derive(Clone)]
+pub struct DeviceSnapshot {
+    pub summary: DeviceSummary,
+    pub state: DeviceState,
+    pub reconnect_policy: ReconnectPolicy,
+    pub board_profile: BoardProfile,
+    pub temperatures: Option<TemperatureReading>,
+}
+
+impl DeviceSnapshot {
+    fn of(device: &Device) -> Self {
+        Self {
+            summary: device.create_summary(),
+            state: device.device_state.clone(),
+            reconnect_policy: device.reconnect_policy().clone(),
+            board_profile: device.board_profile(),
+            temperatures: device.last_temperatures().cloned(),
+        }
+    }
+}
+
+enum Priority {
+    Control, // anything that changes what the board does
+    Query,   // reads, mostly for the UI
+}
+
+enum Request {
+    FetchData(Reply<()>),
+    OpenConnection(Reply<()>),
+    TestConnection { timeout: Duration, interval: Duration, reply: Reply<bool> },
+    SetState(DeviceState, Reply<()>),
+    SetReconnectPolicy(ReconnectPolicy, Reply<()>),
+    SetTrace(Option<PathBuf>, Reply<()>),
+    SetDeviceConfig(DeviceConfig, Reply<()>),
+    SetPlugValues(Vec<(u8, PortValue)>, Reply<Vec<Result<(), DeviceError>>>),
+    Heartbeat(Reply<()>),
+    Reboot(Reply<()>),
+    FactoryReset(Reply<()>),
+    Identify(Duration, Reply<()>),
+    SetTemperatureInputs(Vec<TemperatureInput>, Reply<()>),
+    SetStandaloneCurves(Vec<Option<StandaloneCurve>>, Reply<()>),
+    UpdateFirmware { image: PathBuf, options: FirmwareUpdateOptions, reply: Reply<DiscoveredDevice> },
+    GetBoardInfo(Reply<DeviceInfo>),
+    GetPlugsValues(Reply<Vec<PortValue>>),
+    GetDeviceConfig(Reply<DeviceConfig>),
+    GetDeviceDefaultConfig(Reply<DeviceConfig>),
+    GetRpm(Reply<Vec<Option<u32>>>),
+    GetTemperatures(Reply<Vec<Option<f32>>>),
+    GetStandaloneCurves(Reply<Vec<Option<StandaloneCurve>>>),
+    VerifyStandaloneCurves(Vec<Option<StandaloneCurve>>, Reply<bool>),
+    GetControlStatus(Reply<ControlStatus>),
+}
+
+impl Request {
+    fn priority(&self) -> Priority {
+        match self {
+            Request::GetBoardInfo(_)
+            | Request::GetPlugsValues(_)
+            | Request::GetDeviceConfig(_)
+            | Request::GetDeviceDefaultConfig(_)
+            | Request::GetRpm(_)
+            | Request::GetTemperatures(_)
+            | Request::GetStandaloneCurves(_)
+            | Request::VerifyStandaloneCurves(..)
+            | Request::GetControlStatus(_)
+            | Request::TestConnection { .. } => Priority::Query,
+            _ => Priority::Control,
+        }
+    }
+
+    // Requests whose caller went away are dropped without touching the board
+    async fn handle(self, device: &mut Device, snapshot: &watch::Sender<DeviceSnapshot>) {
+        macro_rules! respond {
+            ($reply:expr, $result:expr) => {
+                if !$reply.is_closed() {
+                    let result = $result;
+                    snapshot.send_replace(DeviceSnapshot::of(device));
+                    let _ = $reply.send(result);
+                }
+            };
+        }
+
+        match self {
+            Request::FetchData(reply) => respond!(reply, device.fetch_data().await),
+            Request::OpenConnection(reply) => respond!(reply, device.open_connection().await),
+            Request::TestConnection { timeout, interval, reply } => {
+                respond!(reply, Ok(device.test_connection(timeout, interval).await))
+            }
+            Request::SetState(state, reply) => respond!(reply, {
+                if let DeviceState::Offline = state {
+                    device.close_connection();
+                }
+                device.device_state = state;
+                Ok(())
+            }),
+            Request::SetReconnectPolicy(policy, reply) => respond!(reply, {
+                device.set_reconnect_policy(policy);
+                Ok(())
+            }),
+            Request::SetTrace(path, reply) => respond!(reply, match path {
+                Some(path) => device.start_trace(&path),
+                None => {
+                    device.stop_trace();
+                    Ok(())
+                }
+            }),
+            Request::SetDeviceConfig(config, reply) => respond!(reply, device.set_device_config(&config).await),
+            Request::SetPlugValues(values, reply) => respond!(reply, device.set_plug_values(&values).await),
+            Request::Heartbeat(reply) => respond!(reply, device.heartbeat().await),
+            Request::Reboot(reply) => respond!(reply, device.reboot().await),
+            Request::FactoryReset(reply) => respond!(reply, device.factory_reset().await),
+            Request::Identify(duration, reply) => respond!(reply, device.identify(duration).await),
+            Request::SetTemperatureInputs(inputs, reply) => {
+                respond!(reply, device.set_temperature_inputs(&inputs).await)
+            }
+            Request::SetStandaloneCurves(curves, reply) => {
+                respond!(reply, device.set_standalone_curves(&curves).await)
+            }
+            Request::UpdateFirmware { image, options, reply } => {
+                respond!(reply, update_firmware(device, &image, &options).await)
+            }
+            Request::GetBoardInfo(reply) => respond!(reply, device.get_board_info().await),
+            Request::GetPlugsValues(reply) => respond!(reply, device.get_plugs_values().await),
+            Request::GetDeviceConfig(reply) => respond!(reply, device.get_device_config().await),
+            Request::GetDeviceDefaultConfig(reply) => respond!(reply, device.get_device_default_config().await),
+            Request::GetRpm(reply) => respond!(reply, device.get_rpm().await),
+            Request::GetTemperatures(reply) => respond!(reply, device.get_temperatures().await),
+            Request::GetStandaloneCurves(reply) => respond!(reply, device.get_standalone_curves().await),
+            Request::VerifyStandaloneCurves(curves, reply) => {
+                respond!(reply, device.verify_standalone_curves(&curves).await)
+            }
+            Request::GetControlStatus(reply) => respond!(reply, device.get_control_status().await),
+        }
+    }
+}
+
+// Owns the device, so slow serial I/O only ever blocks the actor. Control requests go first,
+// the actor stops and closes the port once every handle is dropped
+async fn run(
+    mut device: Device,
+    mut control: mpsc::Receiver<Request>,
+    mut query: mpsc::Receiver<Request>,
+    snapshot: watch::Sender<DeviceSnapshot>,
+) {
+    loop {
+        let request = tokio::select! {
+            biased;
+            Some(request) = control.recv() => request,
+            Some(request) = query.recv() => request,
+            else => break,
+        };
+        request.handle(&mut device, &snapshot).await;
+    }
+    device.close_connection();
+}
+
+#[derive(Clone)]
+pub struct DeviceHandle {
+    control: mpsc::Sender<Request>,
+    query: mpsc::Sender<Request>,
+    snapshot: watch::Receiver<DeviceSnapshot>,
+    events: broadcast::Sender<DeviceEvent>,
+}
+
+impl DeviceHandle {
+    pub fn spawn(device: Device) -> Self {
+        let (control, control_receiver) = mpsc::channel(CONTROL_QUEUE_SIZE);
+        let (query, query_receiver) = mpsc::channel(QUERY_QUEUE_SIZE);
+        let (snapshot_sender, snapshot) = watch::channel(DeviceSnapshot::of(&device));
+        let events = device.event_sender();
+
+        tokio::spawn(run(device, control_receiver, query_receiver, snapshot_sender));
+
+        Self {
+            control,
+            query,
+            snapshot,
+            events,
+        }
+    }
+
+    async fn call<R>(&self, request: impl FnOnce(Reply<R>) -> Request) -> Result<R, DeviceError> {
+        let (reply, response) = oneshot::channel();
+        let request = request(reply);
+        let queue = match request.priority() {
+            Priority::Control => &self.control,
+            Priority::Query => &self.query,
+        };
+        queue.send(request).await.map_err(|_| actor_stopped())?;
+        response.await.map_err(|_| actor_stopped())?
+    }
+
+    pub fn snapshot(&self) -> DeviceSnapshot {
+        self.snapshot.borrow().clone()
+    }
+
+    pub fn create_summary(&self) -> DeviceSummary {
+        self.snapshot.borrow().summary.clone()
+    }
+
+    pub fn device_state(&self) -> DeviceState {
+        self.snapshot.borrow().state.clone()
+    }
+
+    pub fn device_config(&self) -> DeviceConfig {
+        self.snapshot.borrow().summary.device_config.clone()
+    }
+
+    // Checked against the last known board info, nothing is sent to the board
+    pub fn validate_config(&self, config: &DeviceConfig) -> ConfigReport {
+        let snapshot = self.snapshot.borrow();
+        let summary = &snapshot.summary;
+        snapshot.board_profile.validate_all(
+            &summary.device_info,
+            config,
+            &summary.temperature_inputs,
+            &summary.standalone_curves,
+        )
+    }
+
+    pub fn board_profile(&self) -> BoardProfile {
+        self.snapshot.borrow().board_profile.clone()
+    }
+
+    pub fn subscribe_events(&self) -> broadcast::Receiver<DeviceEvent> {
+        self.events.subscribe()
+    }
+
+    // Follows the device without keeping a handle, the actor still stops once every handle is dropped
+    pub fn subscribe_snapshot(&self) -> watch::Receiver<DeviceSnapshot> {
+        self.snapshot.clone()
+    }
+
+    pub async fn fetch_data(&self) -> Result<(), DeviceError> {
+        self.call(Request::FetchData).await
+    }
+
+    pub async fn open_connection(&self) -> Result<(), DeviceError> {
+        self.call(Request::OpenConnection).await
+    }
+
+    pub async fn test_connection(&self, timeout: Duration, interval: Duration) -> bool {
+        self.call(|reply| Request::TestConnection { timeout, interval, reply })
+            .await
+            .unwrap_or(false)
+    }
+
+    pub async fn set_device_state(&self, state: DeviceState) -> Result<(), DeviceError> {
+        self.call(|reply| Request::SetState(state, reply)).await
+    }
+
+    pub async fn set_reconnect_policy(&self, policy: ReconnectPolicy) -> Result<(), DeviceError> {
+        self.call(|reply| Request::SetReconnectPolicy(policy, reply)).await
+    }
+
+    // None stops a running trace
+    pub async fn set_trace(&self, path: Option<PathBuf>) -> Result<(), DeviceError> {
+        self.call(|reply| Request::SetTrace(path, reply)).await
+    }
+
+    pub async fn set_device_config(&self, config: DeviceConfig) -> Result<(), DeviceError> {
+        self.call(|reply| Request::SetDeviceConfig(config, reply)).await
+    }
+
+    pub async fn set_plug_values(
+        &self,
+        values: Vec<(u8, PortValue)>,
+    ) -> Result<Vec<Result<(), DeviceError>>, DeviceError> {
+        self.call(|reply| Request::SetPlugValues(values, reply)).await
+    }
+
+    pub async fn heartbeat(&self) -> Result<(), DeviceError> {
+        self.call(Request::Heartbeat).await
+    }
+
+    // Both wait until the board answers again and its data is fetched anew
+    pub async fn reboot(&self) -> Result<(), DeviceError> {
+        self.call(Request::Reboot).await
+    }
+
+    pub async fn factory_reset(&self) -> Result<(), DeviceError> {
+        self.call(Request::FactoryReset).await
+    }
+
+    pub async fn identify(&self, duration: Duration) -> Result<(), DeviceError> {
+        self.call(|reply| Request::Identify(duration, reply)).await
+    }
+
+    pub async fn set_temperature_inputs(&self, inputs: Vec<TemperatureInput>) -> Result<(), DeviceError> {
+        self.call(|reply| Request::SetTemperatureInputs(inputs, reply)).await
+    }
+
+    // Read back and compared before it returns, a board that stored something else is an error
+    pub async fn set_standalone_curves(&self, curves: Vec<Option<StandaloneCurve>>) -> Result<(), DeviceError> {
+        self.call(|reply| Request::SetStandaloneCurves(curves, reply)).await
+    }
+
+    pub async fn update_firmware(
+        &self,
+        image: PathBuf,
+        options: FirmwareUpdateOptions,
+    ) -> Result<DiscoveredDevice, DeviceError> {
+        self.call(|reply| Request::UpdateFirmware { image, options, reply }).await
+    }
+
+    pub async fn get_board_info(&self) -> Result<DeviceInfo, DeviceError> {
+        self.call(Request::GetBoardInfo).await
+    }
+
+    pub async fn get_plugs_values(&self) -> Result<Vec<PortValue>, DeviceError> {
+        self.call(Request::GetPlugsValues).await
+    }
+
+    pub async fn get_device_config(&self) -> Result<DeviceConfig, DeviceError> {
+        self.call(Request::GetDeviceConfig).await
+    }
+
+    pub async fn get_device_default_config(&self) -> Result<DeviceConfig, DeviceError> {
+        self.call(Request::GetDeviceDefaultConfig).await
+    }
+
+    pub async fn get_rpm(&self) -> Result<Vec<Option<u32>>, DeviceError> {
+        self.call(Request::GetRpm).await
+    }
+
+    pub async fn get_temperatures(&self) -> Result<Vec<Option<f32>>, DeviceError> {
+        self.call(Request::GetTemperatures).await
+    }
+
+    pub async fn get_standalone_curves(&self) -> Result<Vec<Option<StandaloneCurve>>, DeviceError> {
+        self.call(Request::GetStandaloneCurves).await
+    }
+
+    pub async fn verify_standalone_curves(&self, curves: Vec<Option<StandaloneCurve>>) -> Result<bool, DeviceError> {
+        self.call(|reply| Request::VerifyStandaloneCurves(curves, reply)).await
+    }
+
+    // Doesn't take a standalone board over, unlike every other command
+    pub async fn get_control_status(&self) -> Result<ControlStatus, DeviceError> {
+        self.call(Request::GetControlStatus).await
+    }
+}
+
+fn actor_stopped() -> DeviceError {
+    DeviceError::Disconnected("Device actor has stopped".to_string())
+}
@@ -0,0 +1,744 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::discovery::DEFAULT_BAUD_RATE;
+use crate::device::{
+    DeviceConfig, DeviceError, DeviceInfo, MAX_PORT_VALUE, MAX_PWM_FREQUENCY, MAX_PWM_RESOLUTION,
+    MAX_STANDALONE_CURVE_POINTS, MAX_TEMPERATURE_INPUTS, MIN_PWM_FREQUENCY, OutputMode, PortSettings, PortValue,
+    StandaloneCurve, TemperatureInput, TemperatureSource,
+};
+
+const MAX_DEFAULT_VALUE: u8 = 100;
+
+// How GPIOs map onto the PWM hardware, decides which ports can't run together
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PwmLayout {
+    Any,    // every GPIO has a PWM output of its own
+    Rp2040, // 8 slices with 2 channels each, a slice shares divider and wrap
+}
+
+// Fields left out of a profile file fall back to the generic profile
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BoardProfile {
+    pub name: String,
+    pub board_names: Vec<String>, // what `board_info` reports for this board
+    pub gpios: Vec<u8>,           // usable as output or tach input, empty allows any
+    pub led_gpio: Option<u8>,
+    pub reset_button_gpio: Option<u8>, // held at boot to wipe the storage, never usable
+    pub default_baud_rate: u32,
+    pub max_ports: Option<u8>, // None trusts `board_info`
+    pub pwm_layout: PwmLayout,
+    pub max_pwm_frequency: u32,
+    pub max_pwm_resolution: u8,
+    pub adc_gpios: Vec<u8>, // usable by thermistors, empty allows any
+    pub internal_temperature: bool,
+}
+
+impl Default for BoardProfile {
+    fn default() -> Self {
+        Self::generic()
+    }
+}
+
+impl BoardProfile {
+    // Pico pinout: 23/24/29 are wired on the board
+    pub fn rp2040() -> Self {
+        Self {
+            name: "rp2040".to_string(),
+            board_names: vec![
+                "RASPBERRY_PI_PICO".to_string(),
+                "pico4mb".to_string(),
+                "njord_emulator".to_string(),
+            ],
+            gpios: (1..=22).chain(25..=28).collect(),
+            led_gpio: Some(25),
+            reset_button_gpio: Some(0),
+            default_baud_rate: 115200,
+            max_ports: Some(16),
+            pwm_layout: PwmLayout::Rp2040,
+            max_pwm_frequency: MAX_PWM_FREQUENCY,
+            max_pwm_resolution: MAX_PWM_RESOLUTION,
+            adc_gpios: vec![26, 27, 28],
+            internal_temperature: true,
+        }
+    }
+
+    // DevKitM-1: 18/19 are the USB lines, 20/21 the UART, LEDC has 6 channels
+    pub fn esp32c3() -> Self {
+        Self {
+            name: "esp32c3".to_string(),
+            board_names: vec!["ESP32C3_DEV".to_string(), "esp32-c3-devkitm-1".to_string()],
+            gpios: (0..=7).chain([10]).collect(),
+            led_gpio: Some(8),
+            reset_button_gpio: Some(9),
+            default_baud_rate: 115200,
+            max_ports: Some(6),
+            pwm_layout: PwmLayout::Any,
+            max_pwm_frequency: 40_000,
+            max_pwm_resolution: 14,
+            adc_gpios: (0..=4).collect(),
+            internal_temperature: true,
+        }
+    }
+
+    // Boards nobody described yet, only what `board_info` tells is checked
+    pub fn generic() -> Self {
+        Self {
+            name: "generic".to_string(),
+            board_names: Vec::new(),
+            gpios: Vec::new(),
+            led_gpio: None,
+            reset_button_gpio: None,
+            default_baud_rate: DEFAULT_BAUD_RATE,
+            max_ports: None,
+            pwm_layout: PwmLayout::Any,
+            max_pwm_frequency: MAX_PWM_FREQUENCY,
+            max_pwm_resolution: MAX_PWM_RESOLUTION,
+            adc_gpios: Vec::new(),
+            internal_temperature: true,
+        }
+    }
+
+    pub fn matches(&self, board_name: &str) -> bool {
+        self.board_names.iter().any(|name| name.eq_ignore_ascii_case(board_name))
+    }
+
+    pub fn allows_gpio(&self, gpio: u8) -> bool {
+        self.reset_button_gpio != Some(gpio) && (self.gpios.is_empty() || self.gpios.contains(&gpio))
+    }
+
+    pub fn allows_adc_gpio(&self, gpio: u8) -> bool {
+        self.allows_gpio(gpio) && (self.adc_gpios.is_empty() || self.adc_gpios.contains(&gpio))
+    }
+
+    fn max_pwm_frequency(&self) -> u32 {
+        self.max_pwm_frequency.min(MAX_PWM_FREQUENCY)
+    }
+
+    fn max_pwm_resolution(&self) -> u8 {
+        self.max_pwm_resolution.min(MAX_PWM_RESOLUTION)
+    }
+
+    // (slice, channel) driving the GPIO, None when the board gives every GPIO its own output
+    pub fn pwm_channel(&self, gpio: u8) -> Option<(u8, u8)> {
+        match self.pwm_layout {
+            PwmLayout::Any => None,
+            PwmLayout::Rp2040 => Some(((gpio >> 1) & 7, gpio & 1)),
+        }
+    }
+
+    fn max_ports(&self, device_info: &DeviceInfo) -> Option<u8> {
+        let reported = Some(device_info.max_ports).filter(|&max_ports| max_ports > 0);
+        match (self.max_ports, reported) {
+            (Some(profile), Some(reported)) => Some(profile.min(reported)),
+            (profile, reported) => profile.or(reported),
+        }
+    }
+
+    // Collects every problem instead of stopping at the first, the GUI shows them all at once
+    pub fn validate(&self, device_info: &DeviceInfo, config: &DeviceConfig) -> ConfigReport {
+        let mut problems = Vec::new();
+        let ports = &config.ports;
+
+        if let Some(max_ports) = self.max_ports(device_info)
+            && ports.len() > max_ports as usize
+        {
+            problems.push(ConfigProblem::TooManyPorts { ports: ports.len(), max_ports });
+        }
+        if config.default_values.len() != ports.len() {
+            problems.push(ConfigProblem::DefaultValuesLength {
+                ports: ports.len(),
+                default_values: config.default_values.len(),
+            });
+        }
+        for (port, &value) in config.default_values.iter().enumerate() {
+            if value > MAX_DEFAULT_VALUE {
+                problems.push(ConfigProblem::DefaultValueOutOfRange { port, value });
+            }
+        }
+
+        let mut gpio_ports: BTreeMap<u8, Vec<usize>> = BTreeMap::new();
+        for (port, &gpio) in ports.iter().enumerate() {
+            if !self.allows_gpio(gpio) {
+                problems.push(ConfigProblem::GpioNotAllowed { port, gpio });
+            }
+            gpio_ports.entry(gpio).or_default().push(port);
+        }
+        for (&gpio, ports) in &gpio_ports {
+            if ports.len() > 1 {
+                problems.push(ConfigProblem::DuplicateGpio { gpio, ports: ports.clone() });
+            }
+        }
+
+        if config.tach_inputs.len() > ports.len() {
+            problems.push(ConfigProblem::TachInputsLength {
+                ports: ports.len(),
+                tach_inputs: config.tach_inputs.len(),
+            });
+        }
+        let mut tach_gpios = Vec::new();
+        for (port, gpio) in config.tach_inputs.iter().enumerate() {
+            let Some(gpio) = *gpio else { continue };
+            if !self.allows_gpio(gpio) {
+                problems.push(ConfigProblem::TachGpioNotAllowed { port, gpio });
+            } else if gpio_ports.contains_key(&gpio) || tach_gpios.contains(&gpio) {
+                problems.push(ConfigProblem::TachGpioInUse { port, gpio });
+            }
+            tach_gpios.push(gpio);
+        }
+
+        if config.port_settings.len() > ports.len() {
+            problems.push(ConfigProblem::PortSettingsLength {
+                ports: ports.len(),
+                port_settings: config.port_settings.len(),
+            });
+        }
+        for (port, settings) in config.port_settings.iter().enumerate() {
+            if settings.output_mode != OutputMode::Pwm {
+                continue;
+            }
+            let max_frequency = self.max_pwm_frequency();
+            if !(MIN_PWM_FREQUENCY..=max_frequency).contains(&settings.pwm_frequency) {
+                problems.push(ConfigProblem::PwmFrequencyOutOfRange {
+                    port,
+                    frequency: settings.pwm_frequency,
+                    max_frequency,
+                });
+            }
+            let max_resolution = self.max_pwm_resolution();
+            if !(1..=max_resolution).contains(&settings.pwm_resolution) {
+                problems.push(ConfigProblem::PwmResolutionOutOfRange {
+                    port,
+                    resolution: settings.pwm_resolution,
+                    max_resolution,
+                });
+            }
+        }
+
+        self.check_pwm_channels(config, &gpio_ports, &mut problems);
+
+        ConfigReport {
+            board: self.name.clone(),
+            problems,
+        }
+    }
+
+    // Thermistor pins may not be used by ports or tach inputs of `config`
+    pub fn validate_temperature_inputs(&self, config: &DeviceConfig, inputs: &[TemperatureInput]) -> ConfigReport {
+        let mut problems = Vec::new();
+
+        if inputs.len() > MAX_TEMPERATURE_INPUTS {
+            problems.push(ConfigProblem::TooManyTemperatureInputs {
+                inputs: inputs.len(),
+                max_inputs: MAX_TEMPERATURE_INPUTS,
+            });
+        }
+
+        let mut used_gpios = config.ports.clone();
+        used_gpios.extend(config.tach_inputs.iter().flatten());
+        let mut names: Vec<&str> = Vec::new();
+        for (input, temperature_input) in inputs.iter().enumerate() {
+            let name = temperature_input.name.as_str();
+            if name.is_empty() {
+                problems.push(ConfigProblem::TemperatureInputUnnamed { input });
+            } else if names.contains(&name) {
+                problems.push(ConfigProblem::DuplicateTemperatureInput { name: name.to_string() });
+            }
+            names.push(name);
+
+            match &temperature_input.source {
+                TemperatureSource::Internal => {
+                    if !self.internal_temperature {
+                        problems.push(ConfigProblem::InternalTemperatureUnavailable { input });
+                    }
+                }
+                TemperatureSource::Thermistor { gpio, series_resistance, model } => {
+                    if !self.allows_adc_gpio(*gpio) {
+                        problems.push(ConfigProblem::AdcGpioNotAllowed { input, gpio: *gpio });
+                    } else if used_gpios.contains(gpio) {
+                        problems.push(ConfigProblem::TemperatureGpioInUse { input, gpio: *gpio });
+                    }
+                    used_gpios.push(*gpio);
+                    if *series_resistance <= 0.0 || !model.is_valid() {
+                        problems.push(ConfigProblem::InvalidThermistor { input });
+                    }
+                }
+            }
+        }
+
+        ConfigReport {
+            board: self.name.clone(),
+            problems,
+        }
+    }
+
+    // The board runs these without the host, so anything it would refuse is caught here first
+    pub fn validate_standalone_curves(
+        &self,
+        config: &DeviceConfig,
+        inputs: &[TemperatureInput],
+        curves: &[Option<StandaloneCurve>],
+    ) -> ConfigReport {
+        let mut problems = Vec::new();
+
+        if curves.len() > config.ports.len() {
+            problems.push(ConfigProblem::TooManyStandaloneCurves {
+                curves: curves.len(),
+                ports: config.ports.len(),
+            });
+        }
+
+        for (port, curve) in curves.iter().enumerate() {
+            let Some(curve) = curve else {
+                continue;
+            };
+            if !inputs.iter().any(|input| input.name == curve.temperature_input) {
+                problems.push(ConfigProblem::UnknownTemperatureInput {
+                    port,
+                    name: curve.temperature_input.clone(),
+                });
+            }
+            if curve.min_value > curve.max_value || curve.max_value > MAX_PORT_VALUE {
+                problems.push(ConfigProblem::InvalidStandaloneClamps {
+                    port,
+                    min_value: curve.min_value,
+                    max_value: curve.max_value,
+                });
+            }
+
+            let points = curve.curve.len();
+            if points == 0 || points > MAX_STANDALONE_CURVE_POINTS {
+                problems.push(ConfigProblem::StandaloneCurveLength {
+                    port,
+                    points,
+                    max_points: MAX_STANDALONE_CURVE_POINTS,
+                });
+            }
+            if curve.curve.iter().any(|point| !point.temp.is_finite())
+                || curve.curve.windows(2).any(|pair| pair[0].temp >= pair[1].temp)
+            {
+                problems.push(ConfigProblem::StandaloneCurveUnordered { port });
+            }
+            if let Some(point) = curve.curve.iter().find(|point| point.value > MAX_PORT_VALUE) {
+                problems.push(ConfigProblem::StandaloneCurveValueOutOfRange { port, value: point.value });
+            }
+        }
+
+        ConfigReport {
+            board: self.name.clone(),
+            problems,
+        }
+    }
+
+    // What a config write has to pass, the inputs and curves already on the board have to keep
+    // working with the new ports
+    pub fn validate_all(
+        &self,
+        device_info: &DeviceInfo,
+        config: &DeviceConfig,
+        inputs: &[TemperatureInput],
+        curves: &[Option<StandaloneCurve>],
+    ) -> ConfigReport {
+        let mut report = self.validate(device_info, config);
+        report.problems.extend(self.validate_temperature_inputs(config, inputs).problems);
+        // the board drops the curves of removed ports along with them
+        let curves = &curves[..curves.len().min(config.ports.len())];
+        report.problems.extend(self.validate_standalone_curves(config, inputs, curves).problems);
+        report
+    }
+
+    fn check_pwm_channels(
+        &self,
+        config: &DeviceConfig,
+        gpio_ports: &BTreeMap<u8, Vec<usize>>,
+        problems: &mut Vec<ConfigProblem>,
+    ) {
+        let default_settings = PortSettings::default();
+        let mut channels: BTreeMap<(u8, u8), Vec<usize>> = BTreeMap::new();
+        let mut slices: BTreeMap<u8, Vec<usize>> = BTreeMap::new();
+
+        // duplicated GPIOs are reported on their own, only their first port is looked at here
+        for (&gpio, ports) in gpio_ports {
+            let port = ports[0];
+            let settings = config.port_settings.get(port).unwrap_or(&default_settings);
+            if settings.output_mode != OutputMode::Pwm {
+                continue;
+            }
+            if let Some((slice, channel)) = self.pwm_channel(gpio) {
+                channels.entry((slice, channel)).or_default().push(port);
+                slices.entry(slice).or_default().push(port);
+            }
+        }
+
+        for ((slice, channel), ports) in channels {
+            if ports.len() > 1 {
+                problems.push(ConfigProblem::PwmChannelConflict { slice, channel, ports });
+            }
+        }
+        for (slice, ports) in slices {
+            let timing = |port: &usize| {
+                let settings = config.port_settings.get(*port).unwrap_or(&default_settings);
+                (settings.pwm_frequency, settings.pwm_resolution)
+            };
+            let first = timing(&ports[0]);
+            if ports.iter().any(|port| timing(port) != first) {
+                problems.push(ConfigProblem::PwmSliceConflict { slice, ports });
+            }
+        }
+    }
+}
+
+// Built-in profiles plus whatever users described for their own boards
+#[derive(Clone, Debug)]
+pub struct BoardRegistry {
+    profiles: Vec<BoardProfile>,
+}
+
+impl Default for BoardRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl BoardRegistry {
+    pub fn builtin() -> Self {
+        Self {
+            profiles: vec![BoardProfile::rp2040(), BoardProfile::esp32c3()],
+        }
+    }
+
+    // A JSON array of profiles. They replace profiles with the same name and are matched first
+    pub fn load_file(&mut self, path: &Path) -> Result<(), DeviceError> {
+        let profiles: Vec<BoardProfile> = serde_json::from_str(&fs::read_to_string(path)?)?;
+        for profile in profiles.into_iter().rev() {
+            self.add(profile);
+        }
+        Ok(())
+    }
+
+    pub fn add(&mut self, profile: BoardProfile) {
+        self.profiles.retain(|known| known.name != profile.name);
+        self.profiles.insert(0, profile);
+    }
+
+    pub fn profiles(&self) -> &[BoardProfile] {
+        &self.profiles
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BoardProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn find(&self, board_name: &str) -> BoardProfile {
+        self.profiles
+            .iter()
+            .find(|profile| profile.matches(board_name))
+            .cloned()
+            .unwrap_or_else(BoardProfile::generic)
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum ConfigProblem {
+    TooManyPorts { ports: usize, max_ports: u8 },
+    DefaultValuesLength { ports: usize, default_values: usize },
+    DefaultValueOutOfRange { port: usize, value: u8 },
+    GpioNotAllowed { port: usize, gpio: u8 },
+    DuplicateGpio { gpio: u8, ports: Vec<usize> },
+    TachInputsLength { ports: usize, tach_inputs: usize },
+    TachGpioNotAllowed { port: usize, gpio: u8 },
+    TachGpioInUse { port: usize, gpio: u8 },
+    PortSettingsLength { ports: usize, port_settings: usize },
+    PwmFrequencyOutOfRange { port: usize, frequency: u32, max_frequency: u32 },
+    PwmResolutionOutOfRange { port: usize, resolution: u8, max_resolution: u8 },
+    PwmChannelConflict { slice: u8, channel: u8, ports: Vec<usize> }, // the ports would output the same signal
+    PwmSliceConflict { slice: u8, ports: Vec<usize> },                // the slice runs at one frequency and resolution
+    TooManyTemperatureInputs { inputs: usize, max_inputs: usize },
+    TemperatureInputUnnamed { input: usize },
+    DuplicateTemperatureInput { name: String },
+    InternalTemperatureUnavailable { input: usize },
+    AdcGpioNotAllowed { input: usize, gpio: u8 },
+    TemperatureGpioInUse { input: usize, gpio: u8 },
+    InvalidThermistor { input: usize }, // non-positive resistance or beta, or a coefficient that isn't a number
+    TooManyStandaloneCurves { curves: usize, ports: usize },
+    UnknownTemperatureInput { port: usize, name: String },
+    InvalidStandaloneClamps { port: usize, min_value: PortValue, max_value: PortValue },
+    StandaloneCurveLength { port: usize, points: usize, max_points: usize },
+    StandaloneCurveUnordered { port: usize }, // temps have to be numbers in strictly ascending order
+    StandaloneCurveValueOutOfRange { port: usize, value: PortValue },
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyPorts { ports, max_ports } => write!(f, "{} ports, the board has {}", ports, max_ports),
+            Self::DefaultValuesLength { ports, default_values } => {
+                write!(f, "{} default values for {} ports", default_values, ports)
+            }
+            Self::DefaultValueOutOfRange { port, value } => {
+                write!(f, "Port {} default value {}% is above {}%", port, value, MAX_DEFAULT_VALUE)
+            }
+            Self::GpioNotAllowed { port, gpio } => write!(f, "Port {} can't use GPIO {}", port, gpio),
+            Self::DuplicateGpio { gpio, ports } => write!(f, "Ports {:?} all use GPIO {}", ports, gpio),
+            Self::TachInputsLength { ports, tach_inputs } => {
+                write!(f, "{} tach inputs for {} ports", tach_inputs, ports)
+            }
+            Self::TachGpioNotAllowed { port, gpio } => write!(f, "Port {} tach input can't use GPIO {}", port, gpio),
+            Self::TachGpioInUse { port, gpio } => write!(f, "Port {} tach input GPIO {} is already used", port, gpio),
+            Self::PortSettingsLength { ports, port_settings } => {
+                write!(f, "{} port settings for {} ports", port_settings, ports)
+            }
+            Self::PwmFrequencyOutOfRange { port, frequency, max_frequency } => write!(
+                f,
+                "Port {} PWM frequency {} Hz isn't within {}-{} Hz",
+                port, frequency, MIN_PWM_FREQUENCY, max_frequency
+            ),
+            Self::PwmResolutionOutOfRange { port, resolution, max_resolution } => write!(
+                f,
+                "Port {} PWM resolution {} bits isn't within 1-{} bits",
+                port, resolution, max_resolution
+            ),
+            Self::PwmChannelConflict { slice, channel, ports } => {
+                write!(f, "Ports {:?} share PWM slice {} channel {}", ports, slice, channel)
+            }
+            Self::PwmSliceConflict { slice, ports } => {
+                write!(f, "Ports {:?} share PWM slice {} but differ in frequency or resolution", ports, slice)
+            }
+            Self::TooManyTemperatureInputs { inputs, max_inputs } => {
+                write!(f, "{} temperature inputs, the board reads {}", inputs, max_inputs)
+            }
+            Self::TemperatureInputUnnamed { input } => write!(f, "Temperature input {} has no name", input),
+            Self::DuplicateTemperatureInput { name } => write!(f, "Temperature input name {} is used twice", name),
+            Self::InternalTemperatureUnavailable { input } => {
+                write!(f, "Temperature input {} reads an internal sensor the board doesn't have", input)
+            }
+            Self::AdcGpioNotAllowed { input, gpio } => {
+                write!(f, "Temperature input {} can't read GPIO {}, it isn't an ADC pin", input, gpio)
+            }
+            Self::TemperatureGpioInUse { input, gpio } => {
+                write!(f, "Temperature input {} GPIO {} is already used", input, gpio)
+            }
+            Self::InvalidThermistor { input } => write!(f, "Temperature input {} has an invalid thermistor model", input),
+            Self::TooManyStandaloneCurves { curves, ports } => {
+                write!(f, "{} standalone curves for {} ports", curves, ports)
+            }
+            Self::UnknownTemperatureInput { port, name } => {
+                write!(f, "Port {} standalone curve follows unknown temperature input \"{}\"", port, name)
+            }
+            Self::InvalidStandaloneClamps { port, min_value, max_value } => write!(
+                f,
+                "Port {} standalone clamps {}-{}‰ aren't within 0-{}‰",
+                port, min_value, max_value, MAX_PORT_VALUE
+            ),
+            Self::StandaloneCurveLength { port, points, max_points } => {
+                write!(f, "Port {} standalone curve has {} points, the board takes 1-{}", port, points, max_points)
+            }
+            Self::StandaloneCurveUnordered { port } => {
+                write!(f, "Port {} standalone curve temperatures aren't strictly ascending", port)
+            }
+            Self::StandaloneCurveValueOutOfRange { port, value } => {
+                write!(f, "Port {} standalone curve value {}‰ is above {}‰", port, value, MAX_PORT_VALUE)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigReport {
+    pub board: String, // profile the config was checked against
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl ConfigReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), DeviceError> {
+        if self.is_ok() {
+            Ok(())
+        } else {
+            Err(DeviceError::InvalidConfig(self))
+        }
+    }
+}
+
+// Problems go out with their message, so the GUI doesn't need its own wording for each one
+#[derive(Serialize)]
+struct ProblemEntry<'a> {
+    #[serde(flatten)]
+    problem: &'a ConfigProblem,
+    message: String,
+}
+
+impl Serialize for ConfigReport {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let problems: Vec<ProblemEntry> = self
+            .problems
+            .iter()
+            .map(|problem| ProblemEntry { problem, message: problem.to_string() })
+            .collect();
+        let mut state = serializer.serialize_struct("ConfigReport", 2)?;
+        state.serialize_field("board", &self.board)?;
+        state.serialize_field("problems", &problems)?;
+        state.end()
+    }
+}
+
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problems: Vec<String> = self.problems.iter().map(ToString::to_string).collect();
+        write!(f, "{}", problems.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+    use crate::controller::CurvePoint;
+    use crate::device::ThermistorModel;
+
+    fn pico_info() -> DeviceInfo {
+        DeviceInfo {
+            board_name: "RASPBERRY_PI_PICO".to_string(),
+            max_ports: 16,
+            ..Default::default()
+        }
+    }
+
+    fn config(ports: &[u8]) -> DeviceConfig {
+        DeviceConfig {
+            ports: ports.to_vec(),
+            default_values: vec![0; ports.len()],
+            ..Default::default()
+        }
+    }
+
+    fn thermistor(name: &str, gpio: u8) -> TemperatureInput {
+        TemperatureInput {
+            name: name.to_string(),
+            source: TemperatureSource::Thermistor {
+                gpio,
+                series_resistance: 10_000.0,
+                model: ThermistorModel::Beta {
+                    beta: 3950.0,
+                    nominal_resistance: 10_000.0,
+                    nominal_temperature: 25.0,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn rp2040_gpios_map_to_slices_and_channels() {
+        let profile = BoardProfile::rp2040();
+
+        assert_eq!(profile.pwm_channel(0), Some((0, 0)));
+        assert_eq!(profile.pwm_channel(3), Some((1, 1)));
+        assert_eq!(profile.pwm_channel(15), Some((7, 1)));
+        // the slices wrap around from GPIO 16 on
+        assert_eq!(profile.pwm_channel(18), Some((1, 0)));
+        assert_eq!(BoardProfile::esp32c3().pwm_channel(3), None);
+    }
+
+    #[test]
+    fn rp2040_ports_on_one_channel_conflict() {
+        let report = BoardProfile::rp2040().validate(&pico_info(), &config(&[2, 18, 5]));
+
+        assert_eq!(
+            report.problems,
+            vec![ConfigProblem::PwmChannelConflict { slice: 1, channel: 0, ports: vec![0, 1] }]
+        );
+    }
+
+    #[test]
+    fn rp2040_ports_on_one_slice_share_timing() {
+        let mut config = config(&[2, 3]);
+        config.port_settings = vec![PortSettings::default(), PortSettings::default()];
+        assert!(BoardProfile::rp2040().validate(&pico_info(), &config).is_ok());
+
+        config.port_settings[1].pwm_frequency = 1000;
+        let report = BoardProfile::rp2040().validate(&pico_info(), &config);
+
+        assert_eq!(report.problems, vec![ConfigProblem::PwmSliceConflict { slice: 1, ports: vec![0, 1] }]);
+    }
+
+    #[test]
+    fn rp2040_reset_and_board_pins_are_refused() {
+        let mut config = config(&[0, 23, 4]);
+        config.tach_inputs = vec![None, None, Some(29)];
+
+        let report = BoardProfile::rp2040().validate(&pico_info(), &config);
+
+        assert_eq!(
+            report.problems,
+            vec![
+                ConfigProblem::GpioNotAllowed { port: 0, gpio: 0 },
+                ConfigProblem::GpioNotAllowed { port: 1, gpio: 23 },
+                ConfigProblem::TachGpioNotAllowed { port: 2, gpio: 29 },
+            ]
+        );
+    }
+
+    #[test]
+    fn thermistors_need_a_free_adc_pin() {
+        let inputs = [thermistor("case", 5), thermistor("water", 26), thermistor("exhaust", 27)];
+
+        let report = BoardProfile::rp2040().validate_temperature_inputs(&config(&[27]), &inputs);
+
+        assert_eq!(
+            report.problems,
+            vec![
+                ConfigProblem::AdcGpioNotAllowed { input: 0, gpio: 5 },
+                ConfigProblem::TemperatureGpioInUse { input: 2, gpio: 27 },
+            ]
+        );
+    }
+
+    #[test]
+    fn config_checks_cover_inputs_and_curves() {
+        let profile = BoardProfile::rp2040();
+        let inputs = [thermistor("water", 26)];
+        let curve = |temperature_input: &str| {
+            Some(StandaloneCurve {
+                temperature_input: temperature_input.to_string(),
+                curve: vec![CurvePoint { temp: 30.0, value: 500 }],
+                min_value: 0,
+                max_value: MAX_PORT_VALUE,
+            })
+        };
+        // the curve of the dropped third port goes away with it
+        let curves = [curve("water"), curve("air"), curve("gone")];
+
+        let report = profile.validate_all(&pico_info(), &config(&[26, 4]), &inputs, &curves);
+
+        assert_eq!(
+            report.problems,
+            vec![
+                ConfigProblem::TemperatureGpioInUse { input: 0, gpio: 26 },
+                ConfigProblem::UnknownTemperatureInput { port: 1, name: "air".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn report_serializes_problems_with_their_message() {
+        let report = BoardProfile::rp2040().validate(&pico_info(), &config(&[0]));
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "board": "rp2040",
+                "problems": [{"problem": "gpio_not_allowed", "port": 0, "gpio": 0, "message": "Port 0 can't use GPIO 0"}],
+            })
+        );
+    }
+}
@@ -1,11 +1,12 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
-use crate::device::{Device, PortValue};
+use crate::actor::DeviceHandle;
+use crate::device::{DeviceError, PortValue, StandaloneCurve};
 use crate::sensors::Sensor;
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
 #[derive(Default)]
 pub struct CurvePoint {
     pub temp: f32,
@@ -41,12 +42,27 @@ pub struct CoolHolderData {
 pub struct PlugState {
     pub plug_value: PortValue,
     pub last_temp: f32,
+    pub rpm: Option<u32>,
+}
+
+#[derive(Clone, Copy, Serialize, Default)]
+pub struct UpdateStats {
+    pub sent: u64,
+    pub skipped: u64,
+}
+
+// Keeps track of what the device already holds, so unchanged values aren't sent every tick
+pub struct ValueSync {
+    refresh_interval: Duration,
+    last_sent: HashMap<u8, PortValue>,
+    last_refresh: Option<Instant>,
+    stats: UpdateStats,
 }
 
 #[derive(Clone)]
 pub struct PlugExternals {
     pub plug_index: u8,
-    pub device: Arc<Mutex<Device>>,
+    pub device: DeviceHandle,
     pub update_time: u64,
     pub sensor: Arc<dyn Sensor>,
 }
@@ -58,6 +74,18 @@ pub struct PlugConfig {
     pub cool_holder: Option<CoolHolderData>,
 }
 
+impl PlugConfig {
+    // What the board can run on its own: the curve without dead areas or cool holding, plus clamps
+    pub fn standalone_curve(&self, temperature_input: &str, min_value: PortValue, max_value: PortValue) -> StandaloneCurve {
+        StandaloneCurve {
+            temperature_input: temperature_input.to_string(),
+            curve: self.curve.clone(),
+            min_value,
+            max_value,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct PlugHandler {
     plug_state: PlugState,
@@ -68,31 +96,27 @@ pub struct PlugHandler {
 impl PlugHandler {
     pub async fn new(
     plug_index: u8,
-    device: Arc<Mutex<Device>>,
+    device: DeviceHandle,
     sensor: Arc<dyn Sensor>,
     plug_config: PlugConfig
-) -> Result<Self, String> {
-    let plug_value;
-    let update_time;
-    {
-        let mut device_lock = device.lock().await;
-        plug_value = device_lock
-            .get_plugs_values()
-            .await?
-            .get(plug_index as usize)
-            .ok_or("Plug index out of range")?
-            .clone();
-        update_time = device_lock.device_config.update_time.clone();
-    }
+) -> Result<Self, DeviceError> {
+    let plug_value = device
+        .get_plugs_values()
+        .await?
+        .get(plug_index as usize)
+        .copied()
+        .ok_or(DeviceError::InvalidPortIndex(plug_index))?;
+    let update_time = device.device_config().update_time;
 
     let plug_state = PlugState {
         plug_value,
         last_temp: 0f32,
+        rpm: None,
     };
 
     let plug_externals = PlugExternals {
         plug_index,
-        device: device.clone(),
+        device,
         update_time,
         sensor,
     };
@@ -114,8 +138,14 @@ impl PlugHandler {
         self.plug_externals.sensor = sensor;
     }
 
-    fn calculate_curve(curve: &Vec<CurvePoint>, temp: f32) -> PortValue {
-        if curve.len() == 0 {
+    pub fn plug_index(&self) -> u8 { self.plug_externals.plug_index }
+
+    pub fn set_rpm(&mut self, rpm: Option<u32>) {
+        self.plug_state.rpm = rpm;
+    }
+
+    fn calculate_curve(curve: &[CurvePoint], temp: f32) -> PortValue {
+        if curve.is_empty() {
             return 0;
         }
         
@@ -129,81 +159,152 @@ impl PlugHandler {
         for i in 0..curve.len() - 1 {
             if temp >= curve[i].temp && temp <= curve[i + 1].temp {
                 let t = (temp - curve[i].temp) / (curve[i + 1].temp - curve[i].temp);
-                return curve[i].value + t as PortValue * (curve[i + 1].value - curve[i].value);
+                let (from, to) = (curve[i].value as f32, curve[i + 1].value as f32);
+                return (from + t * (to - from)).round() as PortValue;
             }
         }
 
         curve[curve.len() - 1].value
     }
-    pub async fn calculate_speed(&mut self) -> Result<(), String> {
-        let (plug_externals, plug_state) = {
-
-            let current_temp = self.plug_externals.sensor.get_temperature()?;
-            let mut calculated = 0;
-            let last_temp = self.plug_state.last_temp;
+    // Returns the value to send, or None while the cool holder decides to leave the plug alone
+    pub fn calculate_speed(&mut self) -> Result<Option<PortValue>, String> {
+        let current_temp = self.plug_externals.sensor.get_temperature()?;
+        let mut calculated;
+        let last_temp = self.plug_state.last_temp;
 
-            if let Some(ref mut cool_holder) = self.plug_config.cool_holder{
+        if let Some(ref mut cool_holder) = self.plug_config.cool_holder{
 
-                if cool_holder.is_holding {
-                    if let Some(start_time) = cool_holder.start_time {
-                        if start_time.elapsed() > cool_holder.holding_time {
-                            cool_holder.is_holding = false;
-                            return Ok(());
-                        }
-                        if (current_temp - cool_holder.off_delta as f32) > last_temp {
-                            cool_holder.is_holding = false;
-                            return Ok(());
-                        }
-                    } else {
-                        cool_holder.start_time = Some(Instant::now());
+            if cool_holder.is_holding {
+                if let Some(start_time) = cool_holder.start_time {
+                    if start_time.elapsed() > cool_holder.holding_time {
+                        cool_holder.is_holding = false;
+                        return Ok(None);
+                    }
+                    if (current_temp - cool_holder.off_delta as f32) > last_temp {
+                        cool_holder.is_holding = false;
+                        return Ok(None);
                     }
-                    calculated = self.plug_state.plug_value;
-                } else if (current_temp + cool_holder.on_delta as f32) < last_temp {
-                    cool_holder.is_holding = true;
-                    cool_holder.start_time = Some(Instant::now());
-                    return Ok(());
                 } else {
-                    calculated = Self::calculate_curve(&self.plug_config.curve, current_temp);
+                    cool_holder.start_time = Some(Instant::now());
                 }
+                calculated = self.plug_state.plug_value;
+            } else if (current_temp + cool_holder.on_delta as f32) < last_temp {
+                cool_holder.is_holding = true;
+                cool_holder.start_time = Some(Instant::now());
+                return Ok(None);
             } else {
                 calculated = Self::calculate_curve(&self.plug_config.curve, current_temp);
             }
+        } else {
+            calculated = Self::calculate_curve(&self.plug_config.curve, current_temp);
+        }
 
 
-            for dead_area in &self.plug_config.dead_areas {
-                if calculated > dead_area.min_value && calculated < dead_area.max_value {
-                    calculated = match dead_area.variant {
-                        DeadAreaVariant::Min => dead_area.min_value,
-                        DeadAreaVariant::Max => dead_area.max_value,
-                        DeadAreaVariant::Center => {
-                            let delta = dead_area.max_value - dead_area.min_value;
-                            if calculated - dead_area.min_value > delta / 2 {
-                                dead_area.max_value
-                            } else {
-                                dead_area.min_value
-                            }
+        for dead_area in &self.plug_config.dead_areas {
+            if calculated > dead_area.min_value && calculated < dead_area.max_value {
+                calculated = match dead_area.variant {
+                    DeadAreaVariant::Min => dead_area.min_value,
+                    DeadAreaVariant::Max => dead_area.max_value,
+                    DeadAreaVariant::Center => {
+                        let delta = dead_area.max_value - dead_area.min_value;
+                        if calculated - dead_area.min_value > delta / 2 {
+                            dead_area.max_value
+                        } else {
+                            dead_area.min_value
                         }
-                    };
-                }
+                    }
+                };
             }
+        }
+
+        self.plug_state.plug_value = calculated;
+        self.plug_state.last_temp = current_temp;
+        Ok(Some(self.plug_state.plug_value))
+    }
+
+}
+
+impl ValueSync {
+    pub fn new(refresh_interval: Duration) -> Self {
+        Self {
+            refresh_interval,
+            last_sent: HashMap::new(),
+            last_refresh: None,
+            stats: UpdateStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> UpdateStats { self.stats }
+
+    // Changed values only, or all of them once the refresh interval has passed
+    pub fn pending(&mut self, values: &[(u8, PortValue)]) -> Vec<(u8, PortValue)> {
+        let refresh = self
+            .last_refresh
+            .is_none_or(|last_refresh| last_refresh.elapsed() >= self.refresh_interval);
+        if refresh {
+            self.last_refresh = Some(Instant::now());
+        }
 
-            self.plug_state.plug_value = calculated;
-            self.plug_state.last_temp = current_temp;
+        let pending: Vec<(u8, PortValue)> = values
+            .iter()
+            .copied()
+            .filter(|(index, value)| refresh || self.last_sent.get(index) != Some(value))
+            .collect();
 
-            (
-                self.plug_externals.clone(),
-                self.plug_state.clone(),
-            )
-        };
+        // sent values are counted once the board took them, see `record`
+        self.stats.skipped += (values.len() - pending.len()) as u64;
+        pending
+    }
 
-        {
-            let mut device_lock = plug_externals.device.lock().await;
-            device_lock.test_connection(Duration::from_millis(100), Duration::from_millis(10)).await;
-            device_lock.set_plug_value(plug_externals.plug_index, plug_state.plug_value).await?;
+    pub fn record(&mut self, index: u8, value: PortValue, delivered: bool) {
+        if delivered {
+            self.stats.sent += 1;
+            self.last_sent.insert(index, value);
+        } else {
+            self.last_sent.remove(&index);
         }
+    }
+
+    // Nothing is known about the device anymore, everything goes out on the next tick
+    pub fn invalidate(&mut self) {
+        self.last_sent.clear();
+        self.last_refresh = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn unchanged_values_are_skipped() {
+        let mut value_sync = ValueSync::new(Duration::from_secs(60));
 
-        Ok(())
+        let pending = value_sync.pending(&[(0, 500), (1, 300)]);
+        for (index, value) in pending {
+            value_sync.record(index, value, true);
+        }
+        let pending = value_sync.pending(&[(0, 500), (1, 400)]);
+
+        assert_eq!(pending, vec![(1, 400)]);
+        assert_eq!(value_sync.stats().sent, 2);
+        assert_eq!(value_sync.stats().skipped, 1);
     }
 
+    #[test]
+    fn failed_deliveries_are_not_counted_as_sent() {
+        let mut value_sync = ValueSync::new(Duration::from_secs(60));
+
+        let pending = value_sync.pending(&[(0, 500), (1, 300)]);
+        value_sync.record(0, 500, true);
+        value_sync.record(1, 300, false);
+        // a write that failed as a whole records nothing
+        value_sync.pending(&[(0, 600)]);
+        value_sync.invalidate();
+
+        assert_eq!(pending.len(), 2);
+        assert_eq!(value_sync.stats().sent, 1);
+        // the failed value goes out again on the next tick
+        assert_eq!(value_sync.pending(&[(1, 300)]), vec![(1, 300)]);
+    }
 }
@@ -1,9 +1,17 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio_serial::{ClearBuffer, FlowControl, SerialPort, SerialPortType};
+use tokio::sync::broadcast;
+use tokio_serial::SerialPortType;
+use crate::board::{BoardProfile, BoardRegistry, ConfigReport};
+use crate::controller::CurvePoint;
+use crate::reconnect::{ConnectionState, GiveUp, ReconnectPolicy, ReconnectState};
+use crate::trace::{TraceKind, TraceRecorder};
+use crate::transport::Transport;
+use crate::transports::serial_transport::SerialTransport;
 
 const PING_API: &str = "ping";
 const PONG_API: &str = "pong";
@@ -14,11 +22,81 @@ const GET_CONFIG_API: &str = "get_config";
 const SET_UPDATE_TIME_API: &str = "set_update_time";
 const SET_PLUG_DEFAULT_VALUE_API: &str = "set_default_value";
 const SET_PLUG_VALUE_API: &str = "set_value";
+const SET_PLUGS_VALUES_API: &str = "set_values";
 const SET_DEVICE_CONFIG_API: &str = "set_config";
 const SET_PLUS_CONFIG_API: &str = "ports_setup";
 const LOAD_DEFAULT_CONFIG_API: &str = "load_default_config";
+const GET_RPM_API: &str = "get_rpm";
+const BOOTLOADER_API: &str = "bootloader";
+const HEARTBEAT_API: &str = "heartbeat";
+const SET_HEARTBEAT_TIMEOUT_API: &str = "set_heartbeat_timeout";
+const SET_PLUGS_VALUES_PERMILLE_API: &str = "set_values_permille";
+const GET_PLUGS_VALUES_PERMILLE_API: &str = "get_value_permille";
+const REBOOT_API: &str = "reboot";
+const FACTORY_RESET_API: &str = "factory_reset";
+const IDENTIFY_API: &str = "identify";
+const SET_TEMPERATURE_INPUTS_API: &str = "set_temperature_inputs";
+const GET_TEMPERATURE_INPUTS_API: &str = "get_temperature_inputs";
+const GET_TEMPERATURES_API: &str = "get_temperatures";
+const SET_STANDALONE_CURVES_API: &str = "set_standalone_curves";
+const GET_STANDALONE_CURVES_API: &str = "get_standalone_curves";
+const GET_CONTROL_MODE_API: &str = "get_control_mode";
 
-pub type PortValue = u8;
+// commands the board answers without treating the host as present
+const PASSIVE_COMMANDS: [&str; 3] = [PING_API, GET_BOARD_INFO_API, GET_CONTROL_MODE_API];
+
+// command set of firmware that doesn't report its capabilities in `board_info`
+const LEGACY_COMMANDS: [&str; 11] = [
+    PING_API,
+    GET_BOARD_INFO_API,
+    GET_PLUGS_VALUES_API,
+    GET_DEFAULT_CONFIG_API,
+    GET_CONFIG_API,
+    SET_UPDATE_TIME_API,
+    SET_PLUG_DEFAULT_VALUE_API,
+    SET_PLUG_VALUE_API,
+    SET_DEVICE_CONFIG_API,
+    SET_PLUS_CONFIG_API,
+    LOAD_DEFAULT_CONFIG_API,
+];
+
+// capabilities that aren't commands of their own
+const PORT_SETTINGS_CAPABILITY: &str = "port_settings";
+
+pub const MIN_PWM_FREQUENCY: u32 = 10;
+pub const MAX_PWM_FREQUENCY: u32 = 100_000;
+pub const MAX_PWM_RESOLUTION: u8 = 16;
+pub const MAX_TEMPERATURE_INPUTS: usize = 4;
+pub const MAX_STANDALONE_CURVE_POINTS: usize = 8;
+const STANDALONE_TEMP_TOLERANCE: f32 = 0.01; // the board keeps temps as 32 bit floats
+
+const AFTER_HW_RESET_INFO: &str = "after-hw-reset";
+const FAILSAFE_INFO: &str = "failsafe";
+const OK_RESULT: &str = "ok";
+
+const READ_TIMEOUT: Duration = Duration::from_millis(1500);
+const REBOOT_TIMEOUT: Duration = Duration::from_secs(10);
+const REBOOT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+pub const MAX_IDENTIFY_DURATION: Duration = Duration::from_secs(60);
+const EVENTS_CAPACITY: usize = 32;
+
+// Permille of full duty. Boards without the permille commands get it rounded to whole percents
+pub type PortValue = u16;
+
+pub const MAX_PORT_VALUE: PortValue = 1000;
+const PERCENT_SCALE: PortValue = 10;
+
+pub fn percent_to_value(percent: u8) -> PortValue {
+    percent as PortValue * PERCENT_SCALE
+}
+
+pub fn value_to_percent(value: PortValue) -> u8 {
+    ((value.min(MAX_PORT_VALUE) + PERCENT_SCALE / 2) / PERCENT_SCALE) as u8
+}
+
+fn invalid_value(value: PortValue) -> DeviceError {
+    DeviceError::InvalidArgument(format!("Port value {} is above {}", value, MAX_PORT_VALUE))
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PortInfo {
@@ -32,42 +110,352 @@ pub struct GetPlugsResponse {
     pub values: Vec<PortValue>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct SetPlugsValuesResponse {
+    pub results: Vec<String>, // one per pair, in request order
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetRpmResponse {
+    pub rpm: Vec<Option<u32>>, // None for ports without a tach input
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetTemperatureInputsResponse {
+    pub temperature_inputs: Vec<TemperatureInput>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetTemperaturesResponse {
+    pub temperatures: Vec<Option<f32>>, // °C per input, None when the reading is off the scale
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetStandaloneCurvesResponse {
+    pub standalone_curves: Vec<Option<StandaloneCurve>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    #[default]
+    Pwm,
+    OnOff, // plain high/low, anything above 0 switches the port on
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct PortSettings {
+    pub pwm_frequency: u32, // Hz, 25 kHz for 4-pin fans, a few hundred Hz for DC fans behind a MOSFET
+    pub pwm_resolution: u8, // bits
+    pub inverted: bool,     // active-low outputs
+    pub output_mode: OutputMode,
+}
+
+impl Default for PortSettings {
+    fn default() -> Self {
+        Self {
+            pwm_frequency: 25_000,
+            pwm_resolution: 10,
+            inverted: false,
+            output_mode: OutputMode::Pwm,
+        }
+    }
+}
+
+// NTC thermistor curves, resistances in ohms and temperatures in °C
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ThermistorModel {
+    Beta {
+        beta: f32,
+        nominal_resistance: f32,
+        nominal_temperature: f32,
+    },
+    SteinhartHart {
+        a: f32,
+        b: f32,
+        c: f32,
+    },
+}
+
+impl ThermistorModel {
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Self::Beta {
+                beta,
+                nominal_resistance,
+                nominal_temperature,
+            } => beta > 0.0 && nominal_resistance > 0.0 && nominal_temperature.is_finite(),
+            Self::SteinhartHart { a, b, c } => a.is_finite() && b.is_finite() && c.is_finite(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum TemperatureSource {
+    Internal, // the sensor on the chip itself, roughly the case temperature around the board
+    // the series resistor goes from 3.3 V to the ADC pin, the thermistor from the pin to ground
+    Thermistor {
+        gpio: u8,
+        series_resistance: f32,
+        model: ThermistorModel,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TemperatureInput {
+    pub name: String, // unique per board, sensors are found by it
+    #[serde(flatten)]
+    pub source: TemperatureSource,
+}
+
+// A port curve the board runs against one of its temperature inputs while the host is away
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct StandaloneCurve {
+    pub temperature_input: String,
+    pub curve: Vec<CurvePoint>, // ascending temps, at most MAX_STANDALONE_CURVE_POINTS
+    pub min_value: PortValue,
+    pub max_value: PortValue,
+}
+
+impl StandaloneCurve {
+    // Equal up to the float precision the board stores temps with
+    pub fn matches(&self, other: &StandaloneCurve) -> bool {
+        self.temperature_input == other.temperature_input
+            && self.min_value == other.min_value
+            && self.max_value == other.max_value
+            && self.curve.len() == other.curve.len()
+            && self.curve.iter().zip(&other.curve).all(|(point, other_point)| {
+                point.value == other_point.value && (point.temp - other_point.temp).abs() <= STANDALONE_TEMP_TOLERANCE
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlMode {
+    Host,       // values come from the host
+    Standalone, // no host since boot or since the failsafe, the board runs its standalone curves
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct ControlStatus {
+    pub control_mode: ControlMode,
+    pub mode_time: u64, // ms spent in the mode when it was read
+    // ms the board ran standalone before the host last took it over, kept until the next time
+    #[serde(default)]
+    pub standalone_time: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TemperatureReading {
+    pub values: Vec<Option<f32>>, // in `temperature_inputs` order
+    pub read_at: Instant,
+}
+
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct DeviceConfig {
     pub ports: Vec<u8>,
-    pub default_values: Vec<PortValue>,
+    pub default_values: Vec<u8>, // percent, every firmware stores them that way
     pub update_time: u64,
+    #[serde(default)]
+    pub tach_inputs: Vec<Option<u8>>, // tach GPIO per port
+    // ms without host commands before the board falls back to default_values, 0 disables it
+    #[serde(default)]
+    pub heartbeat_timeout: u64,
+    // per port, missing entries run on `PortSettings::default`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub port_settings: Vec<PortSettings>,
+}
+
+impl DeviceConfig {
+    pub fn has_tach_inputs(&self) -> bool {
+        self.tach_inputs.iter().any(Option::is_some)
+    }
+
+    pub fn has_port_settings(&self) -> bool {
+        self.port_settings.iter().any(|settings| *settings != PortSettings::default())
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DeviceCode {
     Ok,
+    Info,
     Err,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct DeviceResponse<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
     pub code: DeviceCode,
     pub message: Option<String>,
     pub data: Option<T>,
 }
 
+// Error messages the firmware answers `err` responses with, see `errors.h`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FirmwareErrorCode {
+    BadJson,
+    BadArgs,
+    BadArgsCount,
+    StorageMount,
+    LoadingConfig,
+    Unknown,
+}
+
+impl FirmwareErrorCode {
+    pub fn from_message(message: &str) -> Self {
+        match message {
+            "bad-json" => Self::BadJson,
+            "bad-args" => Self::BadArgs,
+            "bad-args-count" => Self::BadArgsCount,
+            "storage-mount-err" => Self::StorageMount,
+            "loading-config-err" => Self::LoadingConfig,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Transport,  // the link to the board
+    Protocol,   // the board answered something unexpected
+    Firmware,   // the board refused the command
+    Validation, // refused before anything was sent
+    Update,     // firmware update steps outside the protocol
+}
+
 #[derive(Debug, Error)]
 pub enum DeviceError {
-    #[error("Serialization error: {0}")]
-    SerdeError(#[from] serde_json::Error),
     #[error("Serial error: {0}")]
     SerialPortError(#[from] tokio_serial::Error),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("Line codec error: {0}")]
+    CodecError(#[from] tokio_util::codec::LinesCodecError),
+    #[error("Device response timed out")]
+    Timeout,
+    #[error("Device is offline")]
+    Offline,
+    #[error("{0}")]
+    Disconnected(String),
+    #[error("Reconnecting in {0:?}")]
+    BackingOff(Duration),
+    #[error("Gave up reconnecting after {attempts} attempts")]
+    GaveUp { attempts: u32 },
+
+    #[error("Serialization error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    #[error("Malformed device response: {0}")]
+    MalformedResponse(String),
+    #[error("Device response has no data")]
+    EmptyResponse,
+    #[error("Command `{0}` isn't supported by the device")]
+    UnsupportedCommand(String),
+    #[error("Out of order device response: expected id {expected}, received {received}")]
+    OutOfOrderResponse { expected: u32, received: u32 },
+    #[error("Standalone curves read back from the device differ from the uploaded ones")]
+    StandaloneCurvesMismatch,
+
+    #[error("Device error: {message}")]
+    Firmware { code: FirmwareErrorCode, message: String },
+
+    #[error("Port index {0} is out of range")]
+    InvalidPortIndex(u8),
     #[error("{0}")]
-    CustomError(String),
+    InvalidArgument(String),
+    #[error("The device doesn't support {0}")]
+    UnsupportedFeature(String),
+    #[error("Invalid device config: {0}")]
+    InvalidConfig(ConfigReport),
+
+    #[error("Firmware update failed: {0}")]
+    UpdateFailed(String),
 }
 
-impl From<DeviceError> for String {
-    fn from(error: DeviceError) -> Self {
-        error.to_string()
+impl DeviceError {
+    pub fn firmware(message: String) -> Self {
+        Self::Firmware {
+            code: FirmwareErrorCode::from_message(&message),
+            message,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::SerialPortError(_)
+            | Self::IoError(_)
+            | Self::CodecError(_)
+            | Self::Timeout
+            | Self::Offline
+            | Self::Disconnected(_)
+            | Self::BackingOff(_)
+            | Self::GaveUp { .. } => ErrorKind::Transport,
+            Self::SerdeError(_)
+            | Self::MalformedResponse(_)
+            | Self::EmptyResponse
+            | Self::UnsupportedCommand(_)
+            | Self::OutOfOrderResponse { .. }
+            | Self::StandaloneCurvesMismatch => ErrorKind::Protocol,
+            Self::Firmware { .. } => ErrorKind::Firmware,
+            Self::InvalidPortIndex(_)
+            | Self::InvalidArgument(_)
+            | Self::UnsupportedFeature(_)
+            | Self::InvalidConfig(_) => {
+                ErrorKind::Validation
+            }
+            Self::UpdateFailed(_) => ErrorKind::Update,
+        }
+    }
+
+    pub fn firmware_code(&self) -> Option<FirmwareErrorCode> {
+        match self {
+            Self::Firmware { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    // Whether sending the same command again can succeed. An offline device waits for hotplug instead
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::SerialPortError(_)
+            | Self::IoError(_)
+            | Self::CodecError(_)
+            | Self::Timeout
+            | Self::Disconnected(_)
+            | Self::BackingOff(_)
+            | Self::MalformedResponse(_)
+            | Self::OutOfOrderResponse { .. }
+            | Self::StandaloneCurvesMismatch => true,
+            // a line garbled on the wire
+            Self::Firmware { code, .. } => *code == FirmwareErrorCode::BadJson,
+            _ => false,
+        }
+    }
+}
+
+// Sent to the frontend as an object, the message is the `Display` text
+impl Serialize for DeviceError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("DeviceError", 5)?;
+        state.serialize_field("kind", &self.kind())?;
+        state.serialize_field("code", &self.firmware_code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retryable", &self.is_retryable())?;
+        match self {
+            Self::InvalidConfig(report) => state.serialize_field("report", report)?,
+            _ => state.skip_field("report")?,
+        }
+        state.end()
     }
 }
 
@@ -75,6 +463,36 @@ impl From<DeviceError> for String {
 pub struct DeviceInfo {
     pub board_name: String,
     pub max_ports: u8,
+    #[serde(default)]
+    pub protocol_version: u32, // 0 for firmware that predates versioning
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub device_uid: Option<String>,
+    #[serde(default)]
+    pub firmware_version: Option<String>,
+}
+
+impl DeviceInfo {
+    // Identity that survives port renumbering: the firmware reported id, then the USB serial number
+    pub fn stable_id(&self, usb_serial_number: Option<&str>) -> Option<String> {
+        self.device_uid
+            .clone()
+            .or_else(|| usb_serial_number.map(str::to_string))
+            .filter(|id| !id.is_empty())
+    }
+
+    pub fn supports(&self, command: &str) -> bool {
+        // the handshake itself has to work before anything is known about the board
+        if command == PING_API || command == GET_BOARD_INFO_API {
+            return true;
+        }
+        if self.capabilities.is_empty() {
+            LEGACY_COMMANDS.contains(&command)
+        } else {
+            self.capabilities.iter().any(|capability| capability == command)
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -88,26 +506,53 @@ pub struct DeviceSummary {
     pub serial_info: SerialInfo,
     pub device_info: DeviceInfo,
     pub device_config: DeviceConfig,
+    #[serde(default)]
+    pub temperature_inputs: Vec<TemperatureInput>,
+    #[serde(default)]
+    pub standalone_curves: Vec<Option<StandaloneCurve>>,
+    #[serde(default)]
+    pub control_status: Option<ControlStatus>, // as last read, None for boards without standalone control
+}
+
+// Lines the board sends on its own, outside of any command reply
+#[derive(Serialize, Clone, Debug)]
+pub enum DeviceEvent {
+    HardwareReset,
+    Failsafe, // host heartbeat timed out, the board is running on default_values
+    Connection(ConnectionState),
+    Info(String),
+    Text(String),
 }
 
 #[derive(Serialize, Clone)]
 pub enum DeviceState {
     Ok,
+    Offline,
     Error(String),
 }
 
-pub struct Device {
-    serial_port_connection: Option<Box<dyn SerialPort>>,
-    serial_info: SerialInfo,
+pub struct Device<T: Transport = SerialTransport> {
+    transport: T,
+    last_request_id: u32,
+    read_timeout: Duration,
+    events: broadcast::Sender<DeviceEvent>,
+    reconnect_policy: ReconnectPolicy,
+    reconnect: ReconnectState,
+    trace: Option<TraceRecorder>,
+    board_registry: Arc<BoardRegistry>,
     pub device_info: DeviceInfo,
     plugs_values: Vec<PortValue>,
     pub device_config: DeviceConfig,
+    pub temperature_inputs: Vec<TemperatureInput>,
+    temperatures: Option<TemperatureReading>,
+    pub standalone_curves: Vec<Option<StandaloneCurve>>,
+    control_status: Option<ControlStatus>,
     pub device_state: DeviceState,
 }
 
 impl Device {
-    pub fn get_device_list() -> Result<Vec<PortInfo>, ()> {
-        let ports = tokio_serial::available_ports().map_err(|_| ())?;
+    pub fn get_device_list() -> Result<Vec<PortInfo>, DeviceError> {
+        let ports = tokio_serial::available_ports()?;
         Ok(ports
             .iter()
             .map(|port| {
@@ -136,154 +581,352 @@ impl Device {
             .collect())
     }
 
+    pub fn new(serial_info: SerialInfo) -> Self {
+        Self::with_transport(SerialTransport::new(serial_info))
+    }
+
     pub fn create_summary(&self) -> DeviceSummary {
         DeviceSummary {
-            serial_info: self.serial_info.clone(),
+            serial_info: self.transport.serial_info().clone(),
             device_info: self.device_info.clone(),
             device_config: self.device_config.clone(),
+            temperature_inputs: self.temperature_inputs.clone(),
+            standalone_curves: self.standalone_curves.clone(),
+            control_status: self.control_status,
         }
     }
+}
 
-    pub fn new(serial_info: SerialInfo) -> Self {
-        let mut self_struct = Self {
-            serial_port_connection: None,
-            serial_info,
+impl<T: Transport> Device<T> {
+    pub fn with_transport(transport: T) -> Self {
+        Self {
+            transport,
+            last_request_id: 0,
+            read_timeout: READ_TIMEOUT,
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            reconnect_policy: ReconnectPolicy::default(),
+            reconnect: ReconnectState::default(),
+            trace: None,
+            board_registry: Arc::new(BoardRegistry::builtin()),
             device_info: Default::default(),
             plugs_values: Vec::new(),
             device_config: Default::default(),
+            temperature_inputs: Vec::new(),
+            temperatures: None,
+            standalone_curves: Vec::new(),
+            control_status: None,
             device_state: DeviceState::Ok,
-        };
+        }
+    }
 
-        let _ = self_struct.open_connection();
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
 
-        self_struct
+    pub fn set_read_timeout(&mut self, read_timeout: Duration) {
+        self.read_timeout = read_timeout;
     }
 
-    pub fn open_connection(&mut self) -> Result<(), DeviceError> {
-        if self.serial_port_connection.is_some() {
-            self.serial_port_connection.take();
-        };
-        let mut connection = tokio_serial::new(
-            self.serial_info.com_port.clone(),
-            self.serial_info.baud_rate,
-        )
-        .flow_control(FlowControl::Software)
-        .parity(tokio_serial::Parity::None)
-        .stop_bits(tokio_serial::StopBits::One)
-        .timeout(Duration::from_millis(1500))
-        .open()?;
-        connection.write_data_terminal_ready(true)?;
-        self.serial_port_connection = Some(connection);
+    pub fn reconnect_policy(&self) -> &ReconnectPolicy {
+        &self.reconnect_policy
+    }
+
+    pub fn set_reconnect_policy(&mut self, reconnect_policy: ReconnectPolicy) {
+        self.reconnect_policy = reconnect_policy;
+    }
+
+    pub fn set_board_registry(&mut self, board_registry: Arc<BoardRegistry>) {
+        self.board_registry = board_registry;
+    }
+
+    pub(crate) fn event_sender(&self) -> broadcast::Sender<DeviceEvent> {
+        self.events.clone()
+    }
+
+    pub fn subscribe_events(&self) -> broadcast::Receiver<DeviceEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit_event(&self, event: DeviceEvent) {
+        // no subscribers is fine, events are just dropped then
+        let _ = self.events.send(event);
+    }
 
+    // Records everything that goes over the wire from now on, see `trace::TraceEntry`
+    pub fn start_trace(&mut self, path: &Path) -> Result<(), DeviceError> {
+        self.trace = Some(TraceRecorder::create(path)?);
         Ok(())
     }
 
+    pub fn stop_trace(&mut self) {
+        self.trace = None;
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    fn trace(&mut self, kind: TraceKind, data: Option<&str>) {
+        if let Some(trace) = &mut self.trace {
+            trace.record(kind, data);
+        }
+    }
+
+    fn close_transport(&mut self) {
+        if self.transport.is_open() {
+            self.trace(TraceKind::Close, None);
+        }
+        self.transport.close();
+    }
+
+    pub fn close_connection(&mut self) {
+        self.close_transport();
+    }
+
+    // An explicit open starts the reconnect cycle over, even after giving up
+    pub async fn open_connection(&mut self) -> Result<(), DeviceError> {
+        self.reconnect.reset();
+        self.connect().await
+    }
+
+    async fn connect(&mut self) -> Result<(), DeviceError> {
+        self.reconnect.attempts += 1;
+        self.emit_event(DeviceEvent::Connection(ConnectionState::Connecting {
+            attempt: self.reconnect.attempts,
+        }));
+        self.close_transport();
+        self.trace(TraceKind::Open, None);
+        self.transport
+            .open()
+            .await
+            .inspect_err(|err| self.trace(TraceKind::OpenError, Some(&err.to_string())))
+    }
+
+    // Doesn't wait out the backoff, requests fail right away until the next attempt is due
     async fn ensure_connection(&mut self) -> Result<(), DeviceError> {
-        if self.serial_port_connection.is_none() {
-            if let DeviceState::Error(_) = self.device_state{
-                tokio::time::sleep(Duration::from_millis(500)).await;
+        if let DeviceState::Offline = self.device_state {
+            return Err(DeviceError::Offline);
+        }
+        if !self.transport.is_open() {
+            if self.reconnect.gave_up {
+                return Err(DeviceError::GaveUp {
+                    attempts: self.reconnect.attempts,
+                });
+            }
+            if let Some(next_attempt) = self.reconnect.next_attempt {
+                let now = Instant::now();
+                if now < next_attempt {
+                    return Err(DeviceError::BackingOff(next_attempt - now));
+                }
             }
-            self.open_connection().map_err(|err| {
-                self.device_state = DeviceState::Error(err.to_string());
-                err
-            })?;
+            self.connect()
+                .await
+                .inspect_err(|err| self.connection_failed(err))?;
         };
         self.device_state = DeviceState::Ok;
         Ok(())
     }
 
+    fn drop_connection(&mut self, err: &DeviceError) {
+        self.close_transport();
+        self.connection_failed(err);
+    }
+
+    fn connection_failed(&mut self, err: &DeviceError) {
+        self.device_state = DeviceState::Error(err.to_string());
+        self.reconnect.connected = false;
+
+        if self.reconnect_policy.exhausted(self.reconnect.attempts) {
+            self.reconnect.gave_up = true;
+            if let GiveUp::Offline = self.reconnect_policy.give_up {
+                self.device_state = DeviceState::Offline;
+            }
+            self.emit_event(DeviceEvent::Connection(ConnectionState::Failed {
+                attempts: self.reconnect.attempts,
+            }));
+            return;
+        }
+
+        // the attempt that just failed is already counted, the first backoff is `initial_delay`
+        let delay = self.reconnect_policy.delay(self.reconnect.attempts.saturating_sub(1));
+        self.reconnect.next_attempt = Some(Instant::now() + delay);
+        self.emit_event(DeviceEvent::Connection(ConnectionState::BackingOff {
+            attempt: self.reconnect.attempts + 1,
+            delay,
+        }));
+    }
+
+    // Only an answer proves the link works, an opened port alone doesn't reset the backoff
+    fn connection_confirmed(&mut self) {
+        if !self.reconnect.connected {
+            self.reconnect.reset();
+            self.reconnect.connected = true;
+            self.emit_event(DeviceEvent::Connection(ConnectionState::Connected));
+        }
+    }
+
     pub async fn fetch_data(&mut self) -> Result<(), DeviceError> {
         self.device_info = self.get_board_info().await?;
+        // before anything else, the next command ends standalone control
+        if self.device_info.supports(GET_CONTROL_MODE_API) {
+            self.get_control_status().await?;
+        }
         self.plugs_values.resize(self.device_config.ports.len(), 0);
         self.plugs_values = self.get_plugs_values().await?;
         self.device_config = self.get_device_config().await?;
+        if self.device_info.supports(GET_TEMPERATURE_INPUTS_API) {
+            let temperature_inputs = self.get_temperature_inputs().await?;
+            if temperature_inputs != self.temperature_inputs {
+                self.temperatures = None;
+            }
+            self.temperature_inputs = temperature_inputs;
+        }
+        if self.device_info.supports(GET_STANDALONE_CURVES_API) {
+            self.standalone_curves = self.get_standalone_curves().await?;
+        }
         Ok(())
     }
 
     async fn write(&mut self, value: &Value) -> Result<(), DeviceError> {
         self.ensure_connection().await?;
-        let serial_port_connection =
-            self.serial_port_connection
-                .as_mut()
-                .ok_or(DeviceError::CustomError(
-                    "Device connection isn't created".to_string(),
-                ))?;
-        if let Err(err) = serial_port_connection.clear(ClearBuffer::All) {
-            self.device_state = DeviceState::Error(err.to_string());
-            self.serial_port_connection.take();
-            return Err(DeviceError::from(err));
-        }
-
-        let mut to_write = serde_json::to_string(value)?;
-        to_write += "\n";
-        serial_port_connection
-            .write_all(to_write.as_bytes())
-            .map_err(|err| {
-                self.device_state = DeviceState::Error(err.to_string());
-                self.serial_port_connection.take();
-                err
-            })?;
-        Ok(())
+
+        let to_write = serde_json::to_string(value)?;
+        self.trace(TraceKind::Write, Some(&to_write));
+        self.transport
+            .write_line(&to_write)
+            .await
+            .inspect_err(|err| {
+                self.trace(TraceKind::WriteError, Some(&err.to_string()));
+                self.drop_connection(err);
+            })
     }
 
-    async fn read<T>(&mut self) -> Result<DeviceResponse<T>, DeviceError>
+    async fn read_line(&mut self, deadline: Instant) -> Result<String, DeviceError> {
+        self.ensure_connection().await?;
+
+        let result = match tokio::time::timeout_at(deadline.into(), self.transport.read_line()).await {
+            Ok(result) => result,
+            Err(_) => Err(DeviceError::Timeout),
+        };
+        match &result {
+            Ok(line) => self.trace(TraceKind::Read, Some(line)),
+            Err(DeviceError::Timeout) => self.trace(TraceKind::Timeout, None),
+            Err(err) => self.trace(TraceKind::ReadError, Some(&err.to_string())),
+        }
+        result.inspect_err(|err| self.drop_connection(err))
+    }
+
+    async fn read<R>(&mut self, id: u32) -> Result<DeviceResponse<R>, DeviceError>
     where
-        T: serde::de::DeserializeOwned,
+        R: serde::de::DeserializeOwned,
     {
-        self.ensure_connection().await?;
-        let serial_port_connection =
-            self.serial_port_connection
-                .as_mut()
-                .ok_or(DeviceError::CustomError(
-                    "Device connection isn't created".to_string(),
-                ))?;
-
-        let mut reader = std::io::BufReader::new(serial_port_connection);
-
-        let mut buf_str = String::new();
-        reader
-            .read_line(&mut buf_str)
-            .map_err(|e| DeviceError::CustomError(e.to_string()))
-            .map_err(|err| {
-                self.device_state = DeviceState::Error(err.to_string());
-                self.serial_port_connection.take();
-                err
-            })?;
-        let device_response: DeviceResponse<T> = serde_json::from_str(buf_str.trim_end())
-            .map_err(|e| DeviceError::CustomError(format!("JSON parse error: {}", e)))?;
-
-        match device_response.code {
-            DeviceCode::Ok => Ok(device_response),
-            DeviceCode::Err => Err(DeviceError::CustomError(
-                device_response
-                    .message
-                    .unwrap_or_else(|| "Unknown error".to_string()),
-            )),
+        let deadline = Instant::now() + self.read_timeout;
+        loop {
+            let buf_str = self.read_line(deadline).await?;
+            let line = buf_str.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Ok(json_line) = serde_json::from_str::<Value>(line) else {
+                // boot banners and other noise, whoever listens decides whether to log them
+                self.emit_event(DeviceEvent::Text(line.to_string()));
+                continue;
+            };
+            let device_response: DeviceResponse<Value> = serde_json::from_value(json_line)
+                .map_err(|e| DeviceError::MalformedResponse(e.to_string()))?;
+
+            if let DeviceCode::Info = device_response.code {
+                let message = device_response.message.unwrap_or_default();
+                // the board runs its standalone curves from here on
+                if message == FAILSAFE_INFO
+                    && let Some(status) = &mut self.control_status
+                {
+                    *status = ControlStatus {
+                        control_mode: ControlMode::Standalone,
+                        mode_time: 0,
+                        ..*status
+                    };
+                }
+                self.emit_event(match message.as_str() {
+                    AFTER_HW_RESET_INFO => DeviceEvent::HardwareReset,
+                    FAILSAFE_INFO => DeviceEvent::Failsafe,
+                    _ => DeviceEvent::Info(message),
+                });
+                continue;
+            }
+
+            // firmware that doesn't echo ids is trusted to answer in order
+            match device_response.id {
+                Some(response_id) if response_id < id => continue, // late answer to a timed out command
+                Some(response_id) if response_id > id => {
+                    return Err(DeviceError::OutOfOrderResponse {
+                        expected: id,
+                        received: response_id,
+                    });
+                }
+                _ => {}
+            }
+            self.connection_confirmed();
+
+            return match device_response.code {
+                DeviceCode::Ok => Ok(DeviceResponse {
+                    id: device_response.id,
+                    code: device_response.code,
+                    message: device_response.message,
+                    data: device_response.data.map(serde_json::from_value).transpose()?,
+                }),
+                DeviceCode::Info | DeviceCode::Err => Err(DeviceError::firmware(
+                    device_response
+                        .message
+                        .unwrap_or_else(|| "Unknown error".to_string()),
+                )),
+            };
         }
     }
 
+    async fn request<R>(&mut self, mut command: Value) -> Result<DeviceResponse<R>, DeviceError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let command_name = command["command"].as_str().unwrap_or_default();
+        if !self.device_info.supports(command_name) {
+            return Err(DeviceError::UnsupportedCommand(command_name.to_string()));
+        }
+        let passive = PASSIVE_COMMANDS.contains(&command_name);
+
+        self.last_request_id = self.last_request_id.wrapping_add(1);
+        let id = self.last_request_id;
+        command["id"] = json!(id);
+
+        self.write(&command).await?;
+        let response = self.read(id).await?;
+        if !passive
+            && let Some(status) = &mut self.control_status
+            && status.control_mode == ControlMode::Standalone
+        {
+            *status = ControlStatus {
+                control_mode: ControlMode::Host,
+                mode_time: 0,
+                standalone_time: Some(status.mode_time),
+            };
+        }
+        Ok(response)
+    }
+
     async fn ping(&mut self) -> Result<bool, DeviceError> {
         let json_command = json!({
             "command": PING_API
         });
-        self.write(&json_command).await?;
-        let res = self.read::<String>().await?.message;
-        if let Some(pong) = res {
-            if pong == PONG_API {
-                return Ok(true);
-            }
-        }
-        Ok(false)
+        let res = self.request::<String>(json_command).await?.message;
+        Ok(res.is_some_and(|pong| pong == PONG_API))
     }
 
     pub async fn test_connection(&mut self, timeout: Duration, interval: Duration) -> bool {
         let start = Instant::now();
         while start.elapsed() < timeout {
-            let response = self.ping().await;
-            if let Ok(ping) = response {
-                if ping {
-                    return true;
-                }
+            if let Ok(true) = self.ping().await {
+                return true;
             }
             tokio::time::sleep(interval).await;
         }
@@ -294,57 +937,124 @@ impl Device {
         let json_command = json!({
             "command": GET_BOARD_INFO_API
         });
-        self.write(&json_command).await?;
-        let device_info = self.read::<DeviceInfo>().await?.data;
+        self.request::<DeviceInfo>(json_command)
+            .await?
+            .data
+            .ok_or(DeviceError::EmptyResponse)
+    }
 
-        if device_info.is_none() {
-            Err(DeviceError::CustomError("Empty data".into()))
+    pub async fn get_plugs_values(&mut self) -> Result<Vec<PortValue>, DeviceError> {
+        let permille = self.supports_permille();
+        let json_command = json!({
+            "command": if permille { GET_PLUGS_VALUES_PERMILLE_API } else { GET_PLUGS_VALUES_API }
+        });
+        let values = self
+            .request::<GetPlugsResponse>(json_command)
+            .await?
+            .data
+            .map(|response| response.values)
+            .ok_or(DeviceError::EmptyResponse)?;
+        if permille {
+            Ok(values)
         } else {
-            Ok(device_info.unwrap())
+            Ok(values.into_iter().map(|percent| percent * PERCENT_SCALE).collect())
         }
     }
 
-    pub async fn get_plugs_values(&mut self) -> Result<Vec<u8>, DeviceError> {
+    pub async fn get_rpm(&mut self) -> Result<Vec<Option<u32>>, DeviceError> {
+        let json_command = json!({
+            "command": GET_RPM_API
+        });
+        self.request::<GetRpmResponse>(json_command)
+            .await?
+            .data
+            .map(|response| response.rpm)
+            .ok_or(DeviceError::EmptyResponse)
+    }
+
+    // The board answers before it drops off the bus, the connection is closed afterwards
+    pub async fn reboot_to_bootloader(&mut self) -> Result<(), DeviceError> {
         let json_command = json!({
-            "command": GET_PLUGS_VALUES_API
+            "command": BOOTLOADER_API
         });
-        self.write(&json_command).await?;
-        let response = self.read::<GetPlugsResponse>().await?.data;
+        self.request::<()>(json_command).await?;
+        self.close_connection();
+        Ok(())
+    }
 
-        if response.is_none() {
-            Err(DeviceError::CustomError("Empty data".into()))
-        } else {
-            Ok(response.unwrap().values)
+    // The stored config is kept, the board comes back on it
+    pub async fn reboot(&mut self) -> Result<(), DeviceError> {
+        let json_command = json!({
+            "command": REBOOT_API
+        });
+        self.request::<()>(json_command).await?;
+        self.reconnect_after_reboot().await
+    }
+
+    // Wipes the stored config like holding the reset button at boot, the board comes back on its defaults
+    pub async fn factory_reset(&mut self) -> Result<(), DeviceError> {
+        let json_command = json!({
+            "command": FACTORY_RESET_API
+        });
+        self.request::<()>(json_command).await?;
+        self.reconnect_after_reboot().await
+    }
+
+    // Blinks the status LED, in whole seconds. The outputs keep running meanwhile
+    pub async fn identify(&mut self, duration: Duration) -> Result<(), DeviceError> {
+        let seconds = duration.as_secs();
+        if seconds == 0 || duration > MAX_IDENTIFY_DURATION {
+            return Err(DeviceError::InvalidArgument(format!(
+                "Identify duration has to be within 1-{} s",
+                MAX_IDENTIFY_DURATION.as_secs()
+            )));
+        }
+
+        let json_command = json!({
+            "command": IDENTIFY_API,
+            "data": vec![seconds]
+        });
+        self.request::<()>(json_command).await?;
+        Ok(())
+    }
+
+    // The board drops off the bus while it restarts, the port is reopened once it answers again.
+    // Reconnect backoff doesn't apply here, the reboot was asked for
+    async fn reconnect_after_reboot(&mut self) -> Result<(), DeviceError> {
+        self.close_connection();
+        let start = Instant::now();
+        loop {
+            tokio::time::sleep(REBOOT_POLL_INTERVAL).await;
+            self.reconnect.reset();
+            if let Ok(true) = self.ping().await {
+                break;
+            }
+            if start.elapsed() >= REBOOT_TIMEOUT {
+                return Err(DeviceError::Disconnected("Board didn't come back after the reboot".into()));
+            }
         }
+        self.fetch_data().await
     }
 
     pub async fn get_device_default_config(&mut self) -> Result<DeviceConfig, DeviceError> {
         let json_command = json!({
             "command": GET_DEFAULT_CONFIG_API
         });
-        self.write(&json_command).await?;
-        let config = self.read::<DeviceConfig>().await?.data;
-
-        if config.is_none() {
-            Err(DeviceError::CustomError("Empty data".into()))
-        } else {
-            Ok(config.unwrap())
-        }
+        self.request::<DeviceConfig>(json_command)
+            .await?
+            .data
+            .ok_or(DeviceError::EmptyResponse)
     }
 
     pub async fn get_device_config(&mut self) -> Result<DeviceConfig, DeviceError> {
         let json_command = json!({
             "command": GET_CONFIG_API
         });
-        self.write(&json_command).await?;
 
-        let config = self.read::<DeviceConfig>().await?.data;
-
-        if config.is_none() {
-            Err(DeviceError::CustomError("Empty data".into()))
-        } else {
-            Ok(config.unwrap())
-        }
+        self.request::<DeviceConfig>(json_command)
+            .await?
+            .data
+            .ok_or(DeviceError::EmptyResponse)
     }
 
     pub async fn set_update_time(&mut self, time: u64) -> Result<(), DeviceError> {
@@ -352,64 +1062,347 @@ impl Device {
             "command": SET_UPDATE_TIME_API,
             "data": vec![time]
         });
-        self.write(&json_command).await?;
-        self.read::<()>().await?;
+        self.request::<()>(json_command).await?;
         self.device_config.update_time = time;
         Ok(())
     }
 
+    pub async fn set_heartbeat_timeout(&mut self, timeout: u64) -> Result<(), DeviceError> {
+        let json_command = json!({
+            "command": SET_HEARTBEAT_TIMEOUT_API,
+            "data": vec![timeout]
+        });
+        self.request::<()>(json_command).await?;
+        self.device_config.heartbeat_timeout = timeout;
+        Ok(())
+    }
+
+    pub async fn heartbeat(&mut self) -> Result<(), DeviceError> {
+        let json_command = json!({
+            "command": HEARTBEAT_API
+        });
+        self.request::<()>(json_command).await?;
+        Ok(())
+    }
+
     pub async fn set_default_value(
         &mut self,
         index: u8,
-        value: PortValue,
+        percent: u8,
     ) -> Result<(), DeviceError> {
         if index < self.device_config.ports.len() as u8 {
             let json_command = json!({
                 "command": SET_PLUG_DEFAULT_VALUE_API,
-                "data": vec![index, value]
+                "data": vec![index, percent]
             });
-            self.write(&json_command).await?;
-            self.read::<()>().await?;
+            self.request::<()>(json_command).await?;
             Ok(())
         } else {
-            Err(DeviceError::CustomError("Incorrect index".into()))
+            Err(DeviceError::InvalidPortIndex(index))
+        }
+    }
+
+    // Whether values go over the wire as permille instead of percent
+    pub fn supports_permille(&self) -> bool {
+        self.device_info.supports(SET_PLUGS_VALUES_PERMILLE_API)
+            && self.device_info.supports(GET_PLUGS_VALUES_PERMILLE_API)
+    }
+
+    // What the board ends up holding for `value`, percent boards drop the last digit
+    fn held_value(&self, value: PortValue) -> PortValue {
+        if self.supports_permille() {
+            value
+        } else {
+            percent_to_value(value_to_percent(value))
         }
     }
 
     pub async fn set_plug_value(&mut self, index: u8, value: PortValue) -> Result<(), DeviceError> {
-        if index < self.device_config.ports.len() as u8 {
-            self.plugs_values[index as usize] = value;
+        if self.supports_permille() {
+            // there is no single-value permille command, the batch one covers it
+            return self
+                .set_plug_values(&[(index, value)])
+                .await?
+                .pop()
+                .unwrap_or(Err(DeviceError::EmptyResponse));
+        }
+        self.send_percent_value(index, value).await
+    }
+
+    async fn send_percent_value(&mut self, index: u8, value: PortValue) -> Result<(), DeviceError> {
+        if index >= self.device_config.ports.len() as u8 {
+            return Err(DeviceError::InvalidPortIndex(index));
+        }
+        if value > MAX_PORT_VALUE {
+            return Err(invalid_value(value));
+        }
+
+        let json_command = json!({
+            "command": SET_PLUG_VALUE_API,
+            "data": vec![index, value_to_percent(value)]
+        });
+        self.request::<()>(json_command).await?;
+        let held_value = self.held_value(value);
+        if let Some(plug_value) = self.plugs_values.get_mut(index as usize) {
+            *plug_value = held_value;
+        }
+        Ok(())
+    }
+
+    // Sends all values in one round-trip, the outer error is for the request itself
+    pub async fn set_plug_values(
+        &mut self,
+        values: &[(u8, PortValue)],
+    ) -> Result<Vec<Result<(), DeviceError>>, DeviceError> {
+        if !self.device_info.supports(SET_PLUGS_VALUES_API) {
+            let mut results = Vec::with_capacity(values.len());
+            for &(index, value) in values {
+                match self.send_percent_value(index, value).await {
+                    Err(err) if matches!(err.kind(), ErrorKind::Firmware | ErrorKind::Validation) => results.push(Err(err)),
+                    Err(err) => return Err(err),
+                    Ok(()) => results.push(Ok(())),
+                }
+            }
+            return Ok(results);
+        }
+
+        let ports_count = self.device_config.ports.len() as u8;
+        let permille = self.supports_permille();
+        let to_send: Vec<(u8, PortValue)> = values
+            .iter()
+            .copied()
+            .filter(|&(index, value)| index < ports_count && value <= MAX_PORT_VALUE)
+            .collect();
+
+        let mut sent_results = Vec::new();
+        if !to_send.is_empty() {
             let json_command = json!({
-                "command": SET_PLUG_VALUE_API,
-                "data": vec![index, value]
+                "command": if permille { SET_PLUGS_VALUES_PERMILLE_API } else { SET_PLUGS_VALUES_API },
+                "data": to_send
+                    .iter()
+                    .flat_map(|&(index, value)| {
+                        [index as PortValue, if permille { value } else { value_to_percent(value) as PortValue }]
+                    })
+                    .collect::<Vec<PortValue>>()
             });
+            sent_results = self
+                .request::<SetPlugsValuesResponse>(json_command)
+                .await?
+                .data
+                .ok_or(DeviceError::EmptyResponse)?
+                .results;
+            if sent_results.len() != to_send.len() {
+                return Err(DeviceError::MalformedResponse(format!(
+                    "{} results for {} values",
+                    sent_results.len(),
+                    to_send.len()
+                )));
+            }
+        }
 
-            self.write(&json_command).await?;
-            self.read::<()>().await?;
-            Ok(())
-        } else {
-            Err(DeviceError::CustomError("Incorrect index".into()))
+        let mut sent_results = sent_results.into_iter();
+        let results = values
+            .iter()
+            .map(|&(index, value)| {
+                if index >= ports_count {
+                    return Err(DeviceError::InvalidPortIndex(index));
+                }
+                if value > MAX_PORT_VALUE {
+                    return Err(invalid_value(value));
+                }
+                match sent_results.next() {
+                    Some(result) if result == OK_RESULT => {
+                        let held_value = self.held_value(value);
+                        if let Some(plug_value) = self.plugs_values.get_mut(index as usize) {
+                            *plug_value = held_value;
+                        }
+                        Ok(())
+                    }
+                    Some(result) => Err(DeviceError::firmware(result)),
+                    None => Err(DeviceError::MalformedResponse("Missing result".into())),
+                }
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    pub fn board_profile(&self) -> BoardProfile {
+        self.board_registry.find(&self.device_info.board_name)
+    }
+
+    // Configs can be written before `fetch_data`, the profile and capabilities need the board info.
+    // The control status is read before the write takes a standalone board over
+    async fn ensure_device_info(&mut self) -> Result<(), DeviceError> {
+        if self.device_info.board_name.is_empty() {
+            self.device_info = self.get_board_info().await?;
+            if self.device_info.supports(GET_CONTROL_MODE_API) {
+                self.get_control_status().await?;
+            }
+            if self.device_info.supports(GET_TEMPERATURE_INPUTS_API) {
+                self.temperature_inputs = self.get_temperature_inputs().await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Ports may not take pins the temperature inputs already use
+    pub fn validate_config(&self, config: &DeviceConfig) -> ConfigReport {
+        self.board_profile().validate_all(
+            &self.device_info,
+            config,
+            &self.temperature_inputs,
+            &self.standalone_curves,
+        )
+    }
+
+    pub async fn get_temperature_inputs(&mut self) -> Result<Vec<TemperatureInput>, DeviceError> {
+        let json_command = json!({
+            "command": GET_TEMPERATURE_INPUTS_API
+        });
+        self.request::<GetTemperatureInputsResponse>(json_command)
+            .await?
+            .data
+            .map(|response| response.temperature_inputs)
+            .ok_or(DeviceError::EmptyResponse)
+    }
+
+    // Stored on the board next to its config, without reloading the outputs
+    pub async fn set_temperature_inputs(&mut self, inputs: &[TemperatureInput]) -> Result<(), DeviceError> {
+        self.ensure_device_info().await?;
+        self.board_profile()
+            .validate_temperature_inputs(&self.device_config, inputs)
+            .into_result()?;
+
+        let json_command = json!({
+            "command": SET_TEMPERATURE_INPUTS_API,
+            "data": vec![serde_json::to_string(inputs)?]
+        });
+        self.request::<()>(json_command).await?;
+        self.temperature_inputs = inputs.to_vec();
+        self.temperatures = None; // the old reading belongs to the old inputs
+        Ok(())
+    }
+
+    pub async fn get_temperatures(&mut self) -> Result<Vec<Option<f32>>, DeviceError> {
+        let json_command = json!({
+            "command": GET_TEMPERATURES_API
+        });
+        let values = self
+            .request::<GetTemperaturesResponse>(json_command)
+            .await?
+            .data
+            .map(|response| response.temperatures)
+            .ok_or(DeviceError::EmptyResponse)?;
+        self.temperatures = Some(TemperatureReading {
+            values: values.clone(),
+            read_at: Instant::now(),
+        });
+        Ok(values)
+    }
+
+    pub fn last_temperatures(&self) -> Option<&TemperatureReading> {
+        self.temperatures.as_ref()
+    }
+
+    pub async fn get_standalone_curves(&mut self) -> Result<Vec<Option<StandaloneCurve>>, DeviceError> {
+        let json_command = json!({
+            "command": GET_STANDALONE_CURVES_API
+        });
+        self.request::<GetStandaloneCurvesResponse>(json_command)
+            .await?
+            .data
+            .map(|response| response.standalone_curves)
+            .ok_or(DeviceError::EmptyResponse)
+    }
+
+    // One entry per port, `None` leaves the port on its default value while the host is away.
+    // The board answers before the write is checked, so the curves are read back afterwards
+    pub async fn set_standalone_curves(&mut self, curves: &[Option<StandaloneCurve>]) -> Result<(), DeviceError> {
+        self.ensure_device_info().await?;
+        self.board_profile()
+            .validate_standalone_curves(&self.device_config, &self.temperature_inputs, curves)
+            .into_result()?;
+
+        let json_command = json!({
+            "command": SET_STANDALONE_CURVES_API,
+            "data": vec![serde_json::to_string(curves)?]
+        });
+        self.request::<()>(json_command).await?;
+        if !self.verify_standalone_curves(curves).await? {
+            return Err(DeviceError::StandaloneCurvesMismatch);
         }
+        Ok(())
+    }
+
+    // Missing trailing entries count as `None`, the board keeps one per port
+    pub async fn verify_standalone_curves(&mut self, expected: &[Option<StandaloneCurve>]) -> Result<bool, DeviceError> {
+        self.standalone_curves = self.get_standalone_curves().await?;
+        let stored = &self.standalone_curves;
+        Ok(expected.len() <= stored.len()
+            && stored.iter().enumerate().all(|(port, curve)| {
+                match (curve, expected.get(port).and_then(Option::as_ref)) {
+                    (Some(curve), Some(expected)) => curve.matches(expected),
+                    (None, None) => true,
+                    _ => false,
+                }
+            }))
+    }
+
+    // Passive, reading it leaves a standalone board standalone
+    pub async fn get_control_status(&mut self) -> Result<ControlStatus, DeviceError> {
+        let json_command = json!({
+            "command": GET_CONTROL_MODE_API
+        });
+        let mut status = self
+            .request::<ControlStatus>(json_command)
+            .await?
+            .data
+            .ok_or(DeviceError::EmptyResponse)?;
+        status.standalone_time = self.control_status.and_then(|status| status.standalone_time);
+        self.control_status = Some(status);
+        Ok(status)
+    }
+
+    pub fn control_status(&self) -> Option<ControlStatus> {
+        self.control_status
     }
 
+    // Nothing is written unless the whole config passes. Port settings are refused up front on
+    // boards that would silently drop them
     pub async fn set_device_config(&mut self, config: &DeviceConfig) -> Result<(), DeviceError> {
+        self.ensure_device_info().await?;
+        self.validate_config(config).into_result()?;
+        if config.has_port_settings() && !self.device_info.supports(PORT_SETTINGS_CAPABILITY) {
+            return Err(DeviceError::UnsupportedFeature("per-port PWM settings".to_string()));
+        }
+
         let json_command = json!({
             "command": SET_DEVICE_CONFIG_API,
             "data": vec![serde_json::to_string(&config)?]
         });
-        self.write(&json_command).await?;
-        self.read::<()>().await?;
+        self.request::<()>(json_command).await?;
         Ok(())
     }
 
     pub async fn set_plugs_config(&mut self, plugs: &[u8]) -> Result<(), DeviceError> {
+        self.ensure_device_info().await?;
+        // the board resets defaults and tach inputs along with the ports
+        let config = DeviceConfig {
+            ports: plugs.to_vec(),
+            default_values: vec![0; plugs.len()],
+            update_time: self.device_config.update_time,
+            ..Default::default()
+        };
+        self.validate_config(&config).into_result()?;
+
         let json_command = json!({
             "command": SET_PLUS_CONFIG_API,
             "data": plugs
         });
-        self.write(&json_command).await?;
-        self.read::<()>().await?;
+        self.request::<()>(json_command).await?;
         self.device_config.ports = plugs.to_vec();
+        self.device_config.tach_inputs.clear(); // the board drops them with the old ports
         self.plugs_values = self.get_plugs_values().await?;
 
         Ok(())
@@ -419,11 +1412,211 @@ impl Device {
         let json_command = json!({
             "command": LOAD_DEFAULT_CONFIG_API
         });
-        self.write(&json_command).await?;
-        self.read::<()>().await?;
+        self.request::<()>(json_command).await?;
         self.device_config = self.get_device_config().await?;
         self.plugs_values = self.get_plugs_values().await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transports::memory_transport::{MemoryPeer, MemoryTransport};
+
+    fn memory_device() -> (Device<MemoryTransport>, MemoryPeer) {
+        let (transport, peer) = MemoryTransport::pair();
+        (Device::with_transport(transport), peer)
+    }
+
+    async fn next_request(peer: &mut MemoryPeer) -> Value {
+        serde_json::from_str(&peer.read_line().await.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn permille_rounds_to_the_nearest_percent() {
+        assert_eq!(value_to_percent(0), 0);
+        assert_eq!(value_to_percent(554), 55);
+        assert_eq!(value_to_percent(555), 56);
+        assert_eq!(value_to_percent(1000), 100);
+        assert_eq!(value_to_percent(1500), 100);
+        assert_eq!(percent_to_value(56), 560);
+    }
+
+    #[tokio::test]
+    async fn values_above_1000_are_refused() {
+        let (mut device, mut peer) = memory_device();
+        device.device_config.ports = vec![0];
+
+        let result = device.set_plug_value(0, 1001).await;
+
+        assert!(matches!(result, Err(DeviceError::InvalidArgument(_))));
+        assert!(peer.try_read_line().is_none());
+    }
+
+    #[tokio::test]
+    async fn backoff_starts_at_initial_delay() {
+        let (mut device, peer) = memory_device();
+        drop(peer); // every open fails from now on
+        device.set_reconnect_policy(ReconnectPolicy {
+            initial_delay: Duration::from_millis(5),
+            jitter: 0.0,
+            ..Default::default()
+        });
+        let mut events = device.subscribe_events();
+
+        assert!(device.get_board_info().await.is_err());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(device.get_board_info().await.is_err());
+
+        let mut delays = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let DeviceEvent::Connection(ConnectionState::BackingOff { delay, .. }) = event {
+                delays.push(delay);
+            }
+        }
+        assert_eq!(delays, vec![Duration::from_millis(5), Duration::from_millis(10)]);
+    }
+
+    #[tokio::test]
+    async fn request_gets_its_response() {
+        let (mut device, mut peer) = memory_device();
+        peer.write_line(r#"{"code":"ok","id":1,"data":{"board_name":"rp2040","max_ports":4,"capabilities":["get_rpm"]}}"#);
+
+        let info = device.get_board_info().await.unwrap();
+        let request = next_request(&mut peer).await;
+
+        assert_eq!(request, json!({"command": "board_info", "id": 1}));
+        assert_eq!(info.board_name, "rp2040");
+        assert_eq!(info.max_ports, 4);
+        assert_eq!(info.capabilities, vec!["get_rpm"]);
+    }
+
+    #[tokio::test]
+    async fn late_responses_are_skipped() {
+        let (mut device, mut peer) = memory_device();
+        peer.write_line(r#"{"code":"ok","id":1,"data":{"board_name":"first","max_ports":1}}"#);
+        device.get_board_info().await.unwrap();
+
+        // an answer to a command that already timed out, then the real one
+        peer.write_line(r#"{"code":"ok","id":1,"data":{"board_name":"late","max_ports":1}}"#);
+        peer.write_line(r#"{"code":"ok","id":2,"data":{"board_name":"second","max_ports":2}}"#);
+        let info = device.get_board_info().await.unwrap();
+
+        assert_eq!(info.board_name, "second");
+        assert_eq!(next_request(&mut peer).await["id"], 1);
+        assert_eq!(next_request(&mut peer).await["id"], 2);
+    }
+
+    #[tokio::test]
+    async fn responses_from_the_future_are_refused() {
+        let (mut device, peer) = memory_device();
+        peer.write_line(r#"{"code":"ok","id":3,"data":{"board_name":"rp2040","max_ports":4}}"#);
+
+        let result = device.get_board_info().await;
+
+        assert!(matches!(
+            result,
+            Err(DeviceError::OutOfOrderResponse { expected: 1, received: 3 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn unsolicited_lines_become_events() {
+        let (mut device, peer) = memory_device();
+        let mut events = device.subscribe_events();
+        peer.write_line(r#"{"code":"info","message":"failsafe"}"#);
+        peer.write_line(r#"{"code":"info","message":"after-hw-reset"}"#);
+        peer.write_line("booting");
+        peer.write_line(r#"{"code":"ok","id":1,"data":{"board_name":"rp2040","max_ports":4}}"#);
+
+        device.get_board_info().await.unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if !matches!(event, DeviceEvent::Connection(_)) {
+                received.push(event);
+            }
+        }
+        assert!(matches!(
+            received.as_slice(),
+            [DeviceEvent::Failsafe, DeviceEvent::HardwareReset, DeviceEvent::Text(text)] if text == "booting"
+        ));
+    }
+
+    #[tokio::test]
+    async fn firmware_errors_carry_their_code() {
+        let (mut device, peer) = memory_device();
+        peer.write_line(r#"{"code":"err","id":1,"message":"bad-args"}"#);
+
+        let Err(error) = device.get_board_info().await else {
+            panic!("the firmware error was swallowed");
+        };
+
+        assert_eq!(error.firmware_code(), Some(FirmwareErrorCode::BadArgs));
+        assert_eq!(error.kind(), ErrorKind::Firmware);
+    }
+
+    #[tokio::test]
+    async fn legacy_boards_get_legacy_commands() {
+        let (mut device, mut peer) = memory_device();
+        // no capabilities reported, like firmware from before protocol versioning
+        device.device_info = DeviceInfo {
+            board_name: "rp2040".to_string(),
+            max_ports: 2,
+            ..Default::default()
+        };
+        peer.write_line(r#"{"code":"ok","id":1,"data":{"values":[40,55]}}"#);
+
+        let values = device.get_plugs_values().await.unwrap();
+
+        assert_eq!(next_request(&mut peer).await["command"], GET_PLUGS_VALUES_API);
+        assert_eq!(values, vec![400, 550]);
+        assert!(device.device_info.supports(SET_PLUG_VALUE_API));
+        assert!(!device.device_info.supports(SET_PLUGS_VALUES_PERMILLE_API));
+        assert!(matches!(
+            device.get_rpm().await,
+            Err(DeviceError::UnsupportedCommand(command)) if command == GET_RPM_API
+        ));
+        assert!(peer.try_read_line().is_none());
+    }
+
+    #[tokio::test]
+    async fn legacy_boards_set_percent_values() {
+        let (mut device, mut peer) = memory_device();
+        device.device_config.ports = vec![0, 2];
+        peer.write_line(r#"{"code":"ok","id":1}"#);
+
+        device.set_plug_value(1, 555).await.unwrap();
+
+        assert_eq!(
+            next_request(&mut peer).await,
+            json!({"command": SET_PLUG_VALUE_API, "id": 1, "data": [1, 56]})
+        );
+    }
+
+    #[tokio::test]
+    async fn capable_boards_get_permille_commands() {
+        let (mut device, mut peer) = memory_device();
+        device.device_info.capabilities = vec![
+            SET_PLUGS_VALUES_API.to_string(),
+            SET_PLUGS_VALUES_PERMILLE_API.to_string(),
+            GET_PLUGS_VALUES_PERMILLE_API.to_string(),
+        ];
+        device.device_config.ports = vec![0, 2];
+        peer.write_line(r#"{"code":"ok","id":1,"data":{"results":["ok"]}}"#);
+
+        let results = device.set_plug_values(&[(1, 555), (5, 100), (0, 1001)]).await.unwrap();
+
+        // only the valid pair reaches the board
+        assert_eq!(
+            next_request(&mut peer).await,
+            json!({"command": SET_PLUGS_VALUES_PERMILLE_API, "id": 1, "data": [1, 555]})
+        );
+        assert!(matches!(
+            results.as_slice(),
+            [Ok(()), Err(DeviceError::InvalidPortIndex(5)), Err(error)] if error.kind() == ErrorKind::Validation
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tokio_serial::{SerialPortInfo, SerialPortType};
+use crate::device::{Device, DeviceError, DeviceInfo, SerialInfo};
+
+// USB descriptors set by the firmware in `setupBoard`
+pub const NJORD_USB_MANUFACTURER: &str = "OLEH CORPORATION";
+pub const NJORD_USB_PRODUCT: &str = "KIBER BIDOSA 3000";
+
+pub const DEFAULT_BAUD_RATE: u32 = 115200;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiscoveryOptions {
+    pub baud_rate: u32,
+    pub probe_timeout: Duration,
+    // `None` probes every serial port, not just the ones with Njord USB descriptors
+    pub usb_product: Option<String>,
+    // checked along with `usb_product`, `None` accepts any manufacturer
+    #[serde(default)]
+    pub usb_manufacturer: Option<String>,
+    // ports other devices already hold, probing them would steal their responses
+    #[serde(default)]
+    pub exclude_ports: HashSet<String>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            baud_rate: DEFAULT_BAUD_RATE,
+            probe_timeout: Duration::from_millis(800),
+            usb_product: Some(NJORD_USB_PRODUCT.to_string()),
+            usb_manufacturer: Some(NJORD_USB_MANUFACTURER.to_string()),
+            exclude_ports: HashSet::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiscoveredDevice {
+    pub serial_info: SerialInfo,
+    pub device_info: DeviceInfo,
+}
+
+pub async fn discover_devices(options: &DiscoveryOptions) -> Result<Vec<DiscoveredDevice>, DeviceError> {
+    let candidates = tokio_serial::available_ports()?
+        .into_iter()
+        .filter(|port| matches_descriptors(port, options))
+        .map(|port| SerialInfo {
+            com_port: port.port_name,
+            baud_rate: options.baud_rate,
+        });
+
+    let probes = candidates.map(|serial_info| async move {
+        let device_info = probe_port(serial_info.clone(), options).await?;
+        Some(DiscoveredDevice {
+            serial_info,
+            device_info,
+        })
+    });
+
+    Ok(join_all(probes).await.into_iter().flatten().collect())
+}
+
+// A product string alone is easy to collide with, the manufacturer has to match too
+fn matches_descriptors(port: &SerialPortInfo, options: &DiscoveryOptions) -> bool {
+    let Some(usb_product) = &options.usb_product else {
+        return true;
+    };
+    let SerialPortType::UsbPort(usb_info) = &port.port_type else {
+        return false;
+    };
+    let matches = |descriptor: &Option<String>, expected: &str| {
+        descriptor.as_ref().is_some_and(|descriptor| descriptor.eq_ignore_ascii_case(expected))
+    };
+    matches(&usb_info.product, usb_product)
+        && options
+            .usb_manufacturer
+            .as_ref()
+            .is_none_or(|usb_manufacturer| matches(&usb_info.manufacturer, usb_manufacturer))
+}
+
+// Confirms that a Njord board answers on the port, `None` for anything else and for excluded ports
+pub async fn probe_port(serial_info: SerialInfo, options: &DiscoveryOptions) -> Option<DeviceInfo> {
+    if options.exclude_ports.contains(&serial_info.com_port) {
+        return None;
+    }
+    let timeout = options.probe_timeout;
+    let mut device = Device::new(serial_info);
+    device.set_read_timeout(timeout);
+
+    let probe = async {
+        if !device.test_connection(timeout, Duration::from_millis(50)).await {
+            return None;
+        }
+        device.get_board_info().await.ok()
+    };
+    tokio::time::timeout(timeout * 2, probe).await.ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_serial::UsbPortInfo;
+    use super::*;
+
+    fn usb_port(name: &str, manufacturer: Option<&str>, product: Option<&str>) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid: 0x2e8a,
+                pid: 0x000a,
+                serial_number: None,
+                manufacturer: manufacturer.map(str::to_string),
+                product: product.map(str::to_string),
+            }),
+        }
+    }
+
+    fn ports() -> Vec<SerialPortInfo> {
+        vec![
+            usb_port("njord", Some(NJORD_USB_MANUFACTURER), Some(NJORD_USB_PRODUCT)),
+            usb_port("lowercase", Some("oleh corporation"), Some("kiber bidosa 3000")),
+            usb_port("other_manufacturer", Some("Raspberry Pi"), Some(NJORD_USB_PRODUCT)),
+            usb_port("no_manufacturer", None, Some(NJORD_USB_PRODUCT)),
+            usb_port("other_product", Some(NJORD_USB_MANUFACTURER), Some("Pico")),
+            SerialPortInfo {
+                port_name: "builtin".to_string(),
+                port_type: SerialPortType::PciPort,
+            },
+        ]
+    }
+
+    fn matching(options: &DiscoveryOptions) -> Vec<String> {
+        ports()
+            .into_iter()
+            .filter(|port| matches_descriptors(port, options))
+            .map(|port| port.port_name)
+            .collect()
+    }
+
+    #[test]
+    fn only_njord_descriptors_match() {
+        assert_eq!(matching(&DiscoveryOptions::default()), vec!["njord", "lowercase"]);
+    }
+
+    #[test]
+    fn manufacturer_can_be_left_out() {
+        let options = DiscoveryOptions {
+            usb_manufacturer: None,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            matching(&options),
+            vec!["njord", "lowercase", "other_manufacturer", "no_manufacturer"]
+        );
+    }
+
+    #[test]
+    fn no_product_matches_every_port() {
+        let options = DiscoveryOptions {
+            usb_product: None,
+            ..Default::default()
+        };
+
+        assert_eq!(matching(&options).len(), ports().len());
+    }
+}
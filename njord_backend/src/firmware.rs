@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::device::{Device, DeviceError, SerialInfo};
+use crate::discovery::{DiscoveredDevice, DiscoveryOptions, discover_devices, probe_port};
+
+// Every RP2040 UF2 bootloader volume carries this file
+pub const UF2_INFO_FILE: &str = "INFO_UF2.TXT";
+const UF2_MAGIC: [u8; 4] = *b"UF2\n";
+const UF2_TARGET_NAME: &str = "NJORD.UF2";
+
+// Opening the port at this baud rate makes the arduino core jump into the bootloader
+pub const BOOTLOADER_TOUCH_BAUD_RATE: u32 = 1200;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
+pub struct FirmwareUpdateOptions {
+    // Used instead of searching the mounted volumes, e.g. a plain directory in tests.
+    // It still has to contain `INFO_UF2.TXT` to count as the bootloader volume
+    pub volume_dir: Option<PathBuf>,
+    // `firmware_version` the board has to report after the update, anything goes when `None`
+    pub expected_version: Option<String>,
+    pub volume_timeout: Duration,
+    pub reboot_timeout: Duration,
+    pub discovery: DiscoveryOptions,
+}
+
+impl Default for FirmwareUpdateOptions {
+    fn default() -> Self {
+        Self {
+            volume_dir: None,
+            expected_version: None,
+            volume_timeout: Duration::from_secs(15),
+            reboot_timeout: Duration::from_secs(20),
+            discovery: DiscoveryOptions::default(),
+        }
+    }
+}
+
+// Reboots the board into the bootloader, flashes the image and waits for the board to answer again.
+// The board can come back on another port, the returned serial info is the one to reconnect to
+pub async fn update_firmware(
+    device: &mut Device,
+    image: &Path,
+    options: &FirmwareUpdateOptions,
+) -> Result<DiscoveredDevice, DeviceError> {
+    check_uf2_image(image)?;
+    let serial_info = device.create_summary().serial_info;
+    let device_uid = device.device_info.device_uid.clone();
+
+    reboot_to_bootloader(device).await?;
+    let volume = wait_for_uf2_volume(options).await?;
+
+    let image = image.to_path_buf();
+    let target = volume.join(UF2_TARGET_NAME);
+    tokio::task::spawn_blocking(move || std::fs::copy(image, target))
+        .await
+        .map_err(|e| DeviceError::UpdateFailed(e.to_string()))??;
+
+    wait_for_board(serial_info, device_uid, options).await
+}
+
+pub async fn reboot_to_bootloader(device: &mut Device) -> Result<(), DeviceError> {
+    match device.reboot_to_bootloader().await {
+        Err(DeviceError::UnsupportedCommand(_)) => {
+            device.close_connection();
+            touch_1200_baud(&device.create_summary().serial_info.com_port).await
+        }
+        result => result,
+    }
+}
+
+pub async fn touch_1200_baud(com_port: &str) -> Result<(), DeviceError> {
+    let builder = tokio_serial::new(com_port, BOOTLOADER_TOUCH_BAUD_RATE);
+    let port = tokio_serial::SerialStream::open(&builder)?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drop(port);
+    Ok(())
+}
+
+pub fn check_uf2_image(image: &Path) -> Result<(), DeviceError> {
+    let mut magic = [0u8; 4];
+    let mut file = std::fs::File::open(image)?;
+    std::io::Read::read_exact(&mut file, &mut magic)?;
+    if magic != UF2_MAGIC {
+        return Err(DeviceError::InvalidArgument(format!("{} is not a UF2 image", image.display())));
+    }
+    Ok(())
+}
+
+pub async fn wait_for_uf2_volume(options: &FirmwareUpdateOptions) -> Result<PathBuf, DeviceError> {
+    let start = Instant::now();
+    loop {
+        let volume = match &options.volume_dir {
+            Some(volume_dir) => is_uf2_volume(volume_dir).then(|| volume_dir.clone()),
+            None => find_uf2_volume(),
+        };
+        if let Some(volume) = volume {
+            return Ok(volume);
+        }
+        if start.elapsed() >= options.volume_timeout {
+            return Err(DeviceError::UpdateFailed("UF2 volume didn't show up".into()));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+pub fn find_uf2_volume() -> Option<PathBuf> {
+    volume_candidates()
+        .into_iter()
+        .find(|volume| is_uf2_volume(volume))
+}
+
+pub fn is_uf2_volume(path: &Path) -> bool {
+    path.join(UF2_INFO_FILE).is_file()
+}
+
+fn volume_candidates() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        return ('D'..='Z').map(|letter| PathBuf::from(format!("{}:\\", letter))).collect();
+    }
+
+    let mut roots = vec![PathBuf::from("/Volumes"), PathBuf::from("/media")];
+    if let Ok(user) = std::env::var("USER") {
+        roots.push(Path::new("/media").join(&user));
+        roots.push(Path::new("/run/media").join(&user));
+    }
+    roots
+        .iter()
+        .filter_map(|root| std::fs::read_dir(root).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .collect()
+}
+
+// Checks the port the board was on first, then looks for its id on the other ports
+async fn wait_for_board(
+    serial_info: SerialInfo,
+    device_uid: Option<String>,
+    options: &FirmwareUpdateOptions,
+) -> Result<DiscoveredDevice, DeviceError> {
+    let start = Instant::now();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let mut found = probe_port(serial_info.clone(), &options.discovery)
+            .await
+            .filter(|device_info| device_uid.is_none() || device_info.device_uid == device_uid)
+            .map(|device_info| DiscoveredDevice {
+                serial_info: serial_info.clone(),
+                device_info,
+            });
+        if found.is_none() && device_uid.is_some() {
+            found = discover_devices(&options.discovery)
+                .await?
+                .into_iter()
+                .find(|device| device.device_info.device_uid == device_uid);
+        }
+
+        if let Some(found) = found {
+            return match &options.expected_version {
+                Some(expected) if found.device_info.firmware_version.as_ref() != Some(expected) => {
+                    Err(DeviceError::UpdateFailed(format!(
+                        "Board came back with firmware {}, expected {}",
+                        found.device_info.firmware_version.as_deref().unwrap_or("unknown"),
+                        expected
+                    )))
+                }
+                _ => Ok(found),
+            };
+        }
+        if start.elapsed() >= options.reboot_timeout {
+            return Err(DeviceError::UpdateFailed("Board didn't come back after the update".into()));
+        }
+    }
+}
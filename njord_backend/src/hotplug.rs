@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use tokio_serial::SerialPortType;
+use crate::device::DeviceError;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PortSnapshot {
+    pub name: String,
+    pub usb_serial_number: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub enum PortEvent {
+    Added(PortSnapshot),
+    Removed(PortSnapshot),
+}
+
+// Diffs the serial port list between polls, the caller decides how often to poll
+#[derive(Default)]
+pub struct PortWatcher {
+    known_ports: HashMap<String, PortSnapshot>,
+}
+
+impl PortWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list_ports() -> Result<Vec<PortSnapshot>, DeviceError> {
+        Ok(tokio_serial::available_ports()?
+            .into_iter()
+            .map(|port| PortSnapshot {
+                usb_serial_number: match port.port_type {
+                    SerialPortType::UsbPort(usb_info) => usb_info.serial_number,
+                    _ => None,
+                },
+                name: port.port_name,
+            })
+            .collect())
+    }
+
+    // The port is reported as added again on the next poll
+    pub fn forget(&mut self, name: &str) {
+        self.known_ports.remove(name);
+    }
+
+    // The first poll reports every present port as added
+    pub fn poll(&mut self) -> Result<Vec<PortEvent>, DeviceError> {
+        let current_ports: HashMap<String, PortSnapshot> = Self::list_ports()?
+            .into_iter()
+            .map(|port| (port.name.clone(), port))
+            .collect();
+
+        let mut events = Vec::new();
+        for (name, port) in &self.known_ports {
+            if current_ports.get(name) != Some(port) {
+                events.push(PortEvent::Removed(port.clone()));
+            }
+        }
+        for (name, port) in &current_ports {
+            if self.known_ports.get(name) != Some(port) {
+                events.push(PortEvent::Added(port.clone()));
+            }
+        }
+
+        self.known_ports = current_ports;
+        Ok(events)
+    }
+}
@@ -1,4 +1,13 @@
+pub mod actor;
+pub mod board;
 pub mod device;
 pub mod sensors;
 pub mod sensors_providers;
-pub mod controller;
\ No newline at end of file
+pub mod controller;
+pub mod discovery;
+pub mod firmware;
+pub mod hotplug;
+pub mod reconnect;
+pub mod transport;
+pub mod trace;
+pub mod transports;
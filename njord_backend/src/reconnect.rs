@@ -0,0 +1,147 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum GiveUp {
+    Offline, // the device is marked offline and waits to be reattached
+    Error,   // the device keeps its error and refuses requests until `open_connection` is called
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    pub jitter: f64, // share of the delay, 0.2 spreads it over ±20%
+    pub max_attempts: Option<u32>, // None keeps trying forever
+    pub give_up: GiveUp,
+    pub ping_timeout: Duration,
+    pub ping_interval: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+            give_up: GiveUp::Offline,
+            ping_timeout: Duration::from_millis(800),
+            ping_interval: Duration::from_millis(150),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    // Delay before the next attempt once `failures` attempts in a row went wrong
+    pub fn delay(&self, failures: u32) -> Duration {
+        let base = self.initial_delay.as_secs_f64() * self.multiplier.max(1.0).powi(failures as i32);
+        let base = base.min(self.max_delay.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * (random_unit() * 2.0 - 1.0);
+        Duration::from_secs_f64((base * (1.0 + jitter)).max(0.0))
+    }
+
+    pub fn exhausted(&self, attempts: u32) -> bool {
+        self.max_attempts.is_some_and(|max_attempts| attempts >= max_attempts)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub enum ConnectionState {
+    Connecting { attempt: u32 },
+    Connected,
+    BackingOff { attempt: u32, delay: Duration },
+    Failed { attempts: u32 },
+}
+
+// Where the device is in its reconnect cycle
+#[derive(Default)]
+pub(crate) struct ReconnectState {
+    pub attempts: u32,
+    pub next_attempt: Option<Instant>,
+    pub connected: bool,
+    pub gave_up: bool,
+}
+
+impl ReconnectState {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// Good enough for jitter without pulling a rng in, every RandomState is seeded differently
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(Instant::now().elapsed().as_nanos() as u64);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steady_policy() -> ReconnectPolicy {
+        ReconnectPolicy {
+            jitter: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn delay_grows_by_the_multiplier() {
+        let policy = steady_policy();
+
+        let delays: Vec<Duration> = (0..4).map(|failures| policy.delay(failures)).collect();
+
+        assert_eq!(delays, [500, 1000, 2000, 4000].map(Duration::from_millis));
+    }
+
+    #[test]
+    fn delay_stops_at_max_delay() {
+        let policy = steady_policy();
+
+        assert_eq!(policy.delay(6), Duration::from_secs(30));
+        assert_eq!(policy.delay(1000), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn delay_never_shrinks_below_multiplier_one() {
+        let policy = ReconnectPolicy {
+            multiplier: 0.5,
+            ..steady_policy()
+        };
+
+        assert_eq!(policy.delay(3), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_within_its_share() {
+        let policy = ReconnectPolicy {
+            jitter: 0.2,
+            ..Default::default()
+        };
+
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!((Duration::from_millis(800)..=Duration::from_millis(1200)).contains(&delay));
+        }
+    }
+
+    #[test]
+    fn attempts_run_out_at_max_attempts() {
+        let policy = ReconnectPolicy {
+            max_attempts: Some(3),
+            ..Default::default()
+        };
+
+        assert!(!policy.exhausted(2));
+        assert!(policy.exhausted(3));
+        assert!(policy.exhausted(4));
+        assert!(!ReconnectPolicy::default().exhausted(u32::MAX));
+    }
+}
@@ -12,6 +12,7 @@ pub enum SensorType {
     LhmSensor,
     SysInfoSensor,
     NvmlSensor,
+    DeviceSensor, // temperature inputs of a board, see `DeviceSensor::get_sensors`
 }
 
 pub struct SensorFactory{}
@@ -32,13 +33,16 @@ impl SensorFactory {
                 let sensor = NvmlSensor::new(sensors_providers_state, identifier)?;
                 Ok(sensor as Arc<dyn Sensor>)
             }
+            SensorType::DeviceSensor => Err("Device sensors come with their device".to_string()),
         }
     }
     pub fn get_sensors_names_by_type(sensor_type: SensorType, sensors_providers_state: &SensorsProvidersStates) -> Result<Vec<String>, String>{
         match sensor_type {
+            #[cfg(target_os = "windows")]
             SensorType::LhmSensor => LhmSensor::get_sensors(sensors_providers_state),
             SensorType::SysInfoSensor => SysInfoSensor::get_sensors(sensors_providers_state),
             SensorType::NvmlSensor => NvmlSensor::get_sensors(sensors_providers_state),
+            SensorType::DeviceSensor => Ok(Vec::new()),
         }
     }
     pub fn get_all_sensors(sensors_providers_state: &SensorsProvidersStates) -> HashMap<SensorType, HashMap<String, Arc<dyn Sensor>>>{
@@ -68,6 +72,7 @@ impl SensorFactory {
                 sensors.insert(SensorType::SysInfoSensor, sys_info_sensors);
             }
         };
+        #[cfg(target_os = "windows")]
         {
             let mut lhm_sys_sensors: HashMap<String, Arc<dyn Sensor>> = HashMap::new();
             Self::get_sensors_names_by_type(SensorType::LhmSensor, sensors_providers_state).unwrap_or_default().iter().for_each(|name| {
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use crate::actor::{DeviceHandle, DeviceSnapshot};
+use crate::sensors::{Sensor, SensorId, SensorType};
+
+// whoever drives the device reads it about every second, anything older means nobody does anymore
+const MAX_READING_AGE: Duration = Duration::from_secs(10);
+
+// A temperature input of a board, read from the last `get_temperatures` reply of its device
+pub struct DeviceSensor {
+    sensor_type: SensorType,
+    identifier: String,
+    input_name: String,
+    snapshot: watch::Receiver<DeviceSnapshot>,
+}
+
+impl DeviceSensor {
+    pub fn new(device_id: &str, input_name: String, device: &DeviceHandle) -> Arc<Self> {
+        Arc::new(Self {
+            sensor_type: SensorType::DeviceSensor,
+            identifier: Self::identifier(device_id, &input_name),
+            input_name,
+            snapshot: device.subscribe_snapshot(),
+        })
+    }
+
+    pub fn identifier(device_id: &str, input_name: &str) -> String {
+        format!("{}/{}", device_id, input_name)
+    }
+
+    pub fn belongs_to(identifier: &str, device_id: &str) -> bool {
+        identifier
+            .strip_prefix(device_id)
+            .is_some_and(|input_name| input_name.starts_with('/'))
+    }
+
+    pub fn get_sensors(device_id: &str, device: &DeviceHandle) -> Vec<Arc<Self>> {
+        device
+            .create_summary()
+            .temperature_inputs
+            .into_iter()
+            .map(|input| Self::new(device_id, input.name, device))
+            .collect()
+    }
+}
+
+impl Sensor for DeviceSensor {
+    fn get_temperature(&self) -> Result<f32, String> {
+        let snapshot = self.snapshot.borrow();
+        let index = snapshot
+            .summary
+            .temperature_inputs
+            .iter()
+            .position(|input| input.name == self.input_name)
+            .ok_or(format!("{} is no longer a temperature input of the board", self.input_name))?;
+        let reading = snapshot
+            .temperatures
+            .as_ref()
+            .filter(|reading| reading.read_at.elapsed() < MAX_READING_AGE)
+            .ok_or(format!("{} has no recent reading", self.identifier))?;
+        reading
+            .values
+            .get(index)
+            .copied()
+            .flatten()
+            .ok_or(format!("{} Failed to get temperature", self.identifier))
+    }
+    fn get_sensor_id(&self) -> SensorId {
+        SensorId {
+            sensor_type: self.sensor_type.clone(),
+            identifier: self.identifier.clone()
+        }
+    }
+}
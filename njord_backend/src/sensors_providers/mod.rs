@@ -1,3 +1,4 @@
+pub mod device_sensor;
 pub mod nvml_sensor;
 pub mod sys_info_sensor;
 
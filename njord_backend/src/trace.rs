@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::device::DeviceError;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    Open,
+    OpenError,
+    Write,
+    WriteError,
+    Read,
+    ReadError,
+    Timeout,
+    Close,
+}
+
+// One line of a trace file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TraceEntry {
+    pub at_us: u64,   // since the recording started
+    pub time_ms: u64, // unix time, to line it up with user reports
+    pub kind: TraceKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>, // the line, or the error message
+}
+
+// Every line is flushed right away, so the trace survives the app going down with the device
+pub struct TraceRecorder {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+impl TraceRecorder {
+    pub fn create(path: &Path) -> Result<Self, DeviceError> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+        })
+    }
+
+    pub(crate) fn record(&mut self, kind: TraceKind, data: Option<&str>) {
+        let entry = TraceEntry {
+            at_us: self.started.elapsed().as_micros() as u64,
+            time_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_millis() as u64)
+                .unwrap_or_default(),
+            kind,
+            data: data.map(str::to_string),
+        };
+        // a trace is best effort, it never fails the command it describes
+        let written = serde_json::to_writer(&mut self.writer, &entry)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"))
+            .and_then(|_| self.writer.flush());
+        if let Err(e) = written {
+            eprintln!("Failed writing trace ({})", e);
+        }
+    }
+}
+
+pub fn read_trace(path: &Path) -> Result<Vec<TraceEntry>, DeviceError> {
+    let mut entries = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line)?);
+    }
+    Ok(entries)
+}
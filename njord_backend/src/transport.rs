@@ -0,0 +1,13 @@
+use std::future::Future;
+use crate::device::DeviceError;
+
+// upper bound for one protocol line, anything longer is treated as garbage
+pub const MAX_LINE_LENGTH: usize = 4096;
+
+pub trait Transport: Send {
+    fn open(&mut self) -> impl Future<Output = Result<(), DeviceError>> + Send;
+    fn write_line(&mut self, line: &str) -> impl Future<Output = Result<(), DeviceError>> + Send;
+    fn read_line(&mut self) -> impl Future<Output = Result<String, DeviceError>> + Send;
+    fn close(&mut self);
+    fn is_open(&self) -> bool;
+}
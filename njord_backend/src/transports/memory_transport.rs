@@ -0,0 +1,88 @@
+use tokio::sync::mpsc;
+use crate::device::DeviceError;
+use crate::transport::Transport;
+
+// In-memory link to a fake board, mostly useful for driving `Device` from tests
+pub struct MemoryTransport {
+    to_peer: mpsc::UnboundedSender<String>,
+    from_peer: mpsc::UnboundedReceiver<String>,
+    is_open: bool,
+}
+
+pub struct MemoryPeer {
+    to_device: mpsc::UnboundedSender<String>,
+    from_device: mpsc::UnboundedReceiver<String>,
+}
+
+impl MemoryTransport {
+    pub fn pair() -> (MemoryTransport, MemoryPeer) {
+        let (to_peer, from_device) = mpsc::unbounded_channel();
+        let (to_device, from_peer) = mpsc::unbounded_channel();
+        (
+            MemoryTransport {
+                to_peer,
+                from_peer,
+                is_open: false,
+            },
+            MemoryPeer {
+                to_device,
+                from_device,
+            },
+        )
+    }
+
+    fn check_open(&self) -> Result<(), DeviceError> {
+        if self.is_open {
+            Ok(())
+        } else {
+            Err(DeviceError::Disconnected("Device connection isn't created".to_string()))
+        }
+    }
+}
+
+impl Transport for MemoryTransport {
+    async fn open(&mut self) -> Result<(), DeviceError> {
+        if self.to_peer.is_closed() {
+            return Err(DeviceError::Disconnected("Memory peer is dropped".to_string()));
+        }
+        self.is_open = true;
+        Ok(())
+    }
+
+    async fn write_line(&mut self, line: &str) -> Result<(), DeviceError> {
+        self.check_open()?;
+        self.to_peer
+            .send(line.to_string())
+            .map_err(|_| DeviceError::Disconnected("Memory peer is dropped".to_string()))
+    }
+
+    async fn read_line(&mut self) -> Result<String, DeviceError> {
+        self.check_open()?;
+        self.from_peer
+            .recv()
+            .await
+            .ok_or(DeviceError::Disconnected("Memory peer is dropped".to_string()))
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+}
+
+impl MemoryPeer {
+    pub async fn read_line(&mut self) -> Option<String> {
+        self.from_device.recv().await
+    }
+
+    pub fn try_read_line(&mut self) -> Option<String> {
+        self.from_device.try_recv().ok()
+    }
+
+    pub fn write_line(&self, line: &str) -> bool {
+        self.to_device.send(line.to_string()).is_ok()
+    }
+}
@@ -0,0 +1,4 @@
+pub mod memory_transport;
+pub mod serial_transport;
+pub mod tcp_transport;
+pub mod replay_transport;
@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::Instant;
+use crate::device::DeviceError;
+use crate::trace::{TraceEntry, TraceKind, read_trace};
+use crate::transport::Transport;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplayTiming {
+    Recorded,  // lines arrive with the gaps they had on the wire
+    Immediate, // lines arrive as soon as they are read
+}
+
+// Plays a recorded trace back to `Device`. Recorded timeouts are replayed by never answering,
+// so `Device` runs into its own read timeout just like it did back then
+pub struct ReplayTransport {
+    entries: VecDeque<TraceEntry>,
+    timing: ReplayTiming,
+    strict: bool,
+    is_open: bool,
+    last_entry: Option<(u64, Instant)>,
+}
+
+impl ReplayTransport {
+    pub fn new(entries: Vec<TraceEntry>, timing: ReplayTiming) -> Self {
+        Self {
+            entries: entries.into(),
+            timing,
+            strict: false,
+            is_open: false,
+            last_entry: None,
+        }
+    }
+
+    pub fn from_file(path: &Path, timing: ReplayTiming) -> Result<Self, DeviceError> {
+        Ok(Self::new(read_trace(path)?, timing))
+    }
+
+    // Fail on writes that differ from the recorded ones instead of just consuming them
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn peek(&mut self) -> Option<&TraceEntry> {
+        // closes are decided by `Device`, they are only there for whoever reads the trace
+        while self.entries.front().is_some_and(|entry| entry.kind == TraceKind::Close) {
+            self.entries.pop_front();
+        }
+        self.entries.front()
+    }
+
+    fn take(&mut self, kind: TraceKind) -> Option<TraceEntry> {
+        if self.peek()?.kind != kind {
+            return None;
+        }
+        let entry = self.entries.pop_front()?;
+        self.last_entry = Some((entry.at_us, Instant::now()));
+        Some(entry)
+    }
+
+    async fn wait_for(&mut self, entry: &TraceEntry) {
+        if self.timing == ReplayTiming::Immediate {
+            return;
+        }
+        if let Some((last_at_us, last_instant)) = self.last_entry {
+            let gap = Duration::from_micros(entry.at_us.saturating_sub(last_at_us));
+            tokio::time::sleep_until(last_instant + gap).await;
+        }
+        self.last_entry = Some((entry.at_us, Instant::now()));
+    }
+
+    fn check_open(&self) -> Result<(), DeviceError> {
+        if self.is_open {
+            Ok(())
+        } else {
+            Err(DeviceError::Disconnected("Device connection isn't created".to_string()))
+        }
+    }
+}
+
+fn recorded_error(entry: TraceEntry) -> DeviceError {
+    DeviceError::Disconnected(entry.data.unwrap_or_else(|| "Recorded error".to_string()))
+}
+
+impl Transport for ReplayTransport {
+    async fn open(&mut self) -> Result<(), DeviceError> {
+        if self.take(TraceKind::Open).is_none() {
+            return Err(DeviceError::Disconnected("Trace has no more connections".to_string()));
+        }
+        if let Some(error) = self.take(TraceKind::OpenError) {
+            return Err(recorded_error(error));
+        }
+        self.is_open = true;
+        Ok(())
+    }
+
+    async fn write_line(&mut self, line: &str) -> Result<(), DeviceError> {
+        self.check_open()?;
+        let Some(recorded) = self.take(TraceKind::Write) else {
+            return Err(DeviceError::Disconnected("Trace has no write here".to_string()));
+        };
+        if self.strict && recorded.data.as_deref() != Some(line) {
+            return Err(DeviceError::InvalidArgument(format!(
+                "Replay expected `{}`, got `{}`",
+                recorded.data.unwrap_or_default(),
+                line
+            )));
+        }
+        match self.take(TraceKind::WriteError) {
+            Some(error) => Err(recorded_error(error)),
+            None => Ok(()),
+        }
+    }
+
+    async fn read_line(&mut self) -> Result<String, DeviceError> {
+        self.check_open()?;
+        let Some(entry) = self.peek().cloned() else {
+            return std::future::pending().await;
+        };
+        match entry.kind {
+            TraceKind::Read | TraceKind::ReadError => {
+                // read timeouts cancel this future, the line has to stay until it is really read
+                self.wait_for(&entry).await;
+                self.entries.pop_front();
+                match entry.kind {
+                    TraceKind::Read => Ok(entry.data.unwrap_or_default()),
+                    _ => Err(recorded_error(entry)),
+                }
+            }
+            TraceKind::Timeout => {
+                self.entries.pop_front();
+                std::future::pending().await
+            }
+            // the board said nothing more before the host spoke again
+            _ => std::future::pending().await,
+        }
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::device::Device;
+    use crate::transports::memory_transport::MemoryTransport;
+
+    const BOARD_INFO: &str = r#"{"code":"ok","id":1,"data":{"board_name":"rp2040","max_ports":4}}"#;
+
+    // Records one `board_info` exchange the way the app does it
+    async fn record_session(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("njord_{}_{}.jsonl", name, std::process::id()));
+        let (transport, peer) = MemoryTransport::pair();
+        let mut device = Device::with_transport(transport);
+        device.start_trace(&path).unwrap();
+        peer.write_line(BOARD_INFO);
+        device.get_board_info().await.unwrap();
+        device.stop_trace();
+        path
+    }
+
+    #[tokio::test]
+    async fn recorded_session_replays() {
+        let path = record_session("replay").await;
+        let entries = read_trace(&path).unwrap();
+        let kinds: Vec<TraceKind> = entries.iter().map(|entry| entry.kind).collect();
+        assert_eq!(kinds, vec![TraceKind::Open, TraceKind::Write, TraceKind::Read]);
+        assert_eq!(entries[2].data.as_deref(), Some(BOARD_INFO));
+
+        let transport = ReplayTransport::from_file(&path, ReplayTiming::Immediate).unwrap().strict(true);
+        let mut device = Device::with_transport(transport);
+        let info = device.get_board_info().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(info.board_name, "rp2040");
+        assert_eq!(info.max_ports, 4);
+    }
+
+    #[tokio::test]
+    async fn strict_replay_refuses_other_requests() {
+        let path = record_session("strict").await;
+
+        let transport = ReplayTransport::from_file(&path, ReplayTiming::Immediate).unwrap().strict(true);
+        let mut device = Device::with_transport(transport);
+        let result = device.get_device_config().await;
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(result, Err(DeviceError::InvalidArgument(_))));
+    }
+}
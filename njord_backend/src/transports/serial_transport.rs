@@ -0,0 +1,73 @@
+use futures::{SinkExt, StreamExt};
+use tokio_serial::{ClearBuffer, FlowControl, SerialPort, SerialPortBuilderExt, SerialStream};
+use tokio_util::codec::{Framed, LinesCodec};
+use crate::device::{DeviceError, SerialInfo};
+use crate::transport::{MAX_LINE_LENGTH, Transport};
+
+pub struct SerialTransport {
+    serial_info: SerialInfo,
+    connection: Option<Framed<SerialStream, LinesCodec>>,
+}
+
+impl SerialTransport {
+    pub fn new(serial_info: SerialInfo) -> Self {
+        Self {
+            serial_info,
+            connection: None,
+        }
+    }
+
+    pub fn serial_info(&self) -> &SerialInfo {
+        &self.serial_info
+    }
+
+    fn connection(&mut self) -> Result<&mut Framed<SerialStream, LinesCodec>, DeviceError> {
+        self.connection.as_mut().ok_or(DeviceError::Disconnected(
+            "Device connection isn't created".to_string(),
+        ))
+    }
+}
+
+impl Transport for SerialTransport {
+    async fn open(&mut self) -> Result<(), DeviceError> {
+        self.connection.take();
+        let mut connection = tokio_serial::new(
+            self.serial_info.com_port.clone(),
+            self.serial_info.baud_rate,
+        )
+        .flow_control(FlowControl::Software)
+        .parity(tokio_serial::Parity::None)
+        .stop_bits(tokio_serial::StopBits::One)
+        .open_native_async()?;
+        // pseudo-terminals (e.g. the emulator) have no modem lines, so DTR is best effort
+        let _ = connection.write_data_terminal_ready(true);
+        // only junk from before this connection is dropped, later lines stay in the codec buffer
+        connection.clear(ClearBuffer::All)?;
+        self.connection = Some(Framed::new(
+            connection,
+            LinesCodec::new_with_max_length(MAX_LINE_LENGTH),
+        ));
+
+        Ok(())
+    }
+
+    async fn write_line(&mut self, line: &str) -> Result<(), DeviceError> {
+        self.connection()?.send(line).await?;
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> Result<String, DeviceError> {
+        match self.connection()?.next().await {
+            Some(line) => Ok(line?),
+            None => Err(DeviceError::Disconnected("Serial port closed".to_string())),
+        }
+    }
+
+    fn close(&mut self) {
+        self.connection.take();
+    }
+
+    fn is_open(&self) -> bool {
+        self.connection.is_some()
+    }
+}
@@ -0,0 +1,63 @@
+use futures::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio_util::codec::{Framed, LinesCodec};
+use crate::device::DeviceError;
+use crate::transport::{MAX_LINE_LENGTH, Transport};
+
+pub struct TcpTransport {
+    address: String,
+    connection: Option<Framed<TcpStream, LinesCodec>>,
+}
+
+impl TcpTransport {
+    pub fn new(address: String) -> Self {
+        Self {
+            address,
+            connection: None,
+        }
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn connection(&mut self) -> Result<&mut Framed<TcpStream, LinesCodec>, DeviceError> {
+        self.connection.as_mut().ok_or(DeviceError::Disconnected(
+            "Device connection isn't created".to_string(),
+        ))
+    }
+}
+
+impl Transport for TcpTransport {
+    async fn open(&mut self) -> Result<(), DeviceError> {
+        self.connection.take();
+        let stream = TcpStream::connect(&self.address).await?;
+        stream.set_nodelay(true)?;
+        self.connection = Some(Framed::new(
+            stream,
+            LinesCodec::new_with_max_length(MAX_LINE_LENGTH),
+        ));
+
+        Ok(())
+    }
+
+    async fn write_line(&mut self, line: &str) -> Result<(), DeviceError> {
+        self.connection()?.send(line).await?;
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> Result<String, DeviceError> {
+        match self.connection()?.next().await {
+            Some(line) => Ok(line?),
+            None => Err(DeviceError::Disconnected("Connection closed by peer".to_string())),
+        }
+    }
+
+    fn close(&mut self) {
+        self.connection.take();
+    }
+
+    fn is_open(&self) -> bool {
+        self.connection.is_some()
+    }
+}
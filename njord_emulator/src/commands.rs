@@ -0,0 +1,131 @@
+pub const SET_VALUE_COMMAND: &str = "set_value";
+pub const SET_VALUES_COMMAND: &str = "set_values";
+pub const PORTS_SETUP_COMMAND: &str = "ports_setup";
+pub const GET_VALUE_COMMAND: &str = "get_value";
+pub const GET_CONFIG_COMMAND: &str = "get_config";
+pub const SET_CONFIG_COMMAND: &str = "set_config";
+pub const GET_DEFAULT_CONFIG_COMMAND: &str = "get_default_config";
+pub const LOAD_DEFAULT_CONFIG_COMMAND: &str = "load_default_config";
+pub const SET_DEFAULT_VALUE_COMMAND: &str = "set_default_value";
+pub const SET_UPDATE_TIME_COMMAND: &str = "set_update_time";
+pub const BOARD_INFO_COMMAND: &str = "board_info";
+pub const PING_COMMAND: &str = "ping";
+pub const GET_RPM_COMMAND: &str = "get_rpm";
+pub const BOOTLOADER_COMMAND: &str = "bootloader";
+pub const HEARTBEAT_COMMAND: &str = "heartbeat";
+pub const SET_HEARTBEAT_TIMEOUT_COMMAND: &str = "set_heartbeat_timeout";
+pub const SET_VALUES_PERMILLE_COMMAND: &str = "set_values_permille";
+pub const GET_VALUE_PERMILLE_COMMAND: &str = "get_value_permille";
+pub const REBOOT_COMMAND: &str = "reboot";
+pub const FACTORY_RESET_COMMAND: &str = "factory_reset";
+pub const IDENTIFY_COMMAND: &str = "identify";
+pub const SET_TEMPERATURE_INPUTS_COMMAND: &str = "set_temperature_inputs";
+pub const GET_TEMPERATURE_INPUTS_COMMAND: &str = "get_temperature_inputs";
+pub const GET_TEMPERATURES_COMMAND: &str = "get_temperatures";
+pub const SET_STANDALONE_CURVES_COMMAND: &str = "set_standalone_curves";
+pub const GET_STANDALONE_CURVES_COMMAND: &str = "get_standalone_curves";
+pub const GET_CONTROL_MODE_COMMAND: &str = "get_control_mode";
+
+pub const PORT_SETTINGS_CAPABILITY: &str = "port_settings"; // set_config takes per-port PWM settings
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub const CAPABILITIES: [&str; 28] = [
+    SET_VALUE_COMMAND,
+    SET_VALUES_COMMAND,
+    PORTS_SETUP_COMMAND,
+    GET_VALUE_COMMAND,
+    GET_CONFIG_COMMAND,
+    SET_CONFIG_COMMAND,
+    GET_DEFAULT_CONFIG_COMMAND,
+    LOAD_DEFAULT_CONFIG_COMMAND,
+    SET_DEFAULT_VALUE_COMMAND,
+    SET_UPDATE_TIME_COMMAND,
+    BOARD_INFO_COMMAND,
+    PING_COMMAND,
+    GET_RPM_COMMAND,
+    BOOTLOADER_COMMAND,
+    HEARTBEAT_COMMAND,
+    SET_HEARTBEAT_TIMEOUT_COMMAND,
+    SET_VALUES_PERMILLE_COMMAND,
+    GET_VALUE_PERMILLE_COMMAND,
+    REBOOT_COMMAND,
+    FACTORY_RESET_COMMAND,
+    IDENTIFY_COMMAND,
+    SET_TEMPERATURE_INPUTS_COMMAND,
+    GET_TEMPERATURE_INPUTS_COMMAND,
+    GET_TEMPERATURES_COMMAND,
+    SET_STANDALONE_CURVES_COMMAND,
+    GET_STANDALONE_CURVES_COMMAND,
+    GET_CONTROL_MODE_COMMAND,
+    PORT_SETTINGS_CAPABILITY,
+];
+
+pub enum Command {
+    SetValue,
+    SetValues,
+    PortsSetup,
+    GetValue,
+    GetConfig,
+    SetConfig,
+    GetDefaultConfig,
+    LoadDefaultConfig,
+    SetDefaultValue,
+    SetUpdateTime,
+    BoardInfo,
+    Ping,
+    GetRpm,
+    Bootloader,
+    Heartbeat,
+    SetHeartbeatTimeout,
+    SetValuesPermille,
+    GetValuePermille,
+    Reboot,
+    FactoryReset,
+    Identify,
+    SetTemperatureInputs,
+    GetTemperatureInputs,
+    GetTemperatures,
+    SetStandaloneCurves,
+    GetStandaloneCurves,
+    GetControlMode,
+}
+
+pub fn string_to_command(cmd: &str) -> Command {
+    match cmd {
+        SET_VALUE_COMMAND => Command::SetValue,
+        SET_VALUES_COMMAND => Command::SetValues,
+        PORTS_SETUP_COMMAND => Command::PortsSetup,
+        GET_VALUE_COMMAND => Command::GetValue,
+        GET_CONFIG_COMMAND => Command::GetConfig,
+        SET_CONFIG_COMMAND => Command::SetConfig,
+        GET_DEFAULT_CONFIG_COMMAND => Command::GetDefaultConfig,
+        LOAD_DEFAULT_CONFIG_COMMAND => Command::LoadDefaultConfig,
+        SET_DEFAULT_VALUE_COMMAND => Command::SetDefaultValue,
+        SET_UPDATE_TIME_COMMAND => Command::SetUpdateTime,
+        BOARD_INFO_COMMAND => Command::BoardInfo,
+        GET_RPM_COMMAND => Command::GetRpm,
+        BOOTLOADER_COMMAND => Command::Bootloader,
+        HEARTBEAT_COMMAND => Command::Heartbeat,
+        SET_HEARTBEAT_TIMEOUT_COMMAND => Command::SetHeartbeatTimeout,
+        SET_VALUES_PERMILLE_COMMAND => Command::SetValuesPermille,
+        GET_VALUE_PERMILLE_COMMAND => Command::GetValuePermille,
+        REBOOT_COMMAND => Command::Reboot,
+        FACTORY_RESET_COMMAND => Command::FactoryReset,
+        IDENTIFY_COMMAND => Command::Identify,
+        SET_TEMPERATURE_INPUTS_COMMAND => Command::SetTemperatureInputs,
+        GET_TEMPERATURE_INPUTS_COMMAND => Command::GetTemperatureInputs,
+        GET_TEMPERATURES_COMMAND => Command::GetTemperatures,
+        SET_STANDALONE_CURVES_COMMAND => Command::SetStandaloneCurves,
+        GET_STANDALONE_CURVES_COMMAND => Command::GetStandaloneCurves,
+        GET_CONTROL_MODE_COMMAND => Command::GetControlMode,
+        // unknown commands are answered like a ping, same as the firmware
+        _ => Command::Ping,
+    }
+}
+
+// the connection handshake and discovery probes shouldn't take a standalone board over,
+// a host keeps control with heartbeat or any other command
+pub fn is_passive_command(cmd: &Command) -> bool {
+    matches!(cmd, Command::Ping | Command::BoardInfo | Command::GetControlMode)
+}
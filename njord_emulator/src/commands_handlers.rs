@@ -0,0 +1,477 @@
+use std::time::{Duration, Instant};
+use serde_json::{Value, json};
+use crate::commands::{CAPABILITIES, Command, PROTOCOL_VERSION, is_passive_command, string_to_command};
+use crate::messages::{BAD_ARGS, BAD_ARGS_COUNT, ERR_CODE, FAILSAFE_MSG, INFO_CODE, OK_CODE, PONG_MSG};
+use crate::storage::{
+    ADC_MAX_VALUE, DEFAULT_CONFIG, DEFAULT_PWM_FREQUENCY, DEFAULT_PWM_RESOLUTION, INITIAL_VALUE,
+    INTERNAL_TEMPERATURE_GPIO, KELVIN_OFFSET, MAX_IDENTIFY_TIME, MAX_PERCENT_VALUE, MAX_PERMILLE_VALUE, NO_TACH_INPUT,
+    OUTPUT_MODE_PWM, PERCENT_SCALE, StandaloneCurve, THERMISTOR_MODEL_BETA, TemperatureInput, to_int,
+};
+use crate::{Emulator, MAX_PWM_CHANNEL_INDEX, SYNTHETIC_CHIP_TEMPERATURE, SYNTHETIC_MAX_RPM};
+
+impl Emulator {
+    pub fn handle_command(&mut self) {
+        let command = string_to_command(self.command.get_com());
+        if !is_passive_command(&command) {
+            if self.is_standalone() {
+                self.control_mode_since = Instant::now();
+            }
+            self.last_host_contact = Some(Instant::now());
+            self.in_failsafe = false;
+        }
+
+        match command {
+            Command::SetValue => self.set_value_handler(),
+            Command::SetValues => self.set_values_handler(),
+            Command::PortsSetup => self.ports_setup_handler(),
+            Command::GetValue => self.get_value_handler(),
+            Command::GetConfig => self.get_config_handler(),
+            Command::SetConfig => self.set_config_handler(),
+            Command::GetDefaultConfig => self.get_default_config_handler(),
+            Command::LoadDefaultConfig => self.load_default_config_handler(),
+            Command::SetDefaultValue => self.set_default_value_handler(),
+            Command::SetUpdateTime => self.set_update_time_handler(),
+            Command::BoardInfo => self.board_info_handler(),
+            Command::Ping => self.ping_handler(),
+            Command::GetRpm => self.get_rpm_handler(),
+            Command::Bootloader => self.bootloader_handler(),
+            Command::Heartbeat => self.heartbeat_handler(),
+            Command::SetHeartbeatTimeout => self.set_heartbeat_timeout_handler(),
+            Command::SetValuesPermille => self.set_values_permille_handler(),
+            Command::GetValuePermille => self.get_value_permille_handler(),
+            Command::Reboot => self.reboot_handler(),
+            Command::FactoryReset => self.factory_reset_handler(),
+            Command::Identify => self.identify_handler(),
+            Command::SetTemperatureInputs => self.set_temperature_inputs_handler(),
+            Command::GetTemperatureInputs => self.get_temperature_inputs_handler(),
+            Command::GetTemperatures => self.get_temperatures_handler(),
+            Command::SetStandaloneCurves => self.set_standalone_curves_handler(),
+            Command::GetStandaloneCurves => self.get_standalone_curves_handler(),
+            Command::GetControlMode => self.get_control_mode_handler(),
+        }
+    }
+
+    fn check_port_index(&self, port: i64) -> bool {
+        port >= 0 && (port as usize) < self.data.values.len()
+    }
+
+    fn check_value_input(value: i64, max_value: i64) -> bool {
+        (0..=max_value).contains(&value)
+    }
+
+    fn set_value_handler(&mut self) {
+        if self.command.has_index(1) && self.command.has_index(2) {
+            let port = to_int(self.command.get(1));
+            let value = to_int(self.command.get(2));
+            if self.check_port_index(port) && Self::check_value_input(value, MAX_PERCENT_VALUE) {
+                self.data.values[port as usize] = value * PERCENT_SCALE;
+                self.send_string_response(OK_CODE, OK_CODE);
+                return;
+            }
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        }
+        self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+    }
+
+    // data is flat index/value pairs, every pair gets its own result
+    fn set_value_pairs(&mut self, max_value: i64, scale: i64) {
+        let length = self.command.get_data_length();
+        if length == 0 || !length.is_multiple_of(2) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        let mut results = Vec::new();
+        for i in (0..length).step_by(2) {
+            let port = to_int(self.command.get_data_element(i));
+            let value = to_int(self.command.get_data_element(i + 1));
+            if self.check_port_index(port) && Self::check_value_input(value, max_value) {
+                self.data.values[port as usize] = value * scale;
+                results.push(OK_CODE);
+            } else {
+                results.push(BAD_ARGS);
+            }
+        }
+        self.send_doc_response(OK_CODE, json!({ "results": results }));
+    }
+
+    fn set_values_handler(&mut self) {
+        self.set_value_pairs(MAX_PERCENT_VALUE, PERCENT_SCALE);
+    }
+
+    fn set_values_permille_handler(&mut self) {
+        self.set_value_pairs(MAX_PERMILLE_VALUE, 1);
+    }
+
+    fn ports_setup_handler(&mut self) {
+        if self.command.get_data_length() > 0 {
+            self.data.port_config.clear();
+            self.data.default_values.clear();
+            self.data.values.clear();
+            self.data.tach_inputs.clear();
+            self.data.pwm_frequency.clear();
+            self.data.pwm_resolution.clear();
+            self.data.inverted.clear();
+            self.data.output_mode.clear();
+            self.data.standalone_curves.clear();
+            for i in 0..self.command.get_data_length() {
+                self.data.port_config.push(to_int(self.command.get_data_element(i)));
+                self.data.default_values.push(INITIAL_VALUE);
+                self.data.values.push(INITIAL_VALUE);
+                self.data.tach_inputs.push(NO_TACH_INPUT);
+                self.data.pwm_frequency.push(DEFAULT_PWM_FREQUENCY);
+                self.data.pwm_resolution.push(DEFAULT_PWM_RESOLUTION);
+                self.data.inverted.push(false);
+                self.data.output_mode.push(OUTPUT_MODE_PWM);
+                self.data.standalone_curves.push(None);
+            }
+            self.data.dump_file();
+            self.send_string_response(OK_CODE, OK_CODE);
+        }
+        // the firmware handler has no `return` after success, so it always ends with this line
+        self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+    }
+
+    fn send_values(&mut self, scale: i64) {
+        let values: Vec<i64> = self.data.values.iter().map(|value| (value + scale / 2) / scale).collect();
+        let doc = json!({ "values": values });
+        self.send_doc_response(OK_CODE, doc);
+    }
+
+    fn get_value_handler(&mut self) {
+        self.send_values(PERCENT_SCALE);
+    }
+
+    fn get_value_permille_handler(&mut self) {
+        self.send_values(1);
+    }
+
+    fn get_config_handler(&mut self) {
+        let doc = self.data.get_json();
+        self.send_doc_response(OK_CODE, doc);
+    }
+
+    fn set_config_handler(&mut self) {
+        if !self.command.has_index(1) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        let Ok(doc) = serde_json::from_str::<Value>(self.command.get(1)) else {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        };
+
+        if !self.data.load_json(&doc) {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        }
+
+        self.data.dump_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn get_default_config_handler(&mut self) {
+        let doc = serde_json::from_str(DEFAULT_CONFIG).unwrap_or_default();
+        self.send_doc_response(OK_CODE, doc);
+    }
+
+    fn load_default_config_handler(&mut self) {
+        self.data.load_default();
+        self.data.dump_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn set_default_value_handler(&mut self) {
+        if !self.command.has_index(1) || !self.command.has_index(2) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        let port = to_int(self.command.get(1));
+        let value = to_int(self.command.get(2));
+
+        if !self.check_port_index(port) || !Self::check_value_input(value, MAX_PERCENT_VALUE) {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        }
+
+        self.data.default_values[port as usize] = value;
+        self.data.dump_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn set_update_time_handler(&mut self) {
+        if !self.command.has_index(1) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        self.data.update_time = to_int(self.command.get(1));
+        self.data.dump_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn board_info_handler(&mut self) {
+        let mut doc = json!({
+            "max_ports": MAX_PWM_CHANNEL_INDEX + 1,
+            "board_name": self.board_name,
+        });
+        if !self.legacy {
+            doc["protocol_version"] = json!(PROTOCOL_VERSION);
+            doc["capabilities"] = json!(CAPABILITIES);
+            doc["device_uid"] = json!(self.device_uid);
+            doc["firmware_version"] = json!(self.firmware_version);
+        }
+        self.send_doc_response(OK_CODE, doc);
+    }
+
+    // No fan is attached, so the speed simply follows the duty value
+    fn get_rpm_handler(&mut self) {
+        let rpm: Vec<Value> = self
+            .data
+            .values
+            .iter()
+            .zip(&self.data.tach_inputs)
+            .map(|(&value, &tach_input)| {
+                if tach_input == NO_TACH_INPUT {
+                    Value::Null
+                } else {
+                    json!(value * SYNTHETIC_MAX_RPM / MAX_PERMILLE_VALUE)
+                }
+            })
+            .collect();
+        self.send_doc_response(OK_CODE, json!({ "rpm": rpm }));
+    }
+
+    fn bootloader_handler(&mut self) {
+        self.send_string_response(OK_CODE, OK_CODE);
+        self.enter_bootloader();
+    }
+
+    fn reboot_handler(&mut self) {
+        self.send_string_response(OK_CODE, OK_CODE);
+        self.reboot();
+    }
+
+    // Same as holding INPUT_GPIO at boot, the board comes back on DEFAULT_CONFIG
+    fn factory_reset_handler(&mut self) {
+        self.data.reset_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+        self.reboot();
+    }
+
+    fn identify_handler(&mut self) {
+        if !self.command.has_index(1) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        let seconds = to_int(self.command.get(1));
+        if seconds <= 0 || seconds > MAX_IDENTIFY_TIME {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        }
+
+        self.identify_until = Some(Instant::now() + Duration::from_secs(seconds as u64));
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn set_temperature_inputs_handler(&mut self) {
+        if !self.command.has_index(1) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        let doc: Value = match serde_json::from_str(self.command.get(1)) {
+            Ok(doc) => doc,
+            Err(_) => {
+                self.send_string_response(ERR_CODE, BAD_ARGS);
+                return;
+            }
+        };
+
+        if !self.data.load_temperature_inputs(&doc) {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        }
+
+        self.data.dump_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn get_temperature_inputs_handler(&mut self) {
+        let doc = json!({ "temperature_inputs": self.data.temperature_inputs_json() });
+        self.send_doc_response(OK_CODE, doc);
+    }
+
+    fn get_temperatures_handler(&mut self) {
+        let temperatures: Vec<Value> = self
+            .data
+            .temperature_inputs
+            .iter()
+            .map(|input| match self.read_temperature(input) {
+                Some(temperature) => json!((temperature * 10.0).round() / 10.0),
+                None => Value::Null,
+            })
+            .collect();
+        self.send_doc_response(OK_CODE, json!({ "temperatures": temperatures }));
+    }
+
+    // No thermistor is attached, the pin reads as if the air warmed up while the fans slow down
+    fn synthetic_adc(&self) -> f64 {
+        let mean = if self.data.values.is_empty() {
+            0.0
+        } else {
+            self.data.values.iter().sum::<i64>() as f64 / self.data.values.len() as f64
+        };
+        (0.4 + 0.2 * mean / MAX_PERMILLE_VALUE as f64) * ADC_MAX_VALUE
+    }
+
+    fn read_temperature(&self, input: &TemperatureInput) -> Option<f64> {
+        if input.gpio == INTERNAL_TEMPERATURE_GPIO {
+            return Some(SYNTHETIC_CHIP_TEMPERATURE);
+        }
+
+        let adc = self.synthetic_adc();
+        if !(1.0..=ADC_MAX_VALUE - 1.0).contains(&adc) {
+            return None;
+        }
+        let resistance = input.series_resistance * adc / (ADC_MAX_VALUE - adc);
+
+        let [first, second, third] = input.params;
+        let inverse_kelvin = if input.model == THERMISTOR_MODEL_BETA {
+            1.0 / (third + KELVIN_OFFSET) + (resistance / second).ln() / first
+        } else {
+            let ln_r = resistance.ln();
+            first + second * ln_r + third * ln_r.powi(3)
+        };
+        if inverse_kelvin <= 0.0 {
+            return None;
+        }
+        Some(1.0 / inverse_kelvin - KELVIN_OFFSET)
+    }
+
+    fn set_standalone_curves_handler(&mut self) {
+        if !self.command.has_index(1) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        let Ok(doc) = serde_json::from_str::<Value>(self.command.get(1)) else {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        };
+
+        if !self.data.load_standalone_curves(&doc) {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        }
+
+        self.data.dump_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn get_standalone_curves_handler(&mut self) {
+        let doc = json!({ "standalone_curves": self.data.standalone_curves_json() });
+        self.send_doc_response(OK_CODE, doc);
+    }
+
+    // Passive, asking doesn't end standalone control
+    fn get_control_mode_handler(&mut self) {
+        let doc = json!({
+            "control_mode": if self.is_standalone() { "standalone" } else { "host" },
+            "mode_time": self.control_mode_since.elapsed().as_millis() as u64,
+        });
+        self.send_doc_response(OK_CODE, doc);
+    }
+
+    fn is_standalone(&self) -> bool {
+        self.last_host_contact.is_none() || self.in_failsafe
+    }
+
+    fn evaluate_curve(curve: &StandaloneCurve, temp: f64) -> i64 {
+        let last = curve.temps.len() - 1;
+        if temp <= curve.temps[0] {
+            return curve.values[0];
+        }
+
+        for i in 0..last {
+            if temp <= curve.temps[i + 1] {
+                let t = (temp - curve.temps[i]) / (curve.temps[i + 1] - curve.temps[i]);
+                let (from, to) = (curve.values[i] as f64, curve.values[i + 1] as f64);
+                return (from + t * (to - from)).round() as i64;
+            }
+        }
+        curve.values[last]
+    }
+
+    // The firmware runs this every STANDALONE_UPDATE_TIME, here it runs whenever a line arrives.
+    // Ports whose input can't be read fall back to their default value until it can
+    pub fn standalone_loop(&mut self) {
+        if !self.is_standalone() {
+            return;
+        }
+
+        for port in 0..self.data.standalone_curves.len().min(self.data.values.len()) {
+            let Some(curve) = &self.data.standalone_curves[port] else {
+                continue;
+            };
+            let temp = self
+                .data
+                .temperature_inputs
+                .iter()
+                .find(|input| input.name == curve.temperature_input)
+                .and_then(|input| self.read_temperature(input));
+            self.data.values[port] = match temp {
+                Some(temp) => Self::evaluate_curve(curve, temp).clamp(curve.min_value, curve.max_value),
+                None => self.data.default_values[port] * PERCENT_SCALE,
+            };
+        }
+    }
+
+    fn heartbeat_handler(&mut self) {
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    fn set_heartbeat_timeout_handler(&mut self) {
+        if !self.command.has_index(1) {
+            self.send_string_response(ERR_CODE, BAD_ARGS_COUNT);
+            return;
+        }
+
+        let timeout = to_int(self.command.get(1));
+        if timeout < 0 {
+            self.send_string_response(ERR_CODE, BAD_ARGS);
+            return;
+        }
+
+        self.data.heartbeat_timeout = timeout;
+        self.data.dump_file();
+        self.send_string_response(OK_CODE, OK_CODE);
+    }
+
+    // The firmware checks this every loop, the emulator only gets to run when a line arrives,
+    // so the failsafe line goes out right before the answer to it
+    pub fn check_failsafe(&mut self) {
+        let Some(last_host_contact) = self.last_host_contact else {
+            return;
+        };
+        if self.data.heartbeat_timeout <= 0 || self.in_failsafe {
+            return;
+        }
+        if last_host_contact.elapsed() < Duration::from_millis(self.data.heartbeat_timeout as u64) {
+            return;
+        }
+
+        self.in_failsafe = true;
+        self.control_mode_since = Instant::now();
+        self.data.values = self.data.default_values.iter().map(|value| value * PERCENT_SCALE).collect();
+        self.send_string_response(INFO_CODE, FAILSAFE_MSG);
+    }
+
+    fn ping_handler(&mut self) {
+        self.send_string_response(OK_CODE, PONG_MSG);
+    }
+}
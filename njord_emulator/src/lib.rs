@@ -0,0 +1,155 @@
+pub mod commands;
+pub mod commands_handlers;
+pub mod messages;
+pub mod storage;
+
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::storage::{CommandStorage, GlobalStorage};
+
+pub const DEFAULT_BOARD_NAME: &str = "njord_emulator";
+pub const DEFAULT_DEVICE_UID: &str = "E000000000000001";
+pub const DEFAULT_FIRMWARE_VERSION: &str = "0.2.0";
+pub const UF2_INFO_FILE: &str = "INFO_UF2.TXT";
+pub const MAX_PWM_CHANNEL_INDEX: u8 = 15;
+pub const SYNTHETIC_MAX_RPM: i64 = 2000;
+pub const ADC_GPIOS: RangeInclusive<i64> = 26..=28;
+pub const SYNTHETIC_CHIP_TEMPERATURE: f64 = 27.0;
+
+pub struct Emulator {
+    pub board_name: String,
+    pub device_uid: String,
+    // answers `board_info` like firmware from before protocol versioning
+    pub legacy: bool,
+    pub firmware_version: String,
+    // stands in for the bootloader mass-storage volume, `bootloader` is answered but ignored without it
+    pub uf2_dir: Option<PathBuf>,
+    in_bootloader: bool,
+    last_host_contact: Option<Instant>,
+    in_failsafe: bool,
+    control_mode_since: Instant, // last switch between host and standalone control
+    // there is no LED to blink, kept so a test can see the board was asked to
+    pub identify_until: Option<Instant>,
+    pub data: GlobalStorage,
+    command: CommandStorage,
+    output: Vec<String>,
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new(DEFAULT_BOARD_NAME.to_string())
+    }
+}
+
+impl Emulator {
+    pub fn new(board_name: String) -> Self {
+        let mut data = GlobalStorage::default();
+        data.load_default();
+        Self {
+            board_name,
+            device_uid: DEFAULT_DEVICE_UID.to_string(),
+            legacy: false,
+            firmware_version: DEFAULT_FIRMWARE_VERSION.to_string(),
+            uf2_dir: None,
+            in_bootloader: false,
+            last_host_contact: None,
+            in_failsafe: false,
+            control_mode_since: Instant::now(),
+            identify_until: None,
+            data,
+            command: CommandStorage::default(),
+            output: Vec::new(),
+        }
+    }
+
+    // Mirrors `loadStorage` from the firmware `setup`
+    pub fn boot(&mut self) -> Vec<String> {
+        if !self.data.load_file() {
+            self.send_string_response(messages::ERR_CODE, messages::CONFIG_LOAD_ERROR);
+            self.data.load_default();
+            self.send_string_response(messages::OK_CODE, messages::LOAD_DEFAULT_CONFIG_MSG);
+        }
+
+        std::mem::take(&mut self.output)
+    }
+
+    // Mirrors `readCommandFromSerial` + `handleCommand` from the firmware main loop and
+    // returns every line the board would print in response
+    pub fn handle_line(&mut self, line: &str) -> Vec<String> {
+        // the bootloader doesn't speak the protocol, the board is back once an image was copied
+        if self.in_bootloader {
+            if !self.take_uf2_image() {
+                return Vec::new();
+            }
+            self.output = self.boot();
+        }
+
+        self.check_failsafe();
+        self.standalone_loop();
+        let doc = serde_json::from_str(line.trim_end()).unwrap_or(serde_json::Value::Null);
+        if !self.command.set_from_json(&doc) {
+            self.send_string_response(messages::ERR_CODE, messages::BAD_JSON_ERROR);
+        }
+        self.handle_command();
+        self.command.clear();
+
+        std::mem::take(&mut self.output)
+    }
+
+    // Mirrors a watchdog reboot, everything but the storage is gone. The board prints its boot
+    // messages before the host opens the port again, only `after-hw-reset` gets through
+    pub fn reboot(&mut self) {
+        let storage_file = self.data.storage_file.take();
+        self.data = GlobalStorage {
+            storage_file,
+            ..Default::default()
+        };
+        self.last_host_contact = None;
+        self.in_failsafe = false;
+        self.control_mode_since = Instant::now();
+        self.identify_until = None;
+
+        let output = std::mem::take(&mut self.output);
+        self.boot();
+        self.output = output;
+        self.command.clear();
+        self.send_string_response(messages::INFO_CODE, messages::AFTER_HW_RESET);
+    }
+
+    pub fn enter_bootloader(&mut self) {
+        if let Some(uf2_dir) = &self.uf2_dir {
+            let _ = fs::create_dir_all(uf2_dir);
+            self.in_bootloader = fs::write(uf2_dir.join(UF2_INFO_FILE), "UF2 Bootloader v3.0\r\nModel: Njord emulator\r\n").is_ok();
+        }
+    }
+
+    // Removes the volume files again, like the bootloader does after flashing
+    fn take_uf2_image(&mut self) -> bool {
+        let Some(uf2_dir) = &self.uf2_dir else {
+            return true;
+        };
+        let Ok(entries) = fs::read_dir(uf2_dir) else {
+            return false;
+        };
+        let images: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("uf2")))
+            .collect();
+        if images.is_empty() {
+            return false;
+        }
+        for image in images {
+            let _ = fs::remove_file(image);
+        }
+        let _ = fs::remove_file(uf2_dir.join(UF2_INFO_FILE));
+        self.in_bootloader = false;
+        true
+    }
+
+    fn println(&mut self, line: String) {
+        self.output.push(line);
+    }
+}
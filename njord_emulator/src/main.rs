@@ -0,0 +1,154 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::{env, thread};
+use njord_emulator::Emulator;
+
+struct Args {
+    board_name: String,
+    device_uid: Option<String>,
+    legacy: bool,
+    firmware_version: Option<String>,
+    uf2_dir: Option<String>,
+    storage: Option<String>,
+    tcp: Option<String>,
+    link: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        board_name: njord_emulator::DEFAULT_BOARD_NAME.to_string(),
+        device_uid: None,
+        legacy: false,
+        firmware_version: None,
+        uf2_dir: None,
+        storage: None,
+        tcp: None,
+        link: None,
+    };
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("Missing value for {arg}"));
+        match arg.as_str() {
+            "--board-name" => args.board_name = value()?,
+            "--uid" => args.device_uid = Some(value()?),
+            "--legacy" => args.legacy = true,
+            "--firmware-version" => args.firmware_version = Some(value()?),
+            "--uf2-dir" => args.uf2_dir = Some(value()?),
+            "--storage" => args.storage = Some(value()?),
+            "--tcp" => args.tcp = Some(value()?),
+            "--link" => args.link = Some(value()?),
+            "--help" | "-h" => {
+                return Err("Usage: njord_emulator [--board-name NAME] [--uid UID] [--legacy] [--firmware-version VERSION] [--uf2-dir DIR] [--storage FILE] [--tcp ADDR | --link PATH]".to_string());
+            }
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    Ok(args)
+}
+
+// Feeds every received line to the emulator and writes its answers back, like the firmware loop
+fn serve<R: BufRead, W: Write>(emulator: &Mutex<Emulator>, reader: R, mut writer: W) -> std::io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        eprintln!("-> {line}");
+        let responses = emulator.lock().unwrap().handle_line(&line);
+        for response in responses {
+            eprintln!("<- {response}");
+            writer.write_all(response.as_bytes())?;
+            writer.write_all(b"\r\n")?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn serve_tcp(emulator: Arc<Mutex<Emulator>>, address: &str) -> Result<(), String> {
+    let listener = TcpListener::bind(address).map_err(|e| e.to_string())?;
+    eprintln!("Njord emulator listening on {}", listener.local_addr().map_err(|e| e.to_string())?);
+    for stream in listener.incoming() {
+        let stream = stream.map_err(|e| e.to_string())?;
+        let emulator = emulator.clone();
+        thread::spawn(move || {
+            let reader = match stream.try_clone() {
+                Ok(reader) => BufReader::new(reader),
+                Err(_) => return,
+            };
+            let _ = serve(&emulator, reader, stream);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn serve_pty(emulator: Arc<Mutex<Emulator>>, link: Option<&str>) -> Result<(), String> {
+    use std::fs::File;
+    use std::os::fd::FromRawFd;
+    use nix::pty::openpty;
+    use nix::sys::termios::{SetArg, cfmakeraw, tcgetattr, tcsetattr};
+    use nix::unistd::ttyname;
+
+    let pty = openpty(None, None).map_err(|e| e.to_string())?;
+    let mut termios = tcgetattr(pty.slave).map_err(|e| e.to_string())?;
+    cfmakeraw(&mut termios);
+    tcsetattr(pty.slave, SetArg::TCSANOW, &termios).map_err(|e| e.to_string())?;
+
+    let slave_path = ttyname(pty.slave).map_err(|e| e.to_string())?;
+    if let Some(link) = link {
+        let _ = std::fs::remove_file(link);
+        std::os::unix::fs::symlink(&slave_path, link).map_err(|e| e.to_string())?;
+        eprintln!("Njord emulator serial port: {link} -> {}", slave_path.display());
+    } else {
+        eprintln!("Njord emulator serial port: {}", slave_path.display());
+    }
+
+    // the slave end stays open so the master does not hit EIO while the host reconnects
+    let _slave = unsafe { File::from_raw_fd(pty.slave) };
+    let master = unsafe { File::from_raw_fd(pty.master) };
+    let reader = BufReader::new(master.try_clone().map_err(|e| e.to_string())?);
+    serve(&emulator, reader, master).map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+fn serve_pty(_emulator: Arc<Mutex<Emulator>>, _link: Option<&str>) -> Result<(), String> {
+    Err("Pseudo-terminals are only supported on unix, use --tcp".to_string())
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut emulator = Emulator::new(args.board_name);
+    emulator.legacy = args.legacy;
+    if let Some(device_uid) = args.device_uid {
+        emulator.device_uid = device_uid;
+    }
+    if let Some(firmware_version) = args.firmware_version {
+        emulator.firmware_version = firmware_version;
+    }
+    emulator.uf2_dir = args.uf2_dir.map(Into::into);
+    if let Some(storage) = args.storage {
+        emulator.data.storage_file = Some(storage.into());
+    }
+    for line in emulator.boot() {
+        eprintln!("<- {line}");
+    }
+    let emulator = Arc::new(Mutex::new(emulator));
+
+    let result = match args.tcp {
+        Some(address) => serve_tcp(emulator, &address),
+        None => serve_pty(emulator, args.link.as_deref()),
+    };
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,36 @@
+use serde_json::{Value, json};
+use crate::Emulator;
+
+pub const LOAD_DEFAULT_CONFIG_MSG: &str = "loading-default-config";
+pub const PONG_MSG: &str = "pong";
+pub const CLEAR_OK: &str = "clear-ok";
+pub const AFTER_HW_RESET: &str = "after-hw-reset";
+pub const FAILSAFE_MSG: &str = "failsafe";
+
+pub const OK_CODE: &str = "ok";
+pub const INFO_CODE: &str = "info";
+pub const ERR_CODE: &str = "err";
+
+pub const STORAGE_MOUNT_ERR: &str = "storage-mount-err";
+pub const CONFIG_LOAD_ERROR: &str = "loading-config-err";
+pub const BAD_JSON_ERROR: &str = "bad-json";
+pub const BAD_ARGS_COUNT: &str = "bad-args-count";
+pub const BAD_ARGS: &str = "bad-args";
+
+impl Emulator {
+    pub fn send_string_response(&mut self, code: &str, message: &str) {
+        let mut doc = json!({"code": code, "message": message});
+        if let Some(id) = self.command.get_id() {
+            doc["id"] = json!(id);
+        }
+        self.println(doc.to_string());
+    }
+
+    pub fn send_doc_response(&mut self, code: &str, data: Value) {
+        let mut doc = json!({"code": code, "data": data});
+        if let Some(id) = self.command.get_id() {
+            doc["id"] = json!(id);
+        }
+        self.println(doc.to_string());
+    }
+}
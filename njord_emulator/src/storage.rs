@@ -0,0 +1,489 @@
+use std::fs;
+use std::path::PathBuf;
+use serde_json::{Value, json};
+
+pub const DEFAULT_CONFIG: &str = r#"{"ports":[25], "default_values":[100], "update_time":120}"#;
+pub const INITIAL_VALUE: i64 = 0;
+pub const MAX_PERCENT_VALUE: i64 = 100;
+pub const MAX_PERMILLE_VALUE: i64 = 1000;
+pub const PERCENT_SCALE: i64 = 10; // port values are kept in permille, percent commands and default_values get scaled
+pub const NO_TACH_INPUT: i64 = -1;
+
+pub const DEFAULT_PWM_FREQUENCY: i64 = 25000;
+pub const DEFAULT_PWM_RESOLUTION: i64 = 10;
+pub const MIN_PWM_FREQUENCY: i64 = 10;
+pub const MAX_PWM_FREQUENCY: i64 = 100000;
+pub const MAX_PWM_RESOLUTION: i64 = 16;
+
+pub const MAX_IDENTIFY_TIME: i64 = 60; // seconds
+
+pub const MAX_TEMPERATURE_INPUTS: usize = 4;
+pub const INTERNAL_TEMPERATURE_GPIO: i64 = -1; // the on-chip sensor instead of a thermistor
+pub const THERMISTOR_MODEL_BETA: i64 = 0;
+pub const THERMISTOR_MODEL_STEINHART_HART: i64 = 1;
+pub const MAX_CURVE_POINTS: usize = 8;
+pub const ADC_MAX_VALUE: f64 = 4095.0; // 12 bit reads
+pub const KELVIN_OFFSET: f64 = 273.15;
+
+pub const OUTPUT_MODE_PWM: i64 = 0;
+pub const OUTPUT_MODE_ON_OFF: i64 = 1;
+
+#[derive(Clone)]
+pub struct TemperatureInput {
+    pub name: String,
+    pub gpio: i64, // INTERNAL_TEMPERATURE_GPIO for the on-chip sensor
+    pub series_resistance: f64,
+    pub model: i64, // THERMISTOR_MODEL_BETA or THERMISTOR_MODEL_STEINHART_HART
+    pub params: [f64; 3], // beta, nominal resistance, nominal temperature or a, b, c
+}
+
+// A port curve the board runs by itself while no host is around, values in permille
+#[derive(Clone)]
+pub struct StandaloneCurve {
+    pub temperature_input: String, // name of the input it follows
+    pub temps: Vec<f64>, // ascending
+    pub values: Vec<i64>,
+    pub min_value: i64,
+    pub max_value: i64,
+}
+
+#[derive(Default)]
+pub struct GlobalStorage {
+    pub port_config: Vec<i64>,
+    pub values: Vec<i64>,
+    pub default_values: Vec<i64>,
+    pub tach_inputs: Vec<i64>,
+    pub pwm_frequency: Vec<i64>,
+    pub pwm_resolution: Vec<i64>,
+    pub inverted: Vec<bool>,
+    pub output_mode: Vec<i64>,
+    pub update_time: i64,
+    pub heartbeat_timeout: i64,
+    pub temperature_inputs: Vec<TemperatureInput>, // kept apart from the ports, set_config leaves them alone
+    pub standalone_curves: Vec<Option<StandaloneCurve>>, // one per port, `None` keeps default_values
+    // stands in for the LittleFS `/save.bin` file, nothing is persisted when it is `None`
+    pub storage_file: Option<PathBuf>,
+}
+
+impl GlobalStorage {
+    pub fn dump_file(&self) {
+        if let Some(storage_file) = &self.storage_file {
+            let _ = fs::write(storage_file, self.get_json().to_string());
+        }
+    }
+
+    // Mirrors `resetStorage`, formatting LittleFS
+    pub fn reset_file(&self) {
+        if let Some(storage_file) = &self.storage_file {
+            let _ = fs::remove_file(storage_file);
+        }
+    }
+
+    pub fn load_file(&mut self) -> bool {
+        let Some(storage_file) = &self.storage_file else {
+            return false;
+        };
+        let Ok(content) = fs::read_to_string(storage_file) else {
+            return false;
+        };
+        match serde_json::from_str(&content) {
+            Ok(doc) => self.load_json(&doc),
+            Err(_) => false,
+        }
+    }
+
+    pub fn load_json(&mut self, doc: &Value) -> bool {
+        if doc.get("ports").is_none() && doc.get("update_time").is_none() {
+            return false;
+        }
+
+        let settings = doc["port_settings"].as_array().cloned().unwrap_or_default();
+        if !valid_port_settings(&settings, doc["ports"].as_array().map_or(0, Vec::len)) {
+            return false;
+        }
+
+        // only the stored file carries them, configs from the host don't
+        if let Some(inputs) = doc.get("temperature_inputs")
+            && !self.load_temperature_inputs(inputs)
+        {
+            return false;
+        }
+
+        self.port_config = json_int_array(&doc["ports"]);
+        self.default_values = json_int_array(&doc["default_values"]);
+
+        //resizing default_values to ports_size
+        self.default_values.resize(self.port_config.len(), INITIAL_VALUE);
+        self.values = self.default_values.iter().map(|value| value * PERCENT_SCALE).collect();
+
+        self.tach_inputs = doc["tach_inputs"]
+            .as_array()
+            .map(|arr| arr.iter().map(|pin| if pin.is_null() { NO_TACH_INPUT } else { json_int(pin) }).collect())
+            .unwrap_or_default();
+        self.tach_inputs.resize(self.port_config.len(), NO_TACH_INPUT);
+
+        let ports_count = self.port_config.len();
+        self.pwm_frequency = vec![DEFAULT_PWM_FREQUENCY; ports_count];
+        self.pwm_resolution = vec![DEFAULT_PWM_RESOLUTION; ports_count];
+        self.inverted = vec![false; ports_count];
+        self.output_mode = vec![OUTPUT_MODE_PWM; ports_count];
+        for (i, port) in settings.iter().enumerate() {
+            self.pwm_frequency[i] = json_int_or(&port["pwm_frequency"], DEFAULT_PWM_FREQUENCY);
+            self.pwm_resolution[i] = json_int_or(&port["pwm_resolution"], DEFAULT_PWM_RESOLUTION);
+            self.inverted[i] = port["inverted"].as_bool().unwrap_or(false);
+            self.output_mode[i] = parse_output_mode(port["output_mode"].as_str().unwrap_or("pwm"));
+        }
+
+        self.update_time = json_int(&doc["update_time"]);
+        self.heartbeat_timeout = json_int(&doc["heartbeat_timeout"]);
+
+        // the stored file carries them, a config from the host keeps the ones that still have a port
+        let loaded = doc
+            .get("standalone_curves")
+            .is_some_and(|curves| self.load_standalone_curves(curves));
+        if !loaded {
+            self.standalone_curves.resize(self.port_config.len(), None);
+        }
+        true
+    }
+
+    // All or nothing like the temperature inputs, null entries leave the port on default_values
+    pub fn load_standalone_curves(&mut self, curves: &Value) -> bool {
+        let Some(curves) = curves.as_array() else {
+            return false;
+        };
+        if curves.len() > self.port_config.len() {
+            return false;
+        }
+
+        let mut parsed = vec![None; self.port_config.len()];
+        for (port, curve) in curves.iter().enumerate() {
+            if curve.is_null() {
+                continue;
+            }
+            match parse_standalone_curve(curve, &self.temperature_inputs) {
+                Some(curve) => parsed[port] = Some(curve),
+                None => return false,
+            }
+        }
+        self.standalone_curves = parsed;
+        true
+    }
+
+    pub fn standalone_curves_json(&self) -> Value {
+        let curves: Vec<Value> = self
+            .standalone_curves
+            .iter()
+            .map(|curve| match curve {
+                Some(curve) => json!({
+                    "temperature_input": curve.temperature_input,
+                    "curve": curve
+                        .temps
+                        .iter()
+                        .zip(&curve.values)
+                        .map(|(temp, value)| json!({ "temp": temp, "value": value }))
+                        .collect::<Vec<Value>>(),
+                    "min_value": curve.min_value,
+                    "max_value": curve.max_value,
+                }),
+                None => Value::Null,
+            })
+            .collect();
+        Value::Array(curves)
+    }
+
+    // All or nothing, the stored inputs stay as they were when one of them is bad
+    pub fn load_temperature_inputs(&mut self, inputs: &Value) -> bool {
+        let Some(inputs) = inputs.as_array() else {
+            return false;
+        };
+        if inputs.len() > MAX_TEMPERATURE_INPUTS {
+            return false;
+        }
+
+        let parsed: Option<Vec<TemperatureInput>> = inputs.iter().map(parse_temperature_input).collect();
+        match parsed {
+            Some(parsed) => {
+                self.temperature_inputs = parsed;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn temperature_inputs_json(&self) -> Value {
+        let inputs: Vec<Value> = self
+            .temperature_inputs
+            .iter()
+            .map(|input| {
+                if input.gpio == INTERNAL_TEMPERATURE_GPIO {
+                    return json!({ "name": input.name, "source": "internal" });
+                }
+                let [first, second, third] = input.params;
+                let model = if input.model == THERMISTOR_MODEL_BETA {
+                    json!({ "kind": "beta", "beta": first, "nominal_resistance": second, "nominal_temperature": third })
+                } else {
+                    json!({ "kind": "steinhart_hart", "a": first, "b": second, "c": third })
+                };
+                json!({
+                    "name": input.name,
+                    "source": "thermistor",
+                    "gpio": input.gpio,
+                    "series_resistance": input.series_resistance,
+                    "model": model,
+                })
+            })
+            .collect();
+        Value::Array(inputs)
+    }
+
+    pub fn load_default(&mut self) {
+        let doc = serde_json::from_str(DEFAULT_CONFIG).unwrap_or_default();
+        self.load_json(&doc);
+    }
+
+    pub fn get_json(&self) -> Value {
+        json!({
+            "ports": self.port_config,
+            "default_values": self.default_values,
+            "update_time": self.update_time,
+            "heartbeat_timeout": self.heartbeat_timeout,
+            "tach_inputs": self
+                .tach_inputs
+                .iter()
+                .map(|&pin| if pin == NO_TACH_INPUT { Value::Null } else { json!(pin) })
+                .collect::<Vec<Value>>(),
+            "port_settings": (0..self.port_config.len())
+                .map(|i| {
+                    json!({
+                        "pwm_frequency": self.pwm_frequency[i],
+                        "pwm_resolution": self.pwm_resolution[i],
+                        "inverted": self.inverted[i],
+                        "output_mode": if self.output_mode[i] == OUTPUT_MODE_ON_OFF { "on_off" } else { "pwm" },
+                    })
+                })
+                .collect::<Vec<Value>>(),
+            "temperature_inputs": self.temperature_inputs_json(),
+            "standalone_curves": self.standalone_curves_json(),
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct CommandStorage {
+    com: String,
+    data: Vec<String>,
+    id: Option<i64>,
+}
+
+impl CommandStorage {
+    pub fn get_com(&self) -> &str {
+        &self.com
+    }
+
+    pub fn get_data_length(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn get_data_element(&self, index: usize) -> &str {
+        self.data.get(index).map(String::as_str).unwrap_or_default()
+    }
+
+    pub fn get_id(&self) -> Option<i64> {
+        self.id
+    }
+
+    pub fn set_from_json(&mut self, doc: &Value) -> bool {
+        let Some(com) = doc.get("command") else {
+            return false;
+        };
+        self.com = json_string(com);
+        if let Some(id) = doc.get("id") {
+            self.id = Some(json_int(id));
+        }
+        if let Some(Value::Array(arr)) = doc.get("data") {
+            self.data.extend(arr.iter().map(json_string));
+        }
+        true
+    }
+
+    // The firmware only calls `com.reserve(0)` here, so the last command name survives
+    // and a following line without `command` re-runs it with empty data
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.id = None;
+    }
+
+    pub fn get(&self, index: usize) -> &str {
+        if index == 0 {
+            self.get_com()
+        } else {
+            self.get_data_element(index - 1)
+        }
+    }
+
+    pub fn has_index(&self, index: usize) -> bool {
+        index <= self.get_data_length()
+    }
+}
+
+// `String::toInt` semantics: leading integer, 0 when there is none
+pub fn to_int(value: &str) -> i64 {
+    let value = value.trim_start();
+    let (sign, digits) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let digits: String = digits.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse::<i64>().map(|number| sign * number).unwrap_or(0)
+}
+
+fn json_int(value: &Value) -> i64 {
+    value
+        .as_i64()
+        .or_else(|| value.as_f64().map(|number| number as i64))
+        .unwrap_or(0)
+}
+
+// ArduinoJson `value | fallback`
+fn json_int_or(value: &Value, fallback: i64) -> i64 {
+    if value.is_number() { json_int(value) } else { fallback }
+}
+
+fn parse_output_mode(mode: &str) -> i64 {
+    match mode {
+        "pwm" => OUTPUT_MODE_PWM,
+        "on_off" => OUTPUT_MODE_ON_OFF,
+        _ => -1,
+    }
+}
+
+fn valid_port_settings(settings: &[Value], ports_count: usize) -> bool {
+    if settings.len() > ports_count {
+        return false;
+    }
+    settings.iter().all(|port| {
+        let mode = parse_output_mode(port["output_mode"].as_str().unwrap_or("pwm"));
+        let frequency = json_int_or(&port["pwm_frequency"], DEFAULT_PWM_FREQUENCY);
+        let resolution = json_int_or(&port["pwm_resolution"], DEFAULT_PWM_RESOLUTION);
+
+        mode == OUTPUT_MODE_ON_OFF
+            || (mode == OUTPUT_MODE_PWM
+                && (MIN_PWM_FREQUENCY..=MAX_PWM_FREQUENCY).contains(&frequency)
+                && (1..=MAX_PWM_RESOLUTION).contains(&resolution))
+    })
+}
+
+// ArduinoJson `value | fallback` for floats
+fn json_float_or(value: &Value, fallback: f64) -> f64 {
+    value.as_f64().unwrap_or(fallback)
+}
+
+fn parse_temperature_input(object: &Value) -> Option<TemperatureInput> {
+    let name = object["name"].as_str().unwrap_or_default().to_string();
+    if name.is_empty() {
+        return None;
+    }
+
+    match object["source"].as_str().unwrap_or_default() {
+        "internal" => {
+            return Some(TemperatureInput {
+                name,
+                gpio: INTERNAL_TEMPERATURE_GPIO,
+                series_resistance: 0.0,
+                model: THERMISTOR_MODEL_BETA,
+                params: [0.0; 3],
+            });
+        }
+        "thermistor" => {}
+        _ => return None,
+    }
+
+    let gpio = json_int_or(&object["gpio"], -1);
+    let series_resistance = json_float_or(&object["series_resistance"], 0.0);
+    if !crate::ADC_GPIOS.contains(&gpio) || series_resistance <= 0.0 {
+        return None;
+    }
+
+    let model = &object["model"];
+    let (model_kind, params) = match model["kind"].as_str().unwrap_or_default() {
+        "beta" => {
+            let params = [
+                json_float_or(&model["beta"], 0.0),
+                json_float_or(&model["nominal_resistance"], 0.0),
+                json_float_or(&model["nominal_temperature"], 25.0),
+            ];
+            if params[0] <= 0.0 || params[1] <= 0.0 {
+                return None;
+            }
+            (THERMISTOR_MODEL_BETA, params)
+        }
+        "steinhart_hart" => (
+            THERMISTOR_MODEL_STEINHART_HART,
+            [
+                json_float_or(&model["a"], 0.0),
+                json_float_or(&model["b"], 0.0),
+                json_float_or(&model["c"], 0.0),
+            ],
+        ),
+        _ => return None,
+    };
+
+    Some(TemperatureInput {
+        name,
+        gpio,
+        series_resistance,
+        model: model_kind,
+        params,
+    })
+}
+
+fn parse_standalone_curve(object: &Value, inputs: &[TemperatureInput]) -> Option<StandaloneCurve> {
+    let temperature_input = object["temperature_input"].as_str().unwrap_or_default().to_string();
+    let min_value = json_int_or(&object["min_value"], 0);
+    let max_value = json_int_or(&object["max_value"], MAX_PERMILLE_VALUE);
+    if !inputs.iter().any(|input| input.name == temperature_input) {
+        return None;
+    }
+    if min_value < 0 || max_value > MAX_PERMILLE_VALUE || min_value > max_value {
+        return None;
+    }
+
+    let points = object["curve"].as_array()?;
+    if points.is_empty() || points.len() > MAX_CURVE_POINTS {
+        return None;
+    }
+    let mut temps: Vec<f64> = Vec::new();
+    let mut values = Vec::new();
+    for point in points {
+        let temp = json_float_or(&point["temp"], 0.0);
+        let value = json_int_or(&point["value"], -1);
+        if !(0..=MAX_PERMILLE_VALUE).contains(&value) || temps.last().is_some_and(|&last| temp <= last) {
+            return None;
+        }
+        temps.push(temp);
+        values.push(value);
+    }
+
+    Some(StandaloneCurve {
+        temperature_input,
+        temps,
+        values,
+        min_value,
+        max_value,
+    })
+}
+
+fn json_int_array(value: &Value) -> Vec<i64> {
+    value
+        .as_array()
+        .map(|arr| arr.iter().map(json_int).collect())
+        .unwrap_or_default()
+}
+
+fn json_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        other => other.to_string(),
+    }
+}
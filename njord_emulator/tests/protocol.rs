@@ -0,0 +1,83 @@
+use serde_json::{Value, json};
+use njord_emulator::Emulator;
+
+fn send(emulator: &mut Emulator, command: Value) -> Vec<Value> {
+    emulator
+        .handle_line(&command.to_string())
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn ports_setup_resizes_port_settings() {
+    let mut emulator = Emulator::default();
+    let ports_before = emulator.data.port_config.len();
+
+    send(&mut emulator, json!({"command": "ports_setup", "data": [0, 2, 4]}));
+    let responses = send(&mut emulator, json!({"command": "get_config", "id": 1}));
+    let config = &responses[0]["data"];
+
+    assert!(ports_before < 3);
+    assert_eq!(config["ports"], json!([0, 2, 4]));
+    assert_eq!(config["port_settings"].as_array().unwrap().len(), 3);
+    assert_eq!(config["port_settings"][2]["pwm_frequency"], json!(25000));
+    assert_eq!(config["port_settings"][2]["output_mode"], json!("pwm"));
+    assert_eq!(config["standalone_curves"], json!([null, null, null]));
+}
+
+#[test]
+fn ping_answers_pong_with_the_command_id() {
+    let mut emulator = Emulator::default();
+
+    let responses = send(&mut emulator, json!({"command": "ping", "id": 7}));
+
+    assert_eq!(responses, vec![json!({"code": "ok", "message": "pong", "id": 7})]);
+}
+
+#[test]
+fn board_info_reports_capabilities_unless_legacy() {
+    let mut emulator = Emulator::default();
+
+    let info = &send(&mut emulator, json!({"command": "board_info", "id": 1}))[0]["data"];
+
+    assert_eq!(info["board_name"], json!("njord_emulator"));
+    assert_eq!(info["max_ports"], json!(16));
+    assert!(info["capabilities"].as_array().unwrap().contains(&json!("get_rpm")));
+
+    emulator.legacy = true;
+    let info = &send(&mut emulator, json!({"command": "board_info", "id": 2}))[0]["data"];
+
+    assert_eq!(info, &json!({"board_name": "njord_emulator", "max_ports": 16}));
+}
+
+#[test]
+fn set_values_are_read_back_in_both_scales() {
+    let mut emulator = Emulator::default();
+    send(&mut emulator, json!({"command": "ports_setup", "data": [0, 2]}));
+
+    let responses = send(&mut emulator, json!({"command": "set_values", "id": 1, "data": [0, 40, 1, 101]}));
+    assert_eq!(responses[0]["data"]["results"], json!(["ok", "bad-args"]));
+
+    let responses = send(&mut emulator, json!({"command": "set_values_permille", "id": 2, "data": [1, 555]}));
+    assert_eq!(responses[0]["data"]["results"], json!(["ok"]));
+
+    let responses = send(&mut emulator, json!({"command": "get_value_permille", "id": 3}));
+    assert_eq!(responses[0]["data"]["values"], json!([400, 555]));
+
+    // percent readings round to the nearest step
+    let responses = send(&mut emulator, json!({"command": "get_value", "id": 4}));
+    assert_eq!(responses[0]["data"]["values"], json!([40, 56]));
+}
+
+#[test]
+fn rpm_follows_the_duty_value_on_tach_ports() {
+    let mut emulator = Emulator::default();
+    send(&mut emulator, json!({"command": "ports_setup", "data": [0, 2]}));
+    emulator.data.tach_inputs[1] = 3;
+    send(&mut emulator, json!({"command": "set_values_permille", "data": [0, 500, 1, 250]}));
+
+    let responses = send(&mut emulator, json!({"command": "get_rpm", "id": 1}));
+
+    assert_eq!(responses[0]["data"]["rpm"], json!([null, 500]));
+}
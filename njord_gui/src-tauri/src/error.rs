@@ -0,0 +1,54 @@
+use std::fmt;
+use njord_backend::device::DeviceError;
+use serde::Serialize;
+use serde::ser::SerializeStruct;
+
+// What commands hand back to the frontend, always serialized as `{ kind, code, message, retryable }`
+#[derive(Debug)]
+pub enum AppError {
+    Device(DeviceError),
+    App(String), // app state issues, like an unknown device id
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Device(e) => write!(f, "{}", e),
+            AppError::App(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<DeviceError> for AppError {
+    fn from(e: DeviceError) -> Self {
+        AppError::Device(e)
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::App(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::App(message.to_string())
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            AppError::Device(e) => e.serialize(serializer),
+            AppError::App(message) => {
+                let mut state = serializer.serialize_struct("AppError", 4)?;
+                state.serialize_field("kind", "app")?;
+                state.serialize_field("code", &None::<()>)?;
+                state.serialize_field("message", message)?;
+                state.serialize_field("retryable", &false)?;
+                state.end()
+            }
+        }
+    }
+}
@@ -1,9 +1,17 @@
+use crate::error::AppError;
 use crate::state::{AppState, CoreMessage};
-use njord_backend::controller::{PlugConfig, PlugState};
-use njord_backend::device::{Device, DeviceConfig, DeviceInfo, DeviceState, PortInfo, SerialInfo};
+use njord_backend::actor::DeviceHandle;
+use njord_backend::board::{BoardProfile, ConfigReport};
+use njord_backend::controller::{PlugConfig, PlugState, UpdateStats};
+use njord_backend::device::{
+    ControlStatus, Device, DeviceConfig, DeviceInfo, DeviceState, PortInfo, SerialInfo, StandaloneCurve, TemperatureInput,
+};
+use njord_backend::discovery::{DiscoveredDevice, DiscoveryOptions, discover_devices as discover_njord_devices};
+use njord_backend::firmware::FirmwareUpdateOptions;
 use njord_backend::sensors::{SensorId, SensorType};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::time::Duration;
 use serde::Serialize;
 use tauri::async_runtime::Mutex;
 use tauri::{AppHandle, Emitter, Manager, State};
@@ -17,67 +25,101 @@ pub async fn get_core_messages(state: State<'_, Mutex<AppState>>) -> Result<Vec<
 
 
 #[tauri::command]
-pub fn get_device_list() -> Result<Vec<PortInfo>, ()> {
-    Device::get_device_list()
+pub fn get_device_list() -> Result<Vec<PortInfo>, AppError> {
+    Ok(Device::get_device_list()?)
 }
 
 #[tauri::command]
-pub async fn load_device_info(serial_info: SerialInfo) -> Result<DeviceInfo, String> {
+pub async fn discover_devices(state: State<'_, Mutex<AppState>>) -> Result<Vec<DiscoveredDevice>, AppError> {
+    let options = DiscoveryOptions {
+        exclude_ports: state.lock().await.attached_ports(),
+        ..Default::default()
+    };
+    Ok(discover_njord_devices(&options).await?)
+}
+
+#[tauri::command]
+pub async fn load_device_info(serial_info: SerialInfo) -> Result<DeviceInfo, AppError> {
     let mut device = Device::new(serial_info);
     Ok(device.get_board_info().await?)
 }
 
 #[tauri::command]
-pub async fn load_device_config(serial_info: SerialInfo) -> Result<DeviceConfig, String> {
+pub async fn load_device_config(serial_info: SerialInfo) -> Result<DeviceConfig, AppError> {
     let mut device = Device::new(serial_info);
     Ok(device.get_device_config().await?)
 }
 
 #[tauri::command]
-pub async fn load_connected_device_config(state: State<'_, Mutex<AppState>>, id: String) -> Result<DeviceConfig, String> {
+pub async fn load_connected_device_config(state: State<'_, Mutex<AppState>>, id: String) -> Result<DeviceConfig, AppError> {
     let device = {
         let state_lock = state.lock().await;
         state_lock.devices.get(&id).ok_or("No such device")?.clone()
     };
-    let mut device_lock = device.lock().await;
-    let device_config = device_lock.get_device_config().await?;
+    let device_config = device.get_device_config().await?;
     Ok(device_config)
 }
 
 #[tauri::command]
-pub async fn load_device_default_config(serial_info: SerialInfo) -> Result<DeviceConfig, String> {
+pub async fn load_device_default_config(serial_info: SerialInfo) -> Result<DeviceConfig, AppError> {
     let mut device = Device::new(serial_info);
     Ok(device.get_device_default_config().await?)
 }
 
 #[tauri::command]
-pub async fn load_connected_device_default_config(state: State<'_, Mutex<AppState>>, id: String) -> Result<DeviceConfig, String> {
+pub async fn load_connected_device_default_config(state: State<'_, Mutex<AppState>>, id: String) -> Result<DeviceConfig, AppError> {
     let device = {
         let state_lock = state.lock().await;
         state_lock.devices.get(&id).ok_or("No such device")?.clone()
     };
-    let mut device_lock = device.lock().await;
-    let device_config = device_lock.get_device_default_config().await?;
+    let device_config = device.get_device_default_config().await?;
     Ok(device_config)
 }
 
-pub async fn send_device_summary(app: AppHandle, devices: &HashMap<String, Arc<Mutex<Device>>>) {
+pub async fn send_device_summary(app: AppHandle, devices: &HashMap<String, DeviceHandle>) {
     let mut summaries = HashMap::new();
     for (id, device) in devices {
-        let device = device.lock().await;
-        let summary = device.create_summary();
-        summaries.insert(id.clone(), summary);
+        summaries.insert(id.clone(), device.create_summary());
     }
     let _ = app.emit("devices_update", summaries);
 }
 
+#[tauri::command]
+pub async fn get_board_profiles(state: State<'_, Mutex<AppState>>) -> Result<Vec<BoardProfile>, AppError> {
+    let state_lock = state.lock().await;
+    Ok(state_lock.board_registry.profiles().to_vec())
+}
+
+#[tauri::command]
+pub async fn get_board_profile(state: State<'_, Mutex<AppState>>, device_info: DeviceInfo) -> Result<BoardProfile, AppError> {
+    let state_lock = state.lock().await;
+    Ok(state_lock.board_registry.find(&device_info.board_name))
+}
+
+// Checks a config the way a write would without touching the board. Only an added device has
+// temperature inputs and standalone curves known to check against
+#[tauri::command]
+pub async fn validate_device_config(
+    state: State<'_, Mutex<AppState>>,
+    device_id: Option<String>,
+    device_info: DeviceInfo,
+    device_config: DeviceConfig,
+) -> Result<ConfigReport, AppError> {
+    let state_lock = state.lock().await;
+    if let Some(device) = device_id.and_then(|id| state_lock.devices.get(&id)) {
+        return Ok(device.validate_config(&device_config));
+    }
+    let board_profile = state_lock.board_registry.find(&device_info.board_name);
+    Ok(board_profile.validate_all(&device_info, &device_config, &[], &[]))
+}
+
 #[tauri::command]
 pub async fn add_device(
     app: AppHandle,
     state: State<'_, Mutex<AppState>>,
     serial_info: SerialInfo,
     device_config: DeviceConfig,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     {
         let mut state_lock = state.lock().await;
         state_lock.add_device(serial_info, Some(device_config)).await?;
@@ -91,7 +133,7 @@ pub async fn add_device(
 pub async fn fetch_devices(
     app: AppHandle,
     state: State<'_, Mutex<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let state = state.lock().await;
     send_device_summary(app, &state.devices).await;
     Ok(())
@@ -102,7 +144,7 @@ pub async fn remove_device(
     app: AppHandle,
     state: State<'_, Mutex<AppState>>,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     {
         let mut state_lock = state.lock().await;
         state_lock.remove_device(id).await?;
@@ -112,13 +154,44 @@ pub async fn remove_device(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn update_firmware(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+    image_path: String,
+    expected_version: Option<String>,
+) -> Result<(), AppError> {
+    // the update takes tens of seconds, the state stays unlocked meanwhile
+    let (device, device_store, mut exclude_ports) = {
+        let mut state_lock = state.lock().await;
+        let (device, device_store) = state_lock.begin_firmware_update(&id).await?;
+        (device, device_store, state_lock.attached_ports())
+    };
+    // the board is looked for on its own port first
+    exclude_ports.remove(&device_store.serial_info.com_port);
+    let options = FirmwareUpdateOptions {
+        expected_version,
+        discovery: DiscoveryOptions {
+            exclude_ports,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let updated = device.update_firmware(PathBuf::from(image_path), options).await;
+    state.lock().await.finish_firmware_update(&id, device_store, updated).await?;
+
+    save_settings(app, state).await?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn update_device_config(
     app: AppHandle,
     state: State<'_, Mutex<AppState>>,
     id: String,
     device_config: DeviceConfig,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let mut state = state.lock().await;
 
     state.update_device_config(id, device_config).await?;
@@ -153,7 +226,7 @@ pub async fn set_plug_handler_config(
     plug_index: u8,
     plug_config: PlugConfig,
     sensor_id: SensorId,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     {
         let mut state_lock = state.lock().await;
         state_lock.set_plug_handler(device_id, plug_index, sensor_id, plug_config).await?;
@@ -169,11 +242,11 @@ pub struct PlugHandlerData {
 }
 
 #[tauri::command]
-pub async fn get_plug_handler_config(state: State<'_, Mutex<AppState>>, device_id: String, plug_index: u8) -> Result<Option<PlugHandlerData>, String> {
+pub async fn get_plug_handler_config(state: State<'_, Mutex<AppState>>, device_id: String, plug_index: u8) -> Result<Option<PlugHandlerData>, AppError> {
     let mut state_lock = state.lock().await;
-    let handler_vec = state_lock.plug_handlers.get(&device_id).ok_or("No such device".to_string())?;
+    let handler_vec = state_lock.plug_handlers.get(&device_id).ok_or("No such device")?;
     let handler_vec_lock = handler_vec.lock().await;
-    let handler_option = handler_vec_lock.get(plug_index as usize).ok_or("No such plug".to_string())?;
+    let handler_option = handler_vec_lock.get(plug_index as usize).ok_or("No such plug")?;
     match handler_option {
         Some(handler) => {
             Ok(Some(PlugHandlerData {
@@ -186,10 +259,10 @@ pub async fn get_plug_handler_config(state: State<'_, Mutex<AppState>>, device_i
 }
 
 #[tauri::command]
-pub async fn get_plug_states(state: State<'_, Mutex<AppState>>, device_id: String) -> Result<Vec<Option<PlugState>>, String> {
+pub async fn get_plug_states(state: State<'_, Mutex<AppState>>, device_id: String) -> Result<Vec<Option<PlugState>>, AppError> {
     let plug_handlers_arc = {
         let mut state_lock = state.lock().await;
-        state_lock.plug_handlers.get(&device_id).ok_or("No such device".to_string())?.clone()
+        state_lock.plug_handlers.get(&device_id).ok_or("No such device")?.clone()
     };
     let plug_handlers_lock = plug_handlers_arc.lock().await;
 
@@ -204,21 +277,143 @@ pub async fn get_plug_states(state: State<'_, Mutex<AppState>>, device_id: Strin
 }
 
 #[tauri::command]
-pub async fn get_device_status(state: State<'_, Mutex<AppState>>, device_id: String) -> Result<DeviceState, String> {
+pub async fn get_device_status(state: State<'_, Mutex<AppState>>, device_id: String) -> Result<DeviceState, AppError> {
+    let state_lock = state.lock().await;
+    let device = state_lock.devices.get(&device_id).ok_or("No such device")?;
+
+    Ok(device.device_state())
+}
+
+#[tauri::command]
+pub async fn get_update_stats(state: State<'_, Mutex<AppState>>, device_id: String) -> Result<UpdateStats, AppError> {
+    let state_lock = state.lock().await;
+    state_lock.get_update_stats(&device_id).await
+}
+
+#[tauri::command]
+pub async fn reboot_device(app: AppHandle, state: State<'_, Mutex<AppState>>, id: String) -> Result<(), AppError> {
+    let device = state.lock().await.begin_device_restart(&id)?;
+    let rebooted = device.reboot().await;
+
+    let mut state_lock = state.lock().await;
+    state_lock.finish_reboot(&id, rebooted).await?;
+
+    send_device_summary(app, &state_lock.devices).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn factory_reset_device(app: AppHandle, state: State<'_, Mutex<AppState>>, id: String) -> Result<(), AppError> {
+    let device = state.lock().await.begin_device_restart(&id)?;
+    let reset = device.factory_reset().await;
+    state.lock().await.finish_factory_reset(&id, reset).await?;
+
+    save_settings(app, state).await?;
+    Ok(())
+}
+
+// Stored on the board, every input shows up as a device sensor right away
+#[tauri::command]
+pub async fn set_temperature_inputs(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+    temperature_inputs: Vec<TemperatureInput>,
+) -> Result<(), AppError> {
+    // the board round trip runs without the state locked
+    let device = state.lock().await.devices.get(&id).ok_or("No such device")?.clone();
+    device.set_temperature_inputs(temperature_inputs).await?;
+
+    let mut state_lock = state.lock().await;
+    // handlers keep following inputs by name, renamed or removed ones report an error until reassigned
+    state_lock.register_device_sensors(&id);
+    send_device_summary(app, &state_lock.devices).await;
+    Ok(())
+}
+
+// Curves the board runs on its own temperature inputs while the app isn't running
+#[tauri::command]
+pub async fn set_standalone_curves(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+    standalone_curves: Vec<Option<StandaloneCurve>>,
+) -> Result<(), AppError> {
+    let device = {
+        let state_lock = state.lock().await;
+        state_lock.devices.get(&id).ok_or("No such device")?.clone()
+    };
+    // the board checks what it stored, a curve it didn't take fails the upload
+    device.set_standalone_curves(standalone_curves).await?;
+
     let state_lock = state.lock().await;
-    let device_arc = state_lock.devices.get(&device_id).ok_or("No such device".to_string())?;
-    let device_lock = device_arc.lock().await;
+    send_device_summary(app, &state_lock.devices).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_standalone_curves(state: State<'_, Mutex<AppState>>, id: String) -> Result<Vec<Option<StandaloneCurve>>, AppError> {
+    let device = {
+        let state_lock = state.lock().await;
+        state_lock.devices.get(&id).ok_or("No such device")?.clone()
+    };
+    Ok(device.get_standalone_curves().await?)
+}
 
-    Ok(device_lock.device_state.clone())
+#[tauri::command]
+pub async fn verify_standalone_curves(
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+    standalone_curves: Vec<Option<StandaloneCurve>>,
+) -> Result<bool, AppError> {
+    let device = {
+        let state_lock = state.lock().await;
+        state_lock.devices.get(&id).ok_or("No such device")?.clone()
+    };
+    Ok(device.verify_standalone_curves(standalone_curves).await?)
+}
+
+// Asking leaves a standalone board standalone
+#[tauri::command]
+pub async fn get_control_status(state: State<'_, Mutex<AppState>>, device_id: String) -> Result<ControlStatus, AppError> {
+    let device = {
+        let state_lock = state.lock().await;
+        state_lock.devices.get(&device_id).ok_or("No such device")?.clone()
+    };
+    Ok(device.get_control_status().await?)
+}
+
+// Blinks the board's status LED so it can be told apart from the others
+#[tauri::command]
+pub async fn identify_device(state: State<'_, Mutex<AppState>>, id: String, seconds: u64) -> Result<(), AppError> {
+    let device = {
+        let state_lock = state.lock().await;
+        state_lock.devices.get(&id).ok_or("No such device")?.clone()
+    };
+    device.identify(Duration::from_secs(seconds)).await?;
+    Ok(())
+}
+
+// Records the device's serial traffic into a JSON-lines file, no path stops the recording
+#[tauri::command]
+pub async fn set_device_trace(state: State<'_, Mutex<AppState>>, device_id: String, trace_path: Option<String>) -> Result<(), AppError> {
+    let device = {
+        let state_lock = state.lock().await;
+        state_lock.devices.get(&device_id).ok_or("No such device")?.clone()
+    };
+    device.set_trace(trace_path.map(PathBuf::from)).await?;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn load_settings(app: AppHandle,
-                           state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+                           state: State<'_, Mutex<AppState>>) -> Result<(), AppError> {
     let mut path = app.path().app_data_dir().unwrap();
     path.push("settings.json");
     let path_str = path.to_string_lossy();
     let mut state_lock = state.lock().await;
+    // devices restored below need the custom boards already
+    state_lock.load_board_profiles(&path.with_file_name("boards.json"));
     Storage::load_data(&path_str, &mut state_lock).await?;
 
     send_device_summary(app, &state_lock.devices).await;
@@ -227,7 +422,7 @@ pub async fn load_settings(app: AppHandle,
 
 #[tauri::command]
 pub async fn save_settings(app: AppHandle,
-                           state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+                           state: State<'_, Mutex<AppState>>) -> Result<(), AppError> {
     let mut path = app.path().app_data_dir().unwrap();
     path.push("settings.json");
     let path_str = path.to_string_lossy();
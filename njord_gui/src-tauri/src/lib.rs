@@ -1,3 +1,4 @@
+mod error;
 mod handlers;
 mod state;
 mod utils;
@@ -15,7 +16,7 @@ pub fn run() {
              Ok(())
          })
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![handlers::get_device_list, handlers::load_device_info, handlers::get_core_messages, handlers::load_device_config, handlers::load_device_default_config, handlers::add_device, handlers::remove_device, handlers::update_device_config, handlers::get_sensors, handlers::set_plug_handler_config, handlers::get_plug_handler_config, handlers::load_connected_device_default_config, handlers::get_plug_states, handlers::load_connected_device_config, handlers::load_settings, handlers::get_device_status])
+        .invoke_handler(tauri::generate_handler![handlers::get_device_list, handlers::discover_devices, handlers::load_device_info, handlers::get_core_messages, handlers::load_device_config, handlers::load_device_default_config, handlers::add_device, handlers::remove_device, handlers::update_device_config, handlers::get_sensors, handlers::set_plug_handler_config, handlers::get_plug_handler_config, handlers::load_connected_device_default_config, handlers::get_plug_states, handlers::load_connected_device_config, handlers::load_settings, handlers::get_device_status, handlers::get_update_stats, handlers::update_firmware, handlers::set_device_trace, handlers::validate_device_config, handlers::get_board_profiles, handlers::get_board_profile, handlers::reboot_device, handlers::factory_reset_device, handlers::identify_device, handlers::set_temperature_inputs, handlers::set_standalone_curves, handlers::get_standalone_curves, handlers::verify_standalone_curves, handlers::get_control_status])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
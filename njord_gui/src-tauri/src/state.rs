@@ -1,19 +1,40 @@
+use crate::error::AppError;
+use crate::handlers::send_device_summary;
+use crate::storage::{DeviceStore, Storage};
 use crate::utils::ping_and_reconnect;
-use njord_backend::controller::{PlugConfig, PlugHandler, PlugState};
-use njord_backend::device::{Device, DeviceConfig, DeviceState, SerialInfo};
+use njord_backend::actor::DeviceHandle;
+use njord_backend::board::BoardRegistry;
+use njord_backend::controller::{PlugConfig, PlugHandler, PlugState, UpdateStats, ValueSync};
+use njord_backend::device::{Device, DeviceConfig, DeviceError, DeviceEvent, DeviceState, PortValue, SerialInfo};
+use njord_backend::discovery::DiscoveredDevice;
+use njord_backend::hotplug::{PortEvent, PortSnapshot, PortWatcher};
+use njord_backend::reconnect::{ConnectionState, GiveUp, ReconnectPolicy};
 use njord_backend::sensors::{Sensor, SensorFactory, SensorId, SensorType, SensorsProvidersStates};
+use njord_backend::sensors_providers::device_sensor::DeviceSensor;
 use njord_backend::sensors_providers::lhm_sensor::LhmState;
 use njord_backend::sensors_providers::nvml_sensor::NvmlState;
 use serde::Serialize;
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager, Wry};
 use tauri::async_runtime::{JoinHandle, Mutex};
 use tauri::menu::{Menu, MenuBuilder, MenuItem, Submenu, SubmenuBuilder};
 use tauri::tray::{TrayIconBuilder, TrayIconId};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::time::sleep;
 
+const HOTPLUG_POLL_INTERVAL: Duration = Duration::from_millis(2000);
+const IDLE_WORKER_INTERVAL: Duration = Duration::from_millis(500);
+const MIN_WORKER_INTERVAL: Duration = Duration::from_millis(10);
+const TACH_READ_INTERVAL: Duration = Duration::from_millis(1000); // boards count pulses over a second
+const TEMPERATURE_READ_INTERVAL: Duration = Duration::from_millis(1000);
+const HEARTBEATS_PER_TIMEOUT: u32 = 3; // a single lost heartbeat doesn't trip the failsafe
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_millis(5000);
+const MIN_REPORTED_STANDALONE_TIME: Duration = Duration::from_secs(5); // booting and enumerating alone take a moment
+
 #[derive(Serialize, Clone)]
 pub enum CoreMessageKind {
     Info,
@@ -31,13 +52,22 @@ pub struct CoreMessage {
 struct HandlerWorker {
     pub worker_join: JoinHandle<()>,
     pub worker_stop_signal: Arc<Mutex<bool>>,
+    pub value_sync: Arc<Mutex<ValueSync>>,
 }
 
 pub struct AppState {
     app: AppHandle,
-    pub devices: HashMap<String, Arc<Mutex<Device>>>,
+    pub devices: HashMap<String, DeviceHandle>,
     pub plug_handlers: HashMap<String, Arc<Mutex<Vec<Option<PlugHandler>>>>>,
     pub handler_workers: HashMap<String, HandlerWorker>,
+    pub event_listeners: HashMap<String, JoinHandle<()>>,
+    pub device_ports: HashMap<String, PortSnapshot>,
+    pub offline_devices: HashMap<String, DeviceStore>,
+    // devices in the middle of a reboot or firmware update, their port comes and goes without hotplug stepping in
+    pub busy_devices: HashSet<String>,
+    pub refresh_interval: Duration, // values are re-sent this often even if they didn't change
+    pub reconnect_policy: ReconnectPolicy,
+    pub board_registry: Arc<BoardRegistry>,
     pub sensors_providers_states: SensorsProvidersStates,
     pub sensors: HashMap<SensorType, HashMap<String, Arc<dyn Sensor>>>,
     pub core_messages: Vec<CoreMessage>,
@@ -73,6 +103,13 @@ impl AppState {
             devices: HashMap::new(),
             plug_handlers: HashMap::new(),
             handler_workers: HashMap::new(),
+            event_listeners: HashMap::new(),
+            device_ports: HashMap::new(),
+            offline_devices: HashMap::new(),
+            busy_devices: HashSet::new(),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            reconnect_policy: ReconnectPolicy::default(),
+            board_registry: Arc::new(BoardRegistry::builtin()),
             sensors_providers_states,
             sensors,
             core_messages,
@@ -80,80 +117,407 @@ impl AppState {
         app.manage(Mutex::new(state_self));
         let app_handle = app.clone();
 
-        tauri::async_runtime::spawn(tray_menu_loop(app_handle));
+        tauri::async_runtime::spawn(tray_menu_loop(app_handle.clone()));
+        tauri::async_runtime::spawn(hotplug_loop(app_handle));
+    }
+
+    // Profiles of custom boards on top of the built-in ones, a missing file keeps just the built-in ones
+    pub fn load_board_profiles(&mut self, path: &Path) {
+        if !path.exists() {
+            return;
+        }
+        let mut board_registry = BoardRegistry::builtin();
+        match board_registry.load_file(path) {
+            Ok(()) => self.board_registry = Arc::new(board_registry),
+            Err(e) => self.core_messages.push(CoreMessage {
+                kind: CoreMessageKind::Error,
+                message: format!("Failed loading board profiles ({})", e),
+            }),
+        }
     }
 
+    // Returns the id the device ended up under, see `DeviceInfo::stable_id`
     pub async fn add_device(
         &mut self,
         serial_info: SerialInfo,
         device_config_option: Option<DeviceConfig>,
-    ) -> Result<(), String> {
-        let id = serial_info.com_port.clone();
-        if self.devices.contains_key(&id) {
-            self.remove_device(id.clone()).await?;
+    ) -> Result<String, AppError> {
+        let com_port = serial_info.com_port.clone();
+        // whatever is attached to this port has to let it go first, offline devices only remember it
+        for id in self.device_ids_on_port(&com_port).await {
+            self.remove_device(id).await?;
         }
 
+        let port = PortWatcher::list_ports()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|port| port.name == serial_info.com_port);
         let mut device = Device::new(serial_info);
+        device.set_reconnect_policy(self.reconnect_policy.clone());
+        device.set_board_registry(self.board_registry.clone());
+        let device = DeviceHandle::spawn(device);
 
         if let Some(device_config) = device_config_option {
-            device.set_device_config(&device_config).await?;
-            ping_and_reconnect(&mut device).await?;
+            device.set_device_config(device_config).await?;
+            ping_and_reconnect(&device).await?;
         }
 
         device.fetch_data().await?;
 
+        let id = device
+            .create_summary()
+            .device_info
+            .stable_id(port.as_ref().and_then(|port| port.usb_serial_number.as_deref()))
+            .unwrap_or(com_port);
+        if self.devices.contains_key(&id) {
+            self.remove_device(id.clone()).await?;
+        }
+
         let mut plug_handlers_vec = Vec::new();
-        plug_handlers_vec.resize(device.device_config.ports.len(), None);
+        plug_handlers_vec.resize(device.device_config().ports.len(), None);
+        let device_events = device.subscribe_events();
 
-        self.devices
-            .insert(id.to_string(), Arc::new(Mutex::new(device)));
+        self.report_standalone_run(&id, &device);
+        self.devices.insert(id.to_string(), device);
+        self.register_device_sensors(&id);
 
         self.plug_handlers.insert(id.to_string(), Arc::new(Mutex::new(plug_handlers_vec)));
         self.create_handler_worker(&id.to_string())?;
+        self.create_event_listener(&id.to_string(), device_events);
+        if let Some(port) = port {
+            self.device_ports.insert(id.to_string(), port);
+        }
+
+        Ok(id)
+    }
+
+    // Boards with standalone curves keep the fans going while the app is away, this tells for how long
+    fn report_standalone_run(&mut self, id: &String, device: &DeviceHandle) {
+        let Some(standalone_time) = device.create_summary().control_status.and_then(|status| status.standalone_time) else {
+            return;
+        };
+        let standalone_time = Duration::from_millis(standalone_time);
+        if standalone_time >= MIN_REPORTED_STANDALONE_TIME {
+            self.core_messages.push(CoreMessage {
+                kind: CoreMessageKind::Info,
+                message: format!("Device {} ran standalone for {} s before the host took over", id, standalone_time.as_secs()),
+            });
+        }
+    }
+
+    // Temperature inputs of the board become sensors any plug can follow, of this device or another one
+    pub fn register_device_sensors(&mut self, id: &String) {
+        self.unregister_device_sensors(id);
+        let Some(device) = self.devices.get(id) else {
+            return;
+        };
+        let device_sensors = self.sensors.entry(SensorType::DeviceSensor).or_default();
+        for sensor in DeviceSensor::get_sensors(id, device) {
+            device_sensors.insert(sensor.get_sensor_id().identifier, sensor);
+        }
+    }
+
+    fn unregister_device_sensors(&mut self, id: &String) {
+        if let Some(device_sensors) = self.sensors.get_mut(&SensorType::DeviceSensor) {
+            device_sensors.retain(|identifier, _| !DeviceSensor::belongs_to(identifier, id));
+        }
+    }
+
+    async fn device_ids_on_port(&self, com_port: &str) -> Vec<String> {
+        let mut ids = Vec::new();
+        for (id, device) in &self.devices {
+            if self.offline_devices.contains_key(id) {
+                continue;
+            }
+            if device.create_summary().serial_info.com_port == com_port {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
+
+    // Keeps the device and its configuration around, but stops talking to it until its port is back
+    pub async fn set_device_offline(&mut self, id: &String) -> Result<(), AppError> {
+        let device_store = Storage::snapshot_device(self, id).await?;
+
+        self.stop_worker(id).await;
+        self.handler_workers.remove(id);
+        if let Some(listener) = self.event_listeners.remove(id) {
+            listener.abort();
+        }
+
+        if let Some(device) = self.devices.get(id) {
+            device.set_device_state(DeviceState::Offline).await?;
+        }
+        self.offline_devices.insert(id.clone(), device_store);
+
+        Ok(())
+    }
+
+    pub fn add_offline_device(&mut self, device_store: DeviceStore) {
+        let id = device_store.device_id.clone();
+        let mut device = Device::new(device_store.serial_info.clone());
+        device.set_reconnect_policy(self.reconnect_policy.clone());
+        device.set_board_registry(self.board_registry.clone());
+        device.device_state = DeviceState::Offline;
+
+        self.devices.insert(id.clone(), DeviceHandle::spawn(device));
+        self.plug_handlers.insert(id.clone(), Arc::new(Mutex::new(Vec::new())));
+        self.offline_devices.insert(id, device_store);
+    }
+
+    // Ports held open by attached devices, probing them for discovery would steal their responses
+    pub fn attached_ports(&self) -> HashSet<String> {
+        self.devices
+            .iter()
+            .filter(|(id, _)| !self.offline_devices.contains_key(*id))
+            .map(|(_, device)| device.create_summary().serial_info.com_port)
+            .collect()
+    }
+
+    pub async fn reattach_device(&mut self, id: &String, port: &PortSnapshot) -> Result<(), AppError> {
+        let mut device_store = self.offline_devices.get(id).cloned().ok_or("No such offline device")?;
+        self.remove_device(id.clone()).await?;
+
+        device_store.serial_info.com_port = port.name.clone();
+        if let Err(e) = Storage::restore_device(self, device_store.clone()).await {
+            self.add_offline_device(device_store);
+            return Err(e);
+        }
 
         Ok(())
     }
 
-    fn create_handler_worker(&mut self, id: &String) -> Result<(), String>{
+    // Marks the device busy and stops driving it. The update itself runs without the state locked,
+    // see `finish_firmware_update`
+    pub async fn begin_firmware_update(&mut self, id: &String) -> Result<(DeviceHandle, DeviceStore), AppError> {
+        let device = self.begin_device_restart(id)?;
+        let device_store = match Storage::snapshot_device(self, id).await {
+            Ok(device_store) => device_store,
+            Err(e) => {
+                self.busy_devices.remove(id);
+                return Err(e);
+            }
+        };
+
+        self.stop_worker(id).await;
+        self.handler_workers.remove(id);
+        if let Some(listener) = self.event_listeners.remove(id) {
+            listener.abort();
+        }
+
+        Ok((device, device_store))
+    }
+
+    // The board rebooted and may be back on another port, it is restored with its stored config there
+    pub async fn finish_firmware_update(
+        &mut self,
+        id: &String,
+        mut device_store: DeviceStore,
+        updated: Result<DiscoveredDevice, DeviceError>,
+    ) -> Result<(), AppError> {
+        self.busy_devices.remove(id);
+        self.remove_device(id.clone()).await?;
+        if let Ok(updated) = &updated {
+            device_store.serial_info = updated.serial_info.clone();
+        }
+        let restored = Storage::restore_device(self, device_store.clone()).await;
+        if restored.is_err() {
+            self.add_offline_device(device_store);
+        }
+
+        updated?;
+        restored
+    }
+
+    fn find_offline_device(&self, port: &PortSnapshot) -> Option<String> {
+        self.offline_devices
+            .iter()
+            .find(|(_, device_store)| match (&device_store.usb_serial_number, &port.usb_serial_number) {
+                (Some(stored), Some(current)) => stored == current,
+                _ => device_store.serial_info.com_port == port.name,
+            })
+            .map(|(id, _)| id.clone())
+    }
+
+    // Returns false when a reattach failed, so the port gets reported again on the next poll
+    async fn handle_port_event(&mut self, event: &PortEvent) -> bool {
+        match event {
+            PortEvent::Removed(port) => {
+                let ids: Vec<String> = self
+                    .device_ports
+                    .iter()
+                    .filter(|(id, device_port)| {
+                        device_port.name == port.name
+                            && !self.offline_devices.contains_key(*id)
+                            && !self.busy_devices.contains(*id)
+                    })
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                for id in ids {
+                    if self.set_device_offline(&id).await.is_ok() {
+                        self.core_messages.push(CoreMessage {
+                            kind: CoreMessageKind::Warning,
+                            message: format!("Device {} was disconnected", id),
+                        });
+                    }
+                }
+                true
+            }
+            PortEvent::Added(port) => {
+                let Some(id) = self.find_offline_device(port) else {
+                    return true;
+                };
+                match self.reattach_device(&id, port).await {
+                    Ok(()) => {
+                        self.core_messages.push(CoreMessage {
+                            kind: CoreMessageKind::Info,
+                            message: format!("Device {} reattached on {}", id, port.name),
+                        });
+                        true
+                    }
+                    Err(e) => {
+                        eprintln!("Failed reattaching device {} on {} ({})", id, port.name, e);
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    fn create_event_listener(&mut self, id: &String, mut device_events: broadcast::Receiver<DeviceEvent>) {
+        let app = self.app.clone();
+        let device_id = id.clone();
+
+        let join_handle = tauri::async_runtime::spawn(async move {
+            loop {
+                let event = match device_events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                let _ = app.emit("device_event", (&device_id, &event));
+
+                let reason = match event {
+                    DeviceEvent::HardwareReset => "was reset",
+                    DeviceEvent::Failsafe => "lost the host heartbeat and entered failsafe",
+                    DeviceEvent::Connection(ConnectionState::Failed { attempts }) => {
+                        // setting the device offline stops this listener, so it is done on its own
+                        tauri::async_runtime::spawn(give_up_device(app.clone(), device_id.clone(), attempts));
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let state: tauri::State<Mutex<AppState>> = app.state();
+                let mut state_lock = state.lock().await;
+                let message = match state_lock.reapply_plug_values(&device_id).await {
+                    Ok(()) => CoreMessage {
+                        kind: CoreMessageKind::Warning,
+                        message: format!("Device {} {}, plug values re-applied", device_id, reason),
+                    },
+                    Err(e) => CoreMessage {
+                        kind: CoreMessageKind::Error,
+                        message: format!("Device {} {}, failed to re-apply plug values ({})", device_id, reason, e),
+                    },
+                };
+                state_lock.core_messages.push(message);
+            }
+        });
+
+        if let Some(old_listener) = self.event_listeners.insert(id.to_string(), join_handle) {
+            old_listener.abort();
+        }
+    }
+
+    async fn reapply_plug_values(&mut self, id: &String) -> Result<(), AppError> {
+        let device = self.devices.get(id).ok_or("No such device")?.clone();
+        let value_sync = self.handler_workers.get(id).ok_or("No such device")?.value_sync.clone();
+        let values: Vec<(u8, PortValue)> = {
+            let plug_handlers = self.plug_handlers.get(id).ok_or("No such device")?.lock().await;
+            plug_handlers
+                .iter()
+                .flatten()
+                .map(|plug_handler| (plug_handler.plug_index(), plug_handler.get_state().plug_value))
+                .collect()
+        };
+
+        value_sync.lock().await.invalidate();
+        sync_plug_values(&device, &value_sync, &values).await?;
+        Ok(())
+    }
+
+    pub async fn get_update_stats(&self, id: &String) -> Result<UpdateStats, AppError> {
+        let worker = self.handler_workers.get(id).ok_or("No such device")?;
+        Ok(worker.value_sync.lock().await.stats())
+    }
+
+    fn create_handler_worker(&mut self, id: &String) -> Result<(), AppError>{
         let stop_signal = Arc::new(Mutex::new(false));
-        let plug_handler_vec = self.plug_handlers.get(id).ok_or("No such device".to_string())?;
+        let value_sync = Arc::new(Mutex::new(ValueSync::new(self.refresh_interval)));
+        let plug_handler_vec = self.plug_handlers.get(id).ok_or("No such device")?;
+        let device = self.devices.get(id).ok_or("No such device")?.clone();
 
         let handler_stop_signal = stop_signal.clone();
+        let handler_value_sync = value_sync.clone();
         let plug_handlers = plug_handler_vec.clone();
 
         let join_handler = tauri::async_runtime::spawn(async move{
-            let mut sleep_time = 0;
-            let mut last_update = Instant::now();
-
-            'handler: loop {
+            let mut last_tach_read: Option<Instant> = None;
+            let mut last_temperature_read: Option<Instant> = None;
+            let mut last_contact = Instant::now();
+            loop {
                 if *handler_stop_signal.lock().await {
                     println!("Good bye cap");
-                    break 'handler;
+                    break;
                 }
 
-                if last_update.elapsed().as_millis() as u64 >= sleep_time {
-                    last_update = Instant::now();
+                let mut update_time = None;
+                let mut values = Vec::new();
+                {
                     let mut plug_handler_lock = plug_handlers.lock().await;
-                    if let Some(first_handler_option) = plug_handler_lock.first() {
-                        if let Some(handler_option) = first_handler_option {
-                            sleep_time = handler_option.plug_externals.update_time;
+                    for handler in plug_handler_lock.iter_mut().flatten() {
+                        update_time.get_or_insert(handler.plug_externals.update_time);
+                        match handler.calculate_speed() {
+                            Ok(Some(value)) => values.push((handler.plug_index(), value)),
+                            Ok(None) => {}
+                            Err(data) => eprintln!("{}", data),
                         }
                     }
-                    for handler_option in plug_handler_lock.iter_mut() {
-                        if let Some(handler) = handler_option {
-                            if let Err(data) = handler.calculate_speed().await {
-                                eprintln!("{}", data);
-                            }
+                }
+
+                match sync_plug_values(&device, &handler_value_sync, &values).await {
+                    Ok(true) => last_contact = Instant::now(),
+                    Ok(false) => {}
+                    Err(data) => eprintln!("{}", data),
+                }
+                keep_alive(&device, &mut last_contact).await;
+
+                if last_tach_read.is_none_or(|last_read| last_read.elapsed() >= TACH_READ_INTERVAL) {
+                    last_tach_read = Some(Instant::now());
+                    if let Some(rpm) = read_rpm(&device).await {
+                        let mut plug_handler_lock = plug_handlers.lock().await;
+                        for handler in plug_handler_lock.iter_mut().flatten() {
+                            handler.set_rpm(rpm.get(handler.plug_index() as usize).copied().flatten());
                         }
                     }
                 }
+
+                // device sensors read from the snapshot this refreshes
+                if last_temperature_read.is_none_or(|last_read| last_read.elapsed() >= TEMPERATURE_READ_INTERVAL) {
+                    last_temperature_read = Some(Instant::now());
+                    read_temperatures(&device).await;
+                }
+
+                let update_time = update_time.map_or(IDLE_WORKER_INTERVAL, Duration::from_millis);
+                sleep(update_time.max(MIN_WORKER_INTERVAL)).await;
             }
         });
 
         self.handler_workers
             .insert(id.to_string(), HandlerWorker {
                 worker_join: join_handler,
-                worker_stop_signal: stop_signal
+                worker_stop_signal: stop_signal,
+                value_sync,
             });
 
         Ok(())
@@ -168,7 +532,7 @@ impl AppState {
         }
     }
 
-    async fn clean_and_resize_plug_handlers(&mut self, id: &String, new_size: usize) -> Result<(), String>{
+    async fn clean_and_resize_plug_handlers(&mut self, id: &String, new_size: usize) -> Result<(), AppError>{
         let plug_handlers_arc = self.plug_handlers.get(id).ok_or("No such device")?;
 
         let mut plug_handlers = plug_handlers_arc.lock().await;
@@ -178,28 +542,59 @@ impl AppState {
         Ok(())
     }
 
-    pub async fn remove_device(&mut self, id: String) -> Result<(), String>{
+    pub async fn remove_device(&mut self, id: String) -> Result<(), AppError>{
 
         self.clean_and_resize_plug_handlers(&id, 0).await?; // dropping all plug_handlers
         self.plug_handlers.remove(&id);
         self.devices.remove(&id);
+        self.unregister_device_sensors(&id);
 
         self.stop_worker(&id).await;
         self.handler_workers.remove(&id);
+        if let Some(listener) = self.event_listeners.remove(&id) {
+            listener.abort();
+        }
+        self.device_ports.remove(&id);
+        self.offline_devices.remove(&id);
 
         Ok(())
     }
 
-    pub async fn update_device_config(&mut self, id: String, device_config: DeviceConfig) -> Result<(), String>{
+    // Reboots, resets and updates wait seconds for the board, they run without the state locked and
+    // hand the result to the matching `finish_` method
+    pub fn begin_device_restart(&mut self, id: &String) -> Result<DeviceHandle, AppError> {
+        let device = self.devices.get(id).ok_or("No such device")?.clone();
+        if !self.busy_devices.insert(id.clone()) {
+            return Err("The device is busy".into());
+        }
+        Ok(device)
+    }
+
+    pub async fn finish_reboot(&mut self, id: &String, rebooted: Result<(), DeviceError>) -> Result<(), AppError> {
+        self.busy_devices.remove(id);
+        rebooted?;
+        // the board came back on default_values
+        self.reapply_plug_values(id).await
+    }
+
+    pub async fn finish_factory_reset(&mut self, id: &String, reset: Result<(), DeviceError>) -> Result<(), AppError> {
+        self.busy_devices.remove(id);
+        reset?;
+        let device = self.devices.get(id).ok_or("No such device")?.clone();
+        self.register_device_sensors(id); // the temperature inputs went with the rest of the storage
+        // the board is back on its default ports, handlers of the old ones would drive the wrong pins
+        self.clean_and_resize_plug_handlers(id, device.device_config().ports.len()).await
+    }
+
+    pub async fn update_device_config(&mut self, id: String, device_config: DeviceConfig) -> Result<(), AppError>{
         self.clean_and_resize_plug_handlers(&id, device_config.ports.len()).await?;
 
         let device_option = self.devices.get(&id);
 
-        if let Some(device_ark) = device_option{
-            let mut device = device_ark.lock().await;
-            device.set_device_config(&device_config).await?;
+        if let Some(device) = device_option{
+            device.set_device_config(device_config).await?;
 
-            ping_and_reconnect(&mut *device).await?;
+            ping_and_reconnect(device).await?;
 
             device.fetch_data().await?;
         }
@@ -207,16 +602,16 @@ impl AppState {
         Ok(())
     }
 
-    pub async fn set_plug_handler(&mut self, device_id: String, plug_index: u8, sensor_id: SensorId, plug_config: PlugConfig) -> Result<(), String>{
-        let device = self.devices.get(&device_id).ok_or("No such device".to_string())?;
+    pub async fn set_plug_handler(&mut self, device_id: String, plug_index: u8, sensor_id: SensorId, plug_config: PlugConfig) -> Result<(), AppError>{
+        let device = self.devices.get(&device_id).ok_or("No such device")?;
         let sensor = self.sensors
             .get(&sensor_id.sensor_type)
-            .ok_or("No such sensor provider".to_string())?
+            .ok_or("No such sensor provider")?
             .get(&sensor_id.identifier)
-            .ok_or("No such sensor".to_string())?;
-        let mut plug_handlers = self.plug_handlers.get(&device_id).ok_or("No such device".to_string())?.lock().await;
+            .ok_or("No such sensor")?;
+        let mut plug_handlers = self.plug_handlers.get(&device_id).ok_or("No such device")?.lock().await;
 
-        let plug_handler_option = plug_handlers.get_mut(plug_index as usize).ok_or("No such plug".to_string())?;
+        let plug_handler_option = plug_handlers.get_mut(plug_index as usize).ok_or("No such plug")?;
 
         if let Some(plug_handler) = plug_handler_option{
             plug_handler.set_config(plug_config);
@@ -229,6 +624,61 @@ impl AppState {
     }
 }
 
+// The device ran out of reconnect attempts, see `ReconnectPolicy::give_up`
+async fn give_up_device(app: AppHandle<Wry>, id: String, attempts: u32) {
+    let state: tauri::State<Mutex<AppState>> = app.state();
+    let mut state_lock = state.lock().await;
+    let Some(device) = state_lock.devices.get(&id) else {
+        return;
+    };
+    // the policy decides, the device state may already be overwritten by a failed worker write
+    let give_up = device.snapshot().reconnect_policy.give_up;
+
+    let message = if give_up == GiveUp::Offline && state_lock.set_device_offline(&id).await.is_ok() {
+        CoreMessage {
+            kind: CoreMessageKind::Warning,
+            message: format!("Device {} didn't come back after {} reconnect attempts, waiting for it to be reattached", id, attempts),
+        }
+    } else {
+        CoreMessage {
+            kind: CoreMessageKind::Error,
+            message: format!("Device {} didn't come back after {} reconnect attempts", id, attempts),
+        }
+    };
+    state_lock.core_messages.push(message);
+    send_device_summary(app.clone(), &state_lock.devices).await;
+}
+
+async fn hotplug_loop(app_handle: AppHandle<Wry>) {
+    let mut port_watcher = PortWatcher::new();
+
+    loop {
+        sleep(HOTPLUG_POLL_INTERVAL).await;
+
+        let events = match port_watcher.poll() {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("Failed polling serial ports ({})", e);
+                continue;
+            }
+        };
+        if events.is_empty() {
+            continue;
+        }
+
+        let state: tauri::State<Mutex<AppState>> = app_handle.state();
+        let mut state_lock = state.lock().await;
+        for event in &events {
+            if !state_lock.handle_port_event(event).await {
+                if let PortEvent::Added(port) = event {
+                    port_watcher.forget(&port.name);
+                }
+            }
+        }
+        send_device_summary(app_handle.clone(), &state_lock.devices).await;
+    }
+}
+
 async fn tray_menu_loop(app_handle: AppHandle<Wry>) {
     let tray_menu = MenuBuilder::new(&app_handle)
         .build()
@@ -261,8 +711,7 @@ async fn tray_menu_loop(app_handle: AppHandle<Wry>) {
 async fn gather_current_states(state: &AppState) -> (HashMap<String, DeviceState>, HashMap<String, Vec<Option<PlugState>>>) {
     let mut device_states = HashMap::new();
     for (id, device) in &state.devices {
-        let device_lock = device.lock().await;
-        device_states.insert(id.clone(), device_lock.device_state.clone());
+        device_states.insert(id.clone(), device.device_state());
     }
 
     let mut plug_states = HashMap::new();
@@ -369,13 +818,113 @@ fn build_device_submenu(
 fn format_device_state_text(state: &DeviceState) -> String {
     match state {
         DeviceState::Ok => "Status: OK".to_string(),
+        DeviceState::Offline => "Status: Offline".to_string(),
         DeviceState::Error(e) => format!("Status: Error ({})", e),
     }
 }
 
 fn format_plug_state_text(index: usize, state: &Option<PlugState>) -> String {
     match state {
-        Some(s) => format!("Plug {}: {}°C / {}%", index + 1, s.last_temp, s.plug_value),
+        Some(s) => match s.rpm {
+            Some(rpm) => format!("Plug {}: {}°C / {:.1}% / {} RPM", index + 1, s.last_temp, s.plug_value as f32 / 10.0, rpm),
+            None => format!("Plug {}: {}°C / {:.1}%", index + 1, s.last_temp, s.plug_value as f32 / 10.0),
+        },
         None => format!("Plug {}: Not configured", index + 1),
     }
-}
\ No newline at end of file
+}
+
+// A device that gave up and went offline stays that way, hotplug only reattaches offline devices
+async fn mark_device_error(device: &DeviceHandle, e: &DeviceError) -> Result<(), DeviceError> {
+    if let DeviceState::Offline = device.device_state() {
+        return Ok(());
+    }
+    device.set_device_state(DeviceState::Error(e.to_string())).await
+}
+
+// Heartbeats only go out when the value updates were quiet for a while
+async fn keep_alive(device: &DeviceHandle, last_contact: &mut Instant) {
+    let heartbeat_timeout = device.device_config().heartbeat_timeout;
+    if heartbeat_timeout == 0 || last_contact.elapsed() < Duration::from_millis(heartbeat_timeout) / HEARTBEATS_PER_TIMEOUT {
+        return;
+    }
+    if let DeviceState::Offline = device.device_state() {
+        return;
+    }
+
+    match device.heartbeat().await {
+        Ok(()) => *last_contact = Instant::now(),
+        Err(DeviceError::UnsupportedCommand(_)) => {}
+        Err(e) => {
+            eprintln!("Heartbeat failed: {}", e);
+            let _ = mark_device_error(device, &e).await;
+        }
+    }
+}
+
+// None when the device has nothing to report, so boards without tach inputs cost no extra traffic
+async fn read_rpm(device: &DeviceHandle) -> Option<Vec<Option<u32>>> {
+    if !device.device_config().has_tach_inputs() {
+        return None;
+    }
+    if let DeviceState::Offline = device.device_state() {
+        return None;
+    }
+    match device.get_rpm().await {
+        Ok(rpm) => Some(rpm),
+        Err(DeviceError::UnsupportedCommand(_)) => None,
+        Err(e) => {
+            eprintln!("Failed reading rpm: {}", e);
+            None
+        }
+    }
+}
+
+async fn read_temperatures(device: &DeviceHandle) {
+    if device.create_summary().temperature_inputs.is_empty() {
+        return;
+    }
+    if let DeviceState::Offline = device.device_state() {
+        return;
+    }
+    match device.get_temperatures().await {
+        Ok(_) | Err(DeviceError::UnsupportedCommand(_)) => {}
+        Err(e) => eprintln!("Failed reading temperatures: {}", e),
+    }
+}
+
+// Sends what the device doesn't hold yet in one round-trip. There is no ping beforehand, a failed
+// write is what marks the link as broken, and the next successful one clears that again
+// Returns whether anything was sent
+async fn sync_plug_values(
+    device: &DeviceHandle,
+    value_sync: &Arc<Mutex<ValueSync>>,
+    values: &[(u8, PortValue)],
+) -> Result<bool, AppError> {
+    let mut value_sync = value_sync.lock().await;
+    let pending = value_sync.pending(values);
+    if pending.is_empty() {
+        return Ok(false);
+    }
+
+    if let DeviceState::Offline = device.device_state() {
+        value_sync.invalidate();
+        return Ok(false);
+    }
+    match device.set_plug_values(pending.clone()).await {
+        Ok(results) => {
+            device.set_device_state(DeviceState::Ok).await?;
+            for (&(plug_index, value), result) in pending.iter().zip(results) {
+                if let Err(e) = &result {
+                    eprintln!("Failed setting plug {}: {}", plug_index, e);
+                }
+                value_sync.record(plug_index, value, result.is_ok());
+            }
+            Ok(true)
+        }
+        Err(e) => {
+            value_sync.invalidate();
+            mark_device_error(device, &e).await?;
+            Err(e.into())
+        }
+    }
+}
@@ -1,88 +1,173 @@
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use njord_backend::controller::PlugConfig;
+use njord_backend::device::{PortValue, percent_to_value};
 use njord_backend::device::SerialInfo;
+use njord_backend::reconnect::ReconnectPolicy;
 use njord_backend::sensors::SensorId;
-use crate::state::{AppState, CoreMessage, CoreMessageKind};
+use crate::error::AppError;
+use crate::state::{AppState, CoreMessage, CoreMessageKind, DEFAULT_REFRESH_INTERVAL};
 
-#[derive(Serialize, Deserialize)]
+// 0: devices keyed by com port, 1: devices keyed by their stable id, 2: plug values in permille
+const STORAGE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PlugHandlerStore {
     plug_index: u8,
     sensor_id: SensorId,
     plug_config: PlugConfig
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct DeviceStore {
-    device_id: String,
-    serial_info: SerialInfo,
-    plug_handlers: Vec<PlugHandlerStore>
+    pub device_id: String,
+    pub serial_info: SerialInfo,
+    #[serde(default)]
+    pub usb_serial_number: Option<String>,
+    #[serde(default)]
+    pub device_uid: Option<String>,
+    pub plug_handlers: Vec<PlugHandlerStore>
 }
 
 #[derive(Serialize, Deserialize)]
 
 pub struct Storage {
+    #[serde(default)]
+    version: u32,
+    #[serde(default = "default_refresh_interval")]
+    refresh_interval: u64, // ms
+    #[serde(default)]
+    reconnect_policy: ReconnectPolicy,
     devices: Vec<DeviceStore>
 }
 
+// Curves and dead areas were saved in whole percents before version 2
+fn percent_to_permille(plug_config: &mut PlugConfig) {
+    let scale = |value: PortValue| percent_to_value(value.min(100) as u8);
+    for point in &mut plug_config.curve {
+        point.value = scale(point.value);
+    }
+    for dead_area in &mut plug_config.dead_areas {
+        dead_area.min_value = scale(dead_area.min_value);
+        dead_area.max_value = scale(dead_area.max_value);
+    }
+}
+
+fn default_refresh_interval() -> u64 {
+    DEFAULT_REFRESH_INTERVAL.as_millis() as u64
+}
+
 impl Storage {
-    pub async fn load_data(location: &str, state: &mut AppState) -> Result<(), String> {
+    fn migrate_plug_values(&mut self) {
+        if self.version >= 2 {
+            return;
+        }
+        for device_store in &mut self.devices {
+            for plug_handler in &mut device_store.plug_handlers {
+                percent_to_permille(&mut plug_handler.plug_config);
+            }
+        }
+    }
+
+    pub async fn load_data(location: &str, state: &mut AppState) -> Result<(), AppError> {
         let content = fs::read_to_string(location).map_err(|_| "Failed to read storage".to_string())?;
-        let self_data: Storage = serde_json::from_str(&content).map_err(|_| "Failed to parse storage".to_string())?;
+        let mut self_data: Storage = serde_json::from_str(&content).map_err(|_| "Failed to parse storage".to_string())?;
 
-        for device_store in self_data.devices {
-            let serial_info = device_store.serial_info;
-            let plug_handlers = device_store.plug_handlers;
+        self_data.migrate_plug_values();
 
-            match state.add_device(serial_info.clone(), None).await{
-                Err(e) => state.core_messages.push(CoreMessage {
+        state.refresh_interval = Duration::from_millis(self_data.refresh_interval);
+        state.reconnect_policy = self_data.reconnect_policy;
+        for device_store in self_data.devices {
+            if let Err(e) = Self::restore_device(state, device_store.clone()).await {
+                state.core_messages.push(CoreMessage {
                     kind: CoreMessageKind::Error,
-                    message: format!("Failed loading device {} ({})", serial_info.com_port, e)
-                }),
-                _ => {}
-            };
+                    message: format!("Failed loading device {} ({})", device_store.serial_info.com_port, e)
+                });
+                // keeping it offline, the hotplug watcher attaches it once the port shows up
+                state.add_offline_device(device_store);
+            }
+        }
+
+        if self_data.version < STORAGE_VERSION {
+            // attached devices are re-keyed by now, writing them back finishes the migration
+            Self::dump_data(location, state).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn restore_device(state: &mut AppState, device_store: DeviceStore) -> Result<(), AppError> {
+        let device_id = state.add_device(device_store.serial_info.clone(), None).await?;
 
-            for plug_handler in plug_handlers {
-                state.set_plug_handler(device_store.device_id.clone(), plug_handler.plug_index, plug_handler.sensor_id, plug_handler.plug_config).await?;
+        let device_uid = {
+            let device = state.devices.get(&device_id).ok_or("Device not found")?;
+            device.create_summary().device_info.device_uid
+        };
+        if device_store.device_uid.is_some() && device_uid.is_some() && device_store.device_uid != device_uid {
+            state.remove_device(device_id).await?;
+            return Err(format!("Another device is connected on {}", device_store.serial_info.com_port).into());
+        }
+
+        for plug_handler in device_store.plug_handlers {
+            let plug_index = plug_handler.plug_index;
+            if let Err(e) = state.set_plug_handler(device_id.clone(), plug_index, plug_handler.sensor_id, plug_handler.plug_config).await {
+                state.core_messages.push(CoreMessage {
+                    kind: CoreMessageKind::Error,
+                    message: format!("Failed loading plug {} of device {} ({})", plug_index, device_id, e)
+                });
             }
         }
 
         Ok(())
     }
 
-    pub async fn dump_data(location: &str, state: &AppState) -> Result<(), String> {
-        let mut self_data = Self {
-            devices: Vec::new()
+    pub async fn snapshot_device(state: &AppState, device_id: &String) -> Result<DeviceStore, AppError> {
+        if let Some(device_store) = state.offline_devices.get(device_id) {
+            return Ok(device_store.clone());
+        }
+
+        let summary = {
+            let device = state.devices.get(device_id).ok_or("Device not found")?;
+            device.create_summary()
         };
 
-        for (device_id, device_arc) in state.devices.clone(){
-            let summary = {
-                let device_lock = device_arc.lock().await;
-                device_lock.create_summary()
-            };
-
-            let mut plug_handlers = Vec::new();
-
-            {
-                let plug_handlers_arc = state.plug_handlers.get(&device_id).ok_or("Plug handler not found")?;
-                let plug_handlers_lock = plug_handlers_arc.lock().await;
-                for plug_handler_option in plug_handlers_lock.iter() {
-                    if let Some(plug_handler) = plug_handler_option {
-                        plug_handlers.push(PlugHandlerStore {
-                            plug_index: plug_handler.plug_externals.plug_index,
-                            plug_config: plug_handler.plug_config.clone(),
-                            sensor_id: plug_handler.plug_externals.sensor.get_sensor_id()
-                        })
-                    }
+        let mut plug_handlers = Vec::new();
+
+        {
+            let plug_handlers_arc = state.plug_handlers.get(device_id).ok_or("Plug handler not found")?;
+            let plug_handlers_lock = plug_handlers_arc.lock().await;
+            for plug_handler_option in plug_handlers_lock.iter() {
+                if let Some(plug_handler) = plug_handler_option {
+                    plug_handlers.push(PlugHandlerStore {
+                        plug_index: plug_handler.plug_externals.plug_index,
+                        plug_config: plug_handler.plug_config.clone(),
+                        sensor_id: plug_handler.plug_externals.sensor.get_sensor_id()
+                    })
                 }
             }
+        }
+
+        Ok(DeviceStore {
+            device_id: device_id.clone(),
+            serial_info: summary.serial_info,
+            usb_serial_number: state.device_ports.get(device_id).and_then(|port| port.usb_serial_number.clone()),
+            device_uid: summary.device_info.device_uid,
+            plug_handlers
+        })
+    }
 
-            self_data.devices.push(DeviceStore {
-                device_id,
-                serial_info: summary.serial_info,
-                plug_handlers
-            })
+    pub async fn dump_data(location: &str, state: &AppState) -> Result<(), AppError> {
+        let mut self_data = Self {
+            version: STORAGE_VERSION,
+            refresh_interval: state.refresh_interval.as_millis() as u64,
+            reconnect_policy: state.reconnect_policy.clone(),
+            devices: Vec::new()
+        };
+
+        for device_id in state.devices.keys() {
+            self_data.devices.push(Self::snapshot_device(state, device_id).await?);
         }
 
         let json = serde_json::to_string_pretty(&self_data).map_err(|_| "Failed to dump storage".to_string())?; // prettified JSON
@@ -92,4 +177,66 @@ impl Storage {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn storage(version: u32) -> Storage {
+        serde_json::from_value(json!({
+            "version": version,
+            "devices": [{
+                "device_id": "E000000000000001",
+                "serial_info": {"com_port": "/dev/ttyACM0", "baud_rate": 115200},
+                "plug_handlers": [{
+                    "plug_index": 0,
+                    "sensor_id": {"sensor_type": "SysInfoSensor", "identifier": "cpu"},
+                    "plug_config": {
+                        "curve": [{"temp": 30.0, "value": 25}, {"temp": 70.0, "value": 100}],
+                        "dead_areas": [{"min_value": 0, "max_value": 40, "variant": "Min"}],
+                        "cool_holder": null
+                    }
+                }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn plug_config(storage: &Storage) -> &PlugConfig {
+        &storage.devices[0].plug_handlers[0].plug_config
+    }
+
+    #[test]
+    fn percent_values_scale_to_permille() {
+        let mut plug_config = plug_config(&storage(1)).clone();
+        plug_config.curve[1].value = 150; // hand edited files may go past 100%
+
+        percent_to_permille(&mut plug_config);
+
+        let values: Vec<PortValue> = plug_config.curve.iter().map(|point| point.value).collect();
+        assert_eq!(values, vec![250, 1000]);
+        assert_eq!(plug_config.dead_areas[0].min_value, 0);
+        assert_eq!(plug_config.dead_areas[0].max_value, 400);
+    }
+
+    #[test]
+    fn storage_before_version_2_is_migrated() {
+        let mut storage = storage(1);
+
+        storage.migrate_plug_values();
+
+        assert_eq!(plug_config(&storage).curve[0].value, 250);
+        assert_eq!(plug_config(&storage).dead_areas[0].max_value, 400);
+    }
+
+    #[test]
+    fn storage_from_version_2_is_kept() {
+        let mut storage = storage(STORAGE_VERSION);
+
+        storage.migrate_plug_values();
+
+        assert_eq!(plug_config(&storage).curve[0].value, 25);
+        assert_eq!(plug_config(&storage).dead_areas[0].max_value, 40);
+    }
+}
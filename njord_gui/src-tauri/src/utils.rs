@@ -1,18 +1,19 @@
-use std::time::Duration;
-use njord_backend::device::Device;
+use njord_backend::actor::DeviceHandle;
+use crate::error::AppError;
 
-pub async fn ping_and_reconnect(device: &mut Device) -> Result<(), String>{
+pub async fn ping_and_reconnect(device: &DeviceHandle) -> Result<(), AppError>{
+    let policy = device.snapshot().reconnect_policy;
     let ping = device
-        .test_connection(Duration::from_millis(800), Duration::from_millis(150))
+        .test_connection(policy.ping_timeout, policy.ping_interval)
         .await;
     if !ping {
-        device.open_connection()?;
+        device.open_connection().await?;
         if !device
-            .test_connection(Duration::from_millis(800), Duration::from_millis(150))
+            .test_connection(policy.ping_timeout, policy.ping_interval)
             .await
         {
-            return Err("Failed connecting device after setting config".to_string());
+            return Err("Failed connecting device after setting config".into());
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,9 +1,10 @@
-import { DeviceConfig, DeviceInfo, PortInfo, SerialInfo } from "@/types/api";
+import { BoardProfile, ConfigReport, ControlStatus, DeviceConfig, DeviceInfo, DiscoveredDevice, PortInfo, SerialInfo, StandaloneCurve, TemperatureInput, UpdateStats } from "@/types/api";
 import { errorWrapper } from "@/utils/errorWrapper";
 import { invoke } from "@tauri-apps/api/core";
 import {
   LOAD_DEVICE_INFO,
   GET_DEVICE_LIST,
+  DISCOVER_DEVICES,
   LOAD_DEVICE_CONFIG,
   LOAD_DEVICE_DEFAULT_CONFIG,
   ADD_DEVICE,
@@ -12,6 +13,20 @@ import {
   LOAD_CONNECTED_DEVICE_CONFIG,
   LOAD_CONNECTED_DEVICE_DEFAULT_CONFIG,
   GET_DEVICE_STATUS,
+  GET_UPDATE_STATS,
+  UPDATE_FIRMWARE,
+  SET_DEVICE_TRACE,
+  VALIDATE_DEVICE_CONFIG,
+  GET_BOARD_PROFILES,
+  GET_BOARD_PROFILE,
+  REBOOT_DEVICE,
+  FACTORY_RESET_DEVICE,
+  IDENTIFY_DEVICE,
+  SET_TEMPERATURE_INPUTS,
+  SET_STANDALONE_CURVES,
+  GET_STANDALONE_CURVES,
+  VERIFY_STANDALONE_CURVES,
+  GET_CONTROL_STATUS,
 } from "./paths";
 import { WrappedError } from "@/types/utils";
 
@@ -19,6 +34,10 @@ export async function getDevicesListApi(): Promise<WrappedError<PortInfo[]>> {
   return errorWrapper<PortInfo[]>(() => invoke(GET_DEVICE_LIST));
 }
 
+export async function discoverDevicesApi(): Promise<WrappedError<DiscoveredDevice[]>> {
+  return errorWrapper<DiscoveredDevice[]>(() => invoke(DISCOVER_DEVICES));
+}
+
 export async function loadDeviceInfoApi(
   serialInfo: SerialInfo
 ): Promise<WrappedError<DeviceInfo>> {
@@ -71,6 +90,69 @@ export async function removeDevice(id: string): Promise<WrappedError<unknown>> {
   return errorWrapper<unknown>(() => invoke(REMOVE_DEVICE, { id }));
 }
 
+// Resolves once the board is back and answering
+export async function rebootDevice(id: string): Promise<WrappedError<unknown>> {
+  return errorWrapper<unknown>(() => invoke(REBOOT_DEVICE, { id }));
+}
+
+// Wipes the stored config, plug handlers of the device are dropped too
+export async function factoryResetDevice(id: string): Promise<WrappedError<unknown>> {
+  return errorWrapper<unknown>(() => invoke(FACTORY_RESET_DEVICE, { id }));
+}
+
+export async function identifyDevice(
+  id: string,
+  seconds: number
+): Promise<WrappedError<unknown>> {
+  return errorWrapper<unknown>(() => invoke(IDENTIFY_DEVICE, { id, seconds }));
+}
+
+// Every input shows up as a sensor of the device afterwards
+export async function setTemperatureInputs(
+  id: string,
+  temperatureInputs: TemperatureInput[]
+): Promise<WrappedError<unknown>> {
+  return errorWrapper<unknown>(() =>
+    invoke(SET_TEMPERATURE_INPUTS, { id, temperatureInputs })
+  );
+}
+
+// One entry per port, null leaves the port on its default value. Fails if the board stored something else
+export async function setStandaloneCurves(
+  id: string,
+  standaloneCurves: (StandaloneCurve | null)[]
+): Promise<WrappedError<unknown>> {
+  return errorWrapper<unknown>(() =>
+    invoke(SET_STANDALONE_CURVES, { id, standaloneCurves })
+  );
+}
+
+export async function getStandaloneCurves(
+  id: string
+): Promise<WrappedError<(StandaloneCurve | null)[]>> {
+  return errorWrapper<(StandaloneCurve | null)[]>(() =>
+    invoke(GET_STANDALONE_CURVES, { id })
+  );
+}
+
+export async function verifyStandaloneCurves(
+  id: string,
+  standaloneCurves: (StandaloneCurve | null)[]
+): Promise<WrappedError<boolean>> {
+  return errorWrapper<boolean>(() =>
+    invoke(VERIFY_STANDALONE_CURVES, { id, standaloneCurves })
+  );
+}
+
+// Reading it doesn't take a standalone board over
+export async function getControlStatus(
+  deviceId: string
+): Promise<WrappedError<ControlStatus>> {
+  return errorWrapper<ControlStatus>(() =>
+    invoke<ControlStatus>(GET_CONTROL_STATUS, { deviceId })
+  );
+}
+
 export async function updateDeviceConfig(
   id: string,
   deviceConfig: DeviceConfig
@@ -80,7 +162,7 @@ export async function updateDeviceConfig(
   );
 }
 
-export type DeviceState = "Ok" | { Error: string };
+export type DeviceState = "Ok" | "Offline" | { Error: string };
 
 export async function getDeviceState(
   deviceId: string
@@ -89,3 +171,54 @@ export async function getDeviceState(
     invoke<DeviceState>(GET_DEVICE_STATUS, { deviceId })
   );
 }
+
+export async function getUpdateStats(
+  deviceId: string
+): Promise<WrappedError<UpdateStats>> {
+  return errorWrapper<UpdateStats>(() =>
+    invoke<UpdateStats>(GET_UPDATE_STATS, { deviceId })
+  );
+}
+
+export async function updateFirmware(
+  id: string,
+  imagePath: string,
+  expectedVersion?: string
+): Promise<WrappedError<unknown>> {
+  return errorWrapper<unknown>(() =>
+    invoke(UPDATE_FIRMWARE, { id, imagePath, expectedVersion })
+  );
+}
+
+// Pass no path to stop recording
+export async function setDeviceTrace(
+  deviceId: string,
+  tracePath?: string
+): Promise<WrappedError<unknown>> {
+  return errorWrapper<unknown>(() =>
+    invoke(SET_DEVICE_TRACE, { deviceId, tracePath })
+  );
+}
+
+export async function validateDeviceConfig(
+  deviceInfo: DeviceInfo,
+  deviceConfig: DeviceConfig,
+  deviceId?: string
+): Promise<WrappedError<ConfigReport>> {
+  return errorWrapper<ConfigReport>(() =>
+    invoke(VALIDATE_DEVICE_CONFIG, { deviceId, deviceInfo, deviceConfig })
+  );
+}
+
+export async function getBoardProfiles(): Promise<WrappedError<BoardProfile[]>> {
+  return errorWrapper<BoardProfile[]>(() => invoke(GET_BOARD_PROFILES));
+}
+
+// Falls back to the generic profile for boards nobody described
+export async function getBoardProfile(
+  deviceInfo: DeviceInfo
+): Promise<WrappedError<BoardProfile>> {
+  return errorWrapper<BoardProfile>(() =>
+    invoke(GET_BOARD_PROFILE, { deviceInfo })
+  );
+}
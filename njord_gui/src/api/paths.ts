@@ -1,4 +1,5 @@
 export const GET_DEVICE_LIST = "get_device_list";
+export const DISCOVER_DEVICES = "discover_devices";
 export const LOAD_DEVICE_INFO = "load_device_info";
 export const LOAD_DEVICE_CONFIG = "load_device_config";
 export const LOAD_CONNECTED_DEVICE_CONFIG = "load_connected_device_config";
@@ -9,6 +10,20 @@ export const GET_DEVICES = "get_devices";
 export const REMOVE_DEVICE = "remove_device";
 export const UPDATE_DEVICE_CONFIG = "update_device_config";
 export const GET_DEVICE_STATUS = "get_device_status";
+export const GET_UPDATE_STATS = "get_update_stats";
+export const UPDATE_FIRMWARE = "update_firmware";
+export const SET_DEVICE_TRACE = "set_device_trace";
+export const VALIDATE_DEVICE_CONFIG = "validate_device_config";
+export const GET_BOARD_PROFILES = "get_board_profiles";
+export const GET_BOARD_PROFILE = "get_board_profile";
+export const REBOOT_DEVICE = "reboot_device";
+export const FACTORY_RESET_DEVICE = "factory_reset_device";
+export const IDENTIFY_DEVICE = "identify_device";
+export const SET_TEMPERATURE_INPUTS = "set_temperature_inputs";
+export const SET_STANDALONE_CURVES = "set_standalone_curves";
+export const GET_STANDALONE_CURVES = "get_standalone_curves";
+export const VERIFY_STANDALONE_CURVES = "verify_standalone_curves";
+export const GET_CONTROL_STATUS = "get_control_status";
 
 export const GET_CORE_MESSAGES = "get_core_messages";
 
@@ -8,6 +8,7 @@ import {
   SET_PLUG_HANDLER_CONFIG,
 } from "./paths";
 import { WrappedError } from "@/types/utils";
+import { percentToValue, valueToPercent } from "@/utils/api";
 import {
   CoolHolderData,
   CurvePoint,
@@ -30,8 +31,15 @@ export async function setPlugHandlerConfig(
       deviceId,
       plugIndex,
       plugConfig: {
-        curve: data.curve,
-        dead_areas: data.dead_areas,
+        curve: data.curve.map((point) => ({
+          ...point,
+          value: percentToValue(point.value),
+        })),
+        dead_areas: data.dead_areas.map((deadArea) => ({
+          ...deadArea,
+          min_value: percentToValue(deadArea.min_value),
+          max_value: percentToValue(deadArea.max_value),
+        })),
         cool_holder: data.cool_holder,
       },
       sensorId: data.sensor,
@@ -52,23 +60,43 @@ export async function getPlugHandlerConfig(
   deviceId: string,
   plugIndex: number
 ): Promise<WrappedError<PlugHandlerData>> {
-  return errorWrapper<PlugHandlerData>(() =>
+  const response = await errorWrapper<PlugHandlerData>(() =>
     invoke(GET_PLUG_HANDLER_CONFIG, {
       deviceId,
       plugIndex,
     })
   );
+  const plugConfig = response.data?.plug_config;
+  if (plugConfig) {
+    plugConfig.curve = plugConfig.curve.map((point) => ({
+      ...point,
+      value: valueToPercent(point.value),
+    }));
+    plugConfig.dead_areas = plugConfig.dead_areas.map((deadArea) => ({
+      ...deadArea,
+      min_value: valueToPercent(deadArea.min_value),
+      max_value: valueToPercent(deadArea.max_value),
+    }));
+  }
+  return response;
 }
 
 export interface PlugState {
   last_temp: number;
   plug_value: number;
+  rpm: number | null;
 }
 
 export async function getPlugsStates(
   deviceId: string
 ): Promise<WrappedError<(PlugState | undefined)[]>> {
-  return errorWrapper<(PlugState | undefined)[]>(() =>
+  const response = await errorWrapper<(PlugState | undefined)[]>(() =>
     invoke(GET_PLUG_STATES, { deviceId })
   );
+  if (response.data) {
+    response.data = response.data.map(
+      (state) => state && { ...state, plug_value: valueToPercent(state.plug_value) }
+    );
+  }
+  return response;
 }
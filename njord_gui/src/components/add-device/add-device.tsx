@@ -15,9 +15,14 @@ import {
   SelectValue,
 } from "../ui/select";
 import { useEffect, useState } from "react";
-import { PortInfo } from "@/types/api";
+import { BoardProfile, PortInfo } from "@/types/api";
 import { BAUD_RATES } from "@/const";
-import { loadDeviceInfoApi, getDevicesListApi, addDevice } from "@/api/device";
+import {
+  loadDeviceInfoApi,
+  getDevicesListApi,
+  addDevice,
+  getBoardProfiles,
+} from "@/api/device";
 import DeviceSetup from "../device-setup/device-setup";
 import { useDeviceContext } from "@/context/device";
 
@@ -27,6 +32,7 @@ export default function AddDevice() {
   const { setDeviceInfo, setSerialInfo, clear } = deviceContext.updaters;
 
   const [devices, setDevices] = useState<PortInfo[]>([]);
+  const [boardProfiles, setBoardProfiles] = useState<BoardProfile[]>([]);
   const [isConnecting, setIsConnecting] = useState<boolean>(false);
 
   const [isSheetOpen, setIsSheetOpen] = useState(false);
@@ -41,6 +47,12 @@ export default function AddDevice() {
     setSerialInfo({ com_port: value, baud_rate: serialInfo.baud_rate });
   }
 
+  // the board can't tell its profile before we talk to it, picking one only presets the baud rate
+  function changeBoardProfile(name: string) {
+    const profile = boardProfiles.find((profile) => profile.name === name);
+    if (profile) changeBaudPort(profile.default_baud_rate.toString());
+  }
+
   function changeBaudPort(value: string) {
     setDeviceInfo({ board_name: "", max_ports: -1 });
     setSerialInfo({
@@ -67,6 +79,9 @@ export default function AddDevice() {
 
   useEffect(() => {
     getDevicesList();
+    getBoardProfiles().then((response) =>
+      response.data ? setBoardProfiles(response.data) : null
+    );
   }, []);
 
   return (
@@ -83,6 +98,18 @@ export default function AddDevice() {
         <div className="w-full h-full pb-5 flex flex-col justify-between overflow-auto">
           <div className="w-full space-y-5 mb-5">
             <div className="w-full space-y-1">
+              <Select onValueChange={changeBoardProfile}>
+                <SelectTrigger>
+                  <SelectValue placeholder="Board" />
+                </SelectTrigger>
+                <SelectContent>
+                  {boardProfiles.map((profile) => (
+                    <SelectItem key={profile.name} value={profile.name}>
+                      {profile.name}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
               <div className="w-full flex space-x-2">
                 <Select
                   onValueChange={changeComPort}
@@ -15,6 +15,7 @@ export default function DeviceSetup({deviceId}: {deviceId?: string}) {
   const isDisabled = deviceInfo.max_ports <= 0;
 
   const updateTimeId = useId();
+  const heartbeatTimeoutId = useId();
 
   const loadCurrentConfig = async () => {
     let response;
@@ -65,8 +66,7 @@ export default function DeviceSetup({deviceId}: {deviceId?: string}) {
           value={deviceConfig.update_time}
           onChange={(event) => {
             setDeviceConfig({
-              ports: deviceConfig.ports,
-              default_values: deviceConfig.default_values,
+              ...deviceConfig,
               update_time: parseInt(event.target.value),
             });
           }}
@@ -74,7 +74,23 @@ export default function DeviceSetup({deviceId}: {deviceId?: string}) {
           type="number"
         ></Input>
       </div>
-      <SetupPlugs></SetupPlugs>
+      <div>
+        <Label htmlFor={heartbeatTimeoutId}>Failsafe timeout (ms, 0 disables it)</Label>
+        <Input
+          id={heartbeatTimeoutId}
+          value={deviceConfig.heartbeat_timeout ?? 0}
+          onChange={(event) => {
+            setDeviceConfig({
+              ...deviceConfig,
+              heartbeat_timeout: parseInt(event.target.value),
+            });
+          }}
+          disabled={isDisabled}
+          min={0}
+          type="number"
+        ></Input>
+      </div>
+      <SetupPlugs deviceId={deviceId}></SetupPlugs>
     </div>
   );
 }
@@ -10,27 +10,80 @@ import {
 import { Input } from "../ui/input";
 import { Trash } from "lucide-react";
 import { PlugSetting } from "@/types/device";
-import { DEFAULT_PLUG_VALUE } from "@/const";
+import { Switch } from "../ui/switch";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "../ui/select";
+import { BoardProfile, ConfigProblem, PortSettings } from "@/types/api";
+import { getBoardProfile, validateDeviceConfig } from "@/api/device";
+import {
+  DEFAULT_PLUG_VALUE,
+  DEFAULT_PORT_SETTINGS,
+  PORT_SETTINGS_CAPABILITY,
+} from "@/const";
 import { useDeviceContext } from "@/context/device";
 import {
   deviceConfigToPlugSetting,
   plugSettingToDeviceConfig,
 } from "@/utils/api";
 
-export default function SetupPlugs() {
+const NO_TACH = "none"; // select items can't carry an empty value
+
+export default function SetupPlugs({ deviceId }: { deviceId?: string }) {
   const addDeviceContext = useDeviceContext();
   const { deviceInfo, deviceConfig } = addDeviceContext.data;
   const { setDeviceConfig } = addDeviceContext.updaters;
   const [plugs, setPlugs] = useState<PlugSetting[]>([]);
+  const [problems, setProblems] = useState<ConfigProblem[]>([]);
+  const [boardProfile, setBoardProfile] = useState<BoardProfile | null>(null);
+  // older firmware refuses anything but the default PWM output
+  const hasPortSettings = deviceInfo.capabilities?.includes(PORT_SETTINGS_CAPABILITY) ?? false;
 
   useEffect(() => {
     setPlugs(deviceConfigToPlugSetting(deviceConfig));
   }, [deviceConfig]);
 
+  useEffect(() => {
+    if (deviceInfo.max_ports === -1) {
+      setBoardProfile(null);
+      return;
+    }
+    getBoardProfile(deviceInfo).then((response) =>
+      setBoardProfile(response.data ?? null)
+    );
+  }, [deviceInfo]);
+
+  // a profile without pins accepts any GPIO, free typing is kept for those
+  const gpios = boardProfile?.gpios ?? [];
+
+  function gpioLabel(gpio: number) {
+    return gpio === boardProfile?.led_gpio ? `${gpio} (LED)` : gpio.toString();
+  }
+
+  // the same check the backend runs before writing, shown while the user edits
+  useEffect(() => {
+    if (deviceInfo.max_ports === -1) {
+      return; // no board loaded yet
+    }
+    validateDeviceConfig(deviceInfo, deviceConfig, deviceId).then((response) =>
+      setProblems(response.data?.problems ?? [])
+    );
+  }, [deviceId, deviceInfo, deviceConfig]);
+
   function addPlug() {
     if (plugs.length < deviceInfo.max_ports) {
       let newPlugs = [...plugs];
-      newPlugs.push({ port: 0, default_value: DEFAULT_PLUG_VALUE });
+      const usedGpios = plugs.flatMap((plug) => [plug.port, plug.tach_input]);
+      newPlugs.push({
+        port: gpios.find((gpio) => !usedGpios.includes(gpio)) ?? 0,
+        default_value: DEFAULT_PLUG_VALUE,
+        tach_input: null,
+        settings: { ...DEFAULT_PORT_SETTINGS },
+      });
       setDeviceConfig(plugSettingToDeviceConfig(deviceConfig, newPlugs));
     }
   }
@@ -46,6 +99,18 @@ export default function SetupPlugs() {
     setDeviceConfig(plugSettingToDeviceConfig(deviceConfig, newPlugs));
   }
 
+  function handleChangeTach(index: number, value: string) {
+    let newPlugs = [...plugs];
+    newPlugs[index].tach_input = value === "" ? null : Number(value);
+    setDeviceConfig(plugSettingToDeviceConfig(deviceConfig, newPlugs));
+  }
+
+  function handleChangeSettings(index: number, settings: Partial<PortSettings>) {
+    let newPlugs = [...plugs];
+    newPlugs[index].settings = { ...newPlugs[index].settings, ...settings };
+    setDeviceConfig(plugSettingToDeviceConfig(deviceConfig, newPlugs));
+  }
+
   function handlePlugDelete(elIndex: number) {
     let newPlugs = plugs.filter((_el, index) => index !== elIndex);
     setDeviceConfig(plugSettingToDeviceConfig(deviceConfig, newPlugs));
@@ -59,6 +124,14 @@ export default function SetupPlugs() {
               <TableCell>Plug Id</TableCell>
               <TableCell>Plug GPIO</TableCell>
               <TableCell>Default Value %</TableCell>
+              <TableCell>Tach GPIO</TableCell>
+              {hasPortSettings && (
+                <>
+                  <TableCell>Output</TableCell>
+                  <TableCell>PWM Hz</TableCell>
+                  <TableCell>Inverted</TableCell>
+                </>
+              )}
             </TableRow>
           </TableHeader>
           <TableBody>
@@ -66,14 +139,34 @@ export default function SetupPlugs() {
               <TableRow key={index}>
                 <TableCell>{index}</TableCell>
                 <TableCell>
-                  <Input
-                    min={0}
-                    type="number"
-                    value={plugs[index].port}
-                    onChange={(e) =>
-                      handleChangePlug(index, Number(e.target.value))
-                    }
-                  ></Input>
+                  {gpios.length > 0 ? (
+                    <Select
+                      value={plugs[index].port.toString()}
+                      onValueChange={(value) =>
+                        handleChangePlug(index, Number(value))
+                      }
+                    >
+                      <SelectTrigger>
+                        <SelectValue />
+                      </SelectTrigger>
+                      <SelectContent>
+                        {gpios.map((gpio) => (
+                          <SelectItem key={gpio} value={gpio.toString()}>
+                            {gpioLabel(gpio)}
+                          </SelectItem>
+                        ))}
+                      </SelectContent>
+                    </Select>
+                  ) : (
+                    <Input
+                      min={0}
+                      type="number"
+                      value={plugs[index].port}
+                      onChange={(e) =>
+                        handleChangePlug(index, Number(e.target.value))
+                      }
+                    ></Input>
+                  )}
                 </TableCell>
                 <TableCell>
                   <Input
@@ -86,6 +179,80 @@ export default function SetupPlugs() {
                     }
                   ></Input>
                 </TableCell>
+                <TableCell>
+                  {gpios.length > 0 ? (
+                    <Select
+                      value={plugs[index].tach_input?.toString() ?? NO_TACH}
+                      onValueChange={(value) =>
+                        handleChangeTach(index, value === NO_TACH ? "" : value)
+                      }
+                    >
+                      <SelectTrigger>
+                        <SelectValue />
+                      </SelectTrigger>
+                      <SelectContent>
+                        <SelectItem value={NO_TACH}>None</SelectItem>
+                        {gpios.map((gpio) => (
+                          <SelectItem key={gpio} value={gpio.toString()}>
+                            {gpioLabel(gpio)}
+                          </SelectItem>
+                        ))}
+                      </SelectContent>
+                    </Select>
+                  ) : (
+                    <Input
+                      min={0}
+                      type="number"
+                      placeholder="None"
+                      value={plugs[index].tach_input ?? ""}
+                      onChange={(e) => handleChangeTach(index, e.target.value)}
+                    ></Input>
+                  )}
+                </TableCell>
+                {hasPortSettings && (
+                  <>
+                    <TableCell>
+                      <Select
+                        value={plugs[index].settings.output_mode}
+                        onValueChange={(value) =>
+                          handleChangeSettings(index, {
+                            output_mode: value as PortSettings["output_mode"],
+                          })
+                        }
+                      >
+                        <SelectTrigger>
+                          <SelectValue />
+                        </SelectTrigger>
+                        <SelectContent>
+                          <SelectItem value="pwm">PWM</SelectItem>
+                          <SelectItem value="on_off">On/Off</SelectItem>
+                        </SelectContent>
+                      </Select>
+                    </TableCell>
+                    <TableCell>
+                      <Input
+                        min={10}
+                        max={boardProfile?.max_pwm_frequency ?? 100000}
+                        type="number"
+                        disabled={plugs[index].settings.output_mode !== "pwm"}
+                        value={plugs[index].settings.pwm_frequency}
+                        onChange={(e) =>
+                          handleChangeSettings(index, {
+                            pwm_frequency: Number(e.target.value),
+                          })
+                        }
+                      ></Input>
+                    </TableCell>
+                    <TableCell>
+                      <Switch
+                        checked={plugs[index].settings.inverted}
+                        onCheckedChange={(inverted) =>
+                          handleChangeSettings(index, { inverted })
+                        }
+                      />
+                    </TableCell>
+                  </>
+                )}
                 <TableCell>
                   <Button
                     variant="ghost"
@@ -99,6 +266,13 @@ export default function SetupPlugs() {
           </TableBody>
         </Table>
       </div>
+      {problems.length > 0 && (
+        <ul className="w-full text-sm text-destructive list-disc pl-6">
+          {problems.map((problem, index) => (
+            <li key={index}>{problem.message}</li>
+          ))}
+        </ul>
+      )}
       <div className="w-full">
         <Button disabled={deviceInfo.max_ports === -1} onClick={addPlug}>
           Add plug
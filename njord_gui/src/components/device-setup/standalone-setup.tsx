@@ -0,0 +1,196 @@
+import { useState } from "react";
+import { Button } from "../ui/button";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+import { Input } from "../ui/input";
+import { Label } from "../ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "../ui/select";
+import { StandaloneCurve } from "@/types/api";
+import { setStandaloneCurves, verifyStandaloneCurves } from "@/api/device";
+import { getPlugHandlerConfig } from "@/api/plugs";
+import { MAX_STANDALONE_CURVE_POINTS, STANDALONE_CURVES_CAPABILITY } from "@/const";
+import { useDeviceContext } from "@/context/device";
+import { useDeviceStore } from "@/store/device";
+import { percentToValue, valueToPercent } from "@/utils/api";
+
+const OFF = "off"; // input select value of ports left on their default value
+
+// Curves the board runs against its own temperature inputs while the host is away
+export default function SetupStandaloneCurves({ deviceId }: { deviceId: string }) {
+  const { deviceInfo } = useDeviceContext().data;
+  const { devices } = useDeviceStore();
+  const device = devices.find((device) => device.id === deviceId)?.device;
+  const ports = device?.device_config.ports.length ?? 0;
+  const inputs = device?.temperature_inputs ?? [];
+  const [curves, setCurves] = useState<(StandaloneCurve | null)[]>(
+    Array.from({ length: ports }, (_el, index) => device?.standalone_curves?.[index] ?? null)
+  );
+  const [isSaving, setIsSaving] = useState(false);
+  const [matchesBoard, setMatchesBoard] = useState<boolean | null>(null);
+  const hasStandaloneCurves =
+    deviceInfo.capabilities?.includes(STANDALONE_CURVES_CAPABILITY) ?? false;
+
+  if (!hasStandaloneCurves || inputs.length === 0) {
+    return null;
+  }
+
+  function updateCurve(index: number, curve: StandaloneCurve | null) {
+    setMatchesBoard(null);
+    setCurves(curves.map((el, elIndex) => (elIndex === index ? curve : el)));
+  }
+
+  function handleChangeInput(index: number, value: string) {
+    const curve = curves[index];
+    if (value === OFF) {
+      updateCurve(index, null);
+    } else if (curve) {
+      updateCurve(index, { ...curve, temperature_input: value });
+    } else {
+      updateCurve(index, {
+        temperature_input: value,
+        curve: [],
+        min_value: 0,
+        max_value: percentToValue(100),
+      });
+    }
+  }
+
+  // the board runs the curve alone, dead areas and cooling hold stay with the app
+  async function copyPlugCurve(index: number) {
+    const curve = curves[index];
+    const response = await getPlugHandlerConfig(deviceId, index);
+    if (!curve || !response.data) {
+      return;
+    }
+    updateCurve(index, {
+      ...curve,
+      curve: response.data.plug_config.curve
+        .slice(0, MAX_STANDALONE_CURVE_POINTS)
+        .map((point) => ({ ...point, value: percentToValue(point.value) })),
+    });
+  }
+
+  async function saveCurves() {
+    setIsSaving(true);
+    const response = await setStandaloneCurves(deviceId, curves);
+    setMatchesBoard(response.error ? null : true);
+    setIsSaving(false);
+  }
+
+  async function verifyCurves() {
+    const response = await verifyStandaloneCurves(deviceId, curves);
+    setMatchesBoard(response.data ?? null);
+  }
+
+  return (
+    <div className="space-y-2 m-4">
+      <Label className="font-semibold">Standalone curves</Label>
+      <div className="w-full">
+        <Table>
+          <TableHeader>
+            <TableRow>
+              <TableCell>Port</TableCell>
+              <TableCell>Temperature input</TableCell>
+              <TableCell>Min %</TableCell>
+              <TableCell>Max %</TableCell>
+              <TableCell>Curve</TableCell>
+            </TableRow>
+          </TableHeader>
+          <TableBody>
+            {curves.map((curve, index) => (
+              <TableRow key={index}>
+                <TableCell>{index}</TableCell>
+                <TableCell>
+                  <Select
+                    value={curve?.temperature_input ?? OFF}
+                    onValueChange={(value) => handleChangeInput(index, value)}
+                  >
+                    <SelectTrigger>
+                      <SelectValue />
+                    </SelectTrigger>
+                    <SelectContent>
+                      <SelectItem value={OFF}>Off</SelectItem>
+                      {inputs.map((input) => (
+                        <SelectItem key={input.name} value={input.name}>
+                          {input.name}
+                        </SelectItem>
+                      ))}
+                    </SelectContent>
+                  </Select>
+                </TableCell>
+                {curve ? (
+                  <>
+                    <TableCell>
+                      <Input
+                        min={0}
+                        max={100}
+                        type="number"
+                        value={valueToPercent(curve.min_value)}
+                        onChange={(e) =>
+                          updateCurve(index, {
+                            ...curve,
+                            min_value: percentToValue(Number(e.target.value)),
+                          })
+                        }
+                      ></Input>
+                    </TableCell>
+                    <TableCell>
+                      <Input
+                        min={0}
+                        max={100}
+                        type="number"
+                        value={valueToPercent(curve.max_value)}
+                        onChange={(e) =>
+                          updateCurve(index, {
+                            ...curve,
+                            max_value: percentToValue(Number(e.target.value)),
+                          })
+                        }
+                      ></Input>
+                    </TableCell>
+                    <TableCell className="flex items-center space-x-2">
+                      <p className="text-muted-foreground">
+                        {curve.curve
+                          .map((point) => `${point.temp}°C/${valueToPercent(point.value)}%`)
+                          .join(" ") || "-"}
+                      </p>
+                      <Button variant="outline" onClick={() => copyPlugCurve(index)}>
+                        Copy plug curve
+                      </Button>
+                    </TableCell>
+                  </>
+                ) : (
+                  <TableCell colSpan={3}></TableCell>
+                )}
+              </TableRow>
+            ))}
+          </TableBody>
+        </Table>
+      </div>
+      <div className="w-full flex items-center space-x-2">
+        <Button variant="outline" disabled={isSaving} onClick={saveCurves}>
+          Upload curves
+        </Button>
+        <Button variant="ghost" disabled={isSaving} onClick={verifyCurves}>
+          Verify
+        </Button>
+        {matchesBoard !== null && (
+          <p className="text-muted-foreground">
+            {matchesBoard ? "The board holds these curves" : "The board holds other curves"}
+          </p>
+        )}
+      </div>
+    </div>
+  );
+}
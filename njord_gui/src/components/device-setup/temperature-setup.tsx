@@ -0,0 +1,298 @@
+import { useEffect, useState } from "react";
+import { Button } from "../ui/button";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+import { Input } from "../ui/input";
+import { Label } from "../ui/label";
+import { Trash } from "lucide-react";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "../ui/select";
+import { BoardProfile, TemperatureInput, ThermistorModel } from "@/types/api";
+import { getBoardProfile, setTemperatureInputs } from "@/api/device";
+import {
+  DEFAULT_SERIES_RESISTANCE,
+  DEFAULT_THERMISTOR_MODEL,
+  MAX_TEMPERATURE_INPUTS,
+  TEMPERATURE_INPUTS_CAPABILITY,
+} from "@/const";
+import { useDeviceContext } from "@/context/device";
+import { useDeviceStore } from "@/store/device";
+
+const INTERNAL = "internal"; // source select value of the on-chip sensor
+
+const STEINHART_HART_DEFAULTS: ThermistorModel = {
+  kind: "steinhart_hart",
+  a: 0.001125308852122,
+  b: 0.000234711863267,
+  c: 0.000000085663516,
+};
+
+// The inputs live on the board next to its config but are saved on their own
+export default function SetupTemperatures({ deviceId }: { deviceId: string }) {
+  const { deviceInfo } = useDeviceContext().data;
+  const { devices } = useDeviceStore();
+  const storedInputs =
+    devices.find((device) => device.id === deviceId)?.device.temperature_inputs ?? [];
+  const [inputs, setInputs] = useState<TemperatureInput[]>(storedInputs);
+  const [boardProfile, setBoardProfile] = useState<BoardProfile | null>(null);
+  const [isSaving, setIsSaving] = useState(false);
+  const hasTemperatureInputs =
+    deviceInfo.capabilities?.includes(TEMPERATURE_INPUTS_CAPABILITY) ?? false;
+
+  useEffect(() => {
+    if (deviceInfo.max_ports === -1) {
+      setBoardProfile(null);
+      return;
+    }
+    getBoardProfile(deviceInfo).then((response) =>
+      setBoardProfile(response.data ?? null)
+    );
+  }, [deviceInfo]);
+
+  if (!hasTemperatureInputs) {
+    return null;
+  }
+
+  // a profile without ADC pins accepts any GPIO, free typing is kept for those
+  const adcGpios = boardProfile?.adc_gpios ?? [];
+  const hasInternal = boardProfile?.internal_temperature ?? true;
+
+  function sourceValue(input: TemperatureInput) {
+    return input.source === "internal" ? INTERNAL : input.gpio.toString();
+  }
+
+  function thermistorOn(name: string, gpio: number): TemperatureInput {
+    return {
+      name,
+      source: "thermistor",
+      gpio,
+      series_resistance: DEFAULT_SERIES_RESISTANCE,
+      model: { ...DEFAULT_THERMISTOR_MODEL },
+    };
+  }
+
+  function addInput() {
+    if (inputs.length >= MAX_TEMPERATURE_INPUTS) {
+      return;
+    }
+    const usedGpios = inputs.flatMap((input) =>
+      input.source === "thermistor" ? [input.gpio] : []
+    );
+    const gpio = adcGpios.find((gpio) => !usedGpios.includes(gpio));
+    const name = `temp${inputs.length}`;
+    setInputs([
+      ...inputs,
+      gpio === undefined && hasInternal
+        ? { name, source: "internal" }
+        : thermistorOn(name, gpio ?? 0),
+    ]);
+  }
+
+  function updateInput(index: number, input: TemperatureInput) {
+    setInputs(inputs.map((el, elIndex) => (elIndex === index ? input : el)));
+  }
+
+  function handleChangeSource(index: number, value: string) {
+    const input = inputs[index];
+    if (value === INTERNAL) {
+      updateInput(index, { name: input.name, source: "internal" });
+    } else if (input.source === "thermistor") {
+      updateInput(index, { ...input, gpio: Number(value) });
+    } else {
+      updateInput(index, thermistorOn(input.name, Number(value)));
+    }
+  }
+
+  function handleChangeModel(index: number, model: ThermistorModel) {
+    const input = inputs[index];
+    if (input.source === "thermistor") {
+      updateInput(index, { ...input, model });
+    }
+  }
+
+  function handleChangeModelParam(index: number, param: string, value: number) {
+    const input = inputs[index];
+    if (input.source === "thermistor") {
+      handleChangeModel(index, { ...input.model, [param]: value } as ThermistorModel);
+    }
+  }
+
+  async function saveInputs() {
+    setIsSaving(true);
+    await setTemperatureInputs(deviceId, inputs);
+    setIsSaving(false);
+  }
+
+  function modelParams(model: ThermistorModel): [string, string, number][] {
+    return model.kind === "beta"
+      ? [
+          ["beta", "B", model.beta],
+          ["nominal_resistance", "R0 Ω", model.nominal_resistance],
+          ["nominal_temperature", "T0 °C", model.nominal_temperature],
+        ]
+      : [
+          ["a", "A", model.a],
+          ["b", "B", model.b],
+          ["c", "C", model.c],
+        ];
+  }
+
+  return (
+    <div className="space-y-2 m-4">
+      <Label className="font-semibold">Temperature inputs</Label>
+      <div className="w-full">
+        <Table>
+          <TableHeader>
+            <TableRow>
+              <TableCell>Name</TableCell>
+              <TableCell>Source</TableCell>
+              <TableCell>Series Ω</TableCell>
+              <TableCell>Model</TableCell>
+              <TableCell>Parameters</TableCell>
+            </TableRow>
+          </TableHeader>
+          <TableBody>
+            {inputs.map((input, index) => (
+              <TableRow key={index}>
+                <TableCell>
+                  <Input
+                    value={input.name}
+                    onChange={(e) =>
+                      updateInput(index, { ...input, name: e.target.value })
+                    }
+                  ></Input>
+                </TableCell>
+                <TableCell>
+                  {adcGpios.length > 0 || input.source === "internal" ? (
+                    <Select
+                      value={sourceValue(input)}
+                      onValueChange={(value) => handleChangeSource(index, value)}
+                    >
+                      <SelectTrigger>
+                        <SelectValue />
+                      </SelectTrigger>
+                      <SelectContent>
+                        {hasInternal && (
+                          <SelectItem value={INTERNAL}>Internal</SelectItem>
+                        )}
+                        {adcGpios.map((gpio) => (
+                          <SelectItem key={gpio} value={gpio.toString()}>
+                            GPIO {gpio}
+                          </SelectItem>
+                        ))}
+                        {adcGpios.length === 0 && (
+                          <SelectItem value="0">Thermistor</SelectItem>
+                        )}
+                      </SelectContent>
+                    </Select>
+                  ) : (
+                    <Input
+                      min={0}
+                      type="number"
+                      value={input.gpio}
+                      onChange={(e) =>
+                        handleChangeSource(index, e.target.value)
+                      }
+                    ></Input>
+                  )}
+                </TableCell>
+                {input.source === "thermistor" ? (
+                  <>
+                    <TableCell>
+                      <Input
+                        min={1}
+                        type="number"
+                        value={input.series_resistance}
+                        onChange={(e) =>
+                          updateInput(index, {
+                            ...input,
+                            series_resistance: Number(e.target.value),
+                          })
+                        }
+                      ></Input>
+                    </TableCell>
+                    <TableCell>
+                      <Select
+                        value={input.model.kind}
+                        onValueChange={(kind) =>
+                          handleChangeModel(
+                            index,
+                            kind === "beta"
+                              ? { ...DEFAULT_THERMISTOR_MODEL }
+                              : { ...STEINHART_HART_DEFAULTS }
+                          )
+                        }
+                      >
+                        <SelectTrigger>
+                          <SelectValue />
+                        </SelectTrigger>
+                        <SelectContent>
+                          <SelectItem value="beta">Beta</SelectItem>
+                          <SelectItem value="steinhart_hart">
+                            Steinhart–Hart
+                          </SelectItem>
+                        </SelectContent>
+                      </Select>
+                    </TableCell>
+                    <TableCell className="flex space-x-1">
+                      {modelParams(input.model).map(([param, label, value]) => (
+                        <Input
+                          key={param}
+                          title={label}
+                          placeholder={label}
+                          type="number"
+                          step="any"
+                          value={value}
+                          onChange={(e) =>
+                            handleChangeModelParam(
+                              index,
+                              param,
+                              Number(e.target.value)
+                            )
+                          }
+                        ></Input>
+                      ))}
+                    </TableCell>
+                  </>
+                ) : (
+                  <TableCell colSpan={3}></TableCell>
+                )}
+                <TableCell>
+                  <Button
+                    variant="ghost"
+                    onClick={() =>
+                      setInputs(inputs.filter((_el, elIndex) => elIndex !== index))
+                    }
+                  >
+                    <Trash></Trash>
+                  </Button>
+                </TableCell>
+              </TableRow>
+            ))}
+          </TableBody>
+        </Table>
+      </div>
+      <div className="w-full flex space-x-2">
+        <Button
+          disabled={inputs.length >= MAX_TEMPERATURE_INPUTS}
+          onClick={addInput}
+        >
+          Add input
+        </Button>
+        <Button variant="outline" disabled={isSaving} onClick={saveInputs}>
+          Save inputs
+        </Button>
+      </div>
+    </div>
+  );
+}
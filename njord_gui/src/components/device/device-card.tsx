@@ -8,7 +8,7 @@ import {
   CardTitle,
 } from "../ui/card";
 import { Badge } from "../ui/badge";
-import { DeviceState, removeDevice } from "@/api/device";
+import { DeviceState, getControlStatus, removeDevice } from "@/api/device";
 import { useDeviceContext } from "@/context/device";
 import DeviceSettings from "./device-settings";
 import { ReactNode, useEffect, useState } from "react";
@@ -16,6 +16,8 @@ import PlugSetting from "./plug-settings";
 import PlugContextProvider from "@/context/plug";
 import { PlugState } from "@/api/plugs";
 import { listen } from "@tauri-apps/api/event";
+import { ControlStatus } from "@/types/api";
+import { CONTROL_STATUS_INTERVAL, STANDALONE_CURVES_CAPABILITY } from "@/const";
 
 export default function DeviceCard({ deviceId }: { deviceId: string }) {
   const deviceContext = useDeviceContext();
@@ -23,6 +25,9 @@ export default function DeviceCard({ deviceId }: { deviceId: string }) {
   const [plugIndex, setPlugIndex] = useState(0);
   const [deviceError, setDeviceError] = useState("");
   const [plugsStates, setPlugsStates] = useState<(PlugState | undefined)[]>([]);
+  const [controlStatus, setControlStatus] = useState<ControlStatus | null>(null);
+  const hasStandaloneCurves =
+    deviceInfo.capabilities?.includes(STANDALONE_CURVES_CAPABILITY) ?? false;
 
   const [isSheetOpen, setIsSheetOpen] = useState(false);
 
@@ -40,7 +45,10 @@ export default function DeviceCard({ deviceId }: { deviceId: string }) {
         >
           <p className="font-bold">{index}:</p>
           <p className="font-semibold text-muted-foreground">
-            {state ? `${state.last_temp}°C/${state.plug_value}%` : " -/-"}
+            {state
+              ? `${state.last_temp}°C/${state.plug_value}%` +
+                (state.rpm !== null ? `/${state.rpm} RPM` : "")
+              : " -/-"}
           </p>
         </Badge>
       );
@@ -61,6 +69,8 @@ export default function DeviceCard({ deviceId }: { deviceId: string }) {
         typeof data.payload[deviceId] === "object"
       ) {
         setDeviceError(data.payload[deviceId].Error);
+      } else if (data.payload[deviceId] === "Offline") {
+        setDeviceError("Offline");
       }
       
     })
@@ -73,13 +83,35 @@ export default function DeviceCard({ deviceId }: { deviceId: string }) {
     });
   }, []);
 
+  // a board that missed the heartbeat runs its standalone curves until the app reaches it again
+  useEffect(() => {
+    if (!hasStandaloneCurves || deviceError) {
+      return;
+    }
+    const refresh = () =>
+      getControlStatus(deviceId).then((response) => setControlStatus(response.data ?? null));
+    refresh();
+    const interval = setInterval(refresh, CONTROL_STATUS_INTERVAL);
+    return () => clearInterval(interval);
+  }, [deviceId, hasStandaloneCurves, deviceError]);
+
   return (
     <Card className="w-full">
       <CardHeader>
         <Badge variant={"outline"} className={`bg-error text-white ${deviceError? "": "hidden"}`}>
           {deviceError}
         </Badge>
-        <CardTitle>{deviceInfo.board_name}</CardTitle>
+        <CardTitle className="flex items-center space-x-2">
+          <span>{deviceInfo.board_name}</span>
+          {controlStatus && (
+            <Badge
+              variant={"outline"}
+              title={`For ${Math.round(controlStatus.mode_time / 1000)} s`}
+            >
+              {controlStatus.control_mode === "host" ? "Host control" : "Standalone"}
+            </Badge>
+          )}
+        </CardTitle>
         <CardDescription>On: {serialInfo.com_port}</CardDescription>
       </CardHeader>
       <CardContent className="flex w-full items-center">
@@ -1,19 +1,48 @@
 import { ReactNode, useState } from "react";
 import { Sheet, SheetContent, SheetHeader, SheetTitle, SheetTrigger } from "../ui/sheet";
 import DeviceSetup from "../device-setup/device-setup";
+import SetupTemperatures from "../device-setup/temperature-setup";
+import SetupStandaloneCurves from "../device-setup/standalone-setup";
 import { Button } from "../ui/button";
 import { useDeviceContext } from "@/context/device";
-import { updateDeviceConfig } from "@/api/device";
+import {
+  factoryResetDevice,
+  identifyDevice,
+  rebootDevice,
+  updateDeviceConfig,
+} from "@/api/device";
+import { IDENTIFY_SECONDS } from "@/const";
 
 export default function DeviceSettings(props: {children: ReactNode, id: string}) {
     const DeviceContext = useDeviceContext();
     const [isOpen, setIsOpen] = useState(false);
+    const [isBusy, setIsBusy] = useState(false);
+    const [confirmReset, setConfirmReset] = useState(false);
 
     async function saveDeviceSettings() {
         let response = await updateDeviceConfig(props.id, DeviceContext.data.deviceConfig);
         response.error ? null : setIsOpen(false);
     }
 
+    async function rebootHandler() {
+        setIsBusy(true);
+        await rebootDevice(props.id);
+        setIsBusy(false);
+    }
+
+    // the first click only arms the button, wiping the board's config is a second click away
+    async function factoryResetHandler() {
+        if (!confirmReset) {
+            setConfirmReset(true);
+            return;
+        }
+        setConfirmReset(false);
+        setIsBusy(true);
+        const response = await factoryResetDevice(props.id);
+        setIsBusy(false);
+        response.error ? null : setIsOpen(false);
+    }
+
     return (
       <Sheet open={isOpen} onOpenChange={() => setIsOpen(!isOpen)}>
         <SheetTrigger>{props.children}</SheetTrigger>
@@ -25,7 +54,36 @@ export default function DeviceSettings(props: {children: ReactNode, id: string})
             <SheetTitle>Device Settings</SheetTitle>
           </SheetHeader>
           <DeviceSetup deviceId={props.id} />
-          <Button onClick={saveDeviceSettings}>Save</Button>
+          <SetupTemperatures deviceId={props.id} />
+          <SetupStandaloneCurves deviceId={props.id} />
+          <div className="w-full flex space-x-2">
+            <Button
+              variant="outline"
+              className="flex-1"
+              disabled={isBusy}
+              onClick={() => identifyDevice(props.id, IDENTIFY_SECONDS)}
+            >
+              Identify
+            </Button>
+            <Button
+              variant="outline"
+              className="flex-1"
+              disabled={isBusy}
+              onClick={rebootHandler}
+            >
+              Reboot
+            </Button>
+            <Button
+              variant="destructive"
+              className="flex-1"
+              disabled={isBusy}
+              onClick={factoryResetHandler}
+              onBlur={() => setConfirmReset(false)}
+            >
+              {confirmReset ? "Confirm reset" : "Factory reset"}
+            </Button>
+          </div>
+          <Button disabled={isBusy} onClick={saveDeviceSettings}>Save</Button>
         </SheetContent>
       </Sheet>
     );
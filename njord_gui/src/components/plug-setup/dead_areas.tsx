@@ -65,6 +65,7 @@ export default function DeadAreas() {
                     type="number"
                     min={0}
                     max={100}
+                    step={0.1}
                     value={dead_area.min_value}
                     onChange={(e) =>
                       editDeadAreas(
@@ -81,6 +82,7 @@ export default function DeadAreas() {
                     type="number"
                     min={0}
                     max={100}
+                    step={0.1}
                     value={dead_area.max_value}
                     onChange={(e) =>
                       editDeadAreas(
@@ -131,6 +131,7 @@ export default function PlugChart() {
                     type="number"
                     min={0}
                     max={100}
+                    step={0.1}
                     onChange={(e) =>
                       editPoint(
                         index,
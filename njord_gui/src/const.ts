@@ -1,3 +1,27 @@
+import { PortSettings, ThermistorModel } from "./types/api";
+
 export const BAUD_RATES = [9600, 19200, 38400, 57600, 115200, 230400, 460800, 921600];
 export const DEFAULT_PLUG_VALUE = 80
-export const DEFAULT_UPDATE_TIME = 120
\ No newline at end of file
+export const PORT_VALUE_SCALE = 10 // the backend works in permille, the UI in percent
+export const DEFAULT_UPDATE_TIME = 120
+export const IDENTIFY_SECONDS = 10
+export const PORT_SETTINGS_CAPABILITY = "port_settings"
+export const TEMPERATURE_INPUTS_CAPABILITY = "set_temperature_inputs"
+export const MAX_TEMPERATURE_INPUTS = 4
+export const DEFAULT_SERIES_RESISTANCE = 10000
+export const STANDALONE_CURVES_CAPABILITY = "set_standalone_curves"
+export const MAX_STANDALONE_CURVE_POINTS = 8
+export const CONTROL_STATUS_INTERVAL = 5000
+// the common 10k NTC found on fan hubs and coolant plugs
+export const DEFAULT_THERMISTOR_MODEL: ThermistorModel = {
+  kind: "beta",
+  beta: 3950,
+  nominal_resistance: 10000,
+  nominal_temperature: 25,
+}
+export const DEFAULT_PORT_SETTINGS: PortSettings = {
+  pwm_frequency: 25000,
+  pwm_resolution: 10,
+  inverted: false,
+  output_mode: "pwm",
+}
\ No newline at end of file
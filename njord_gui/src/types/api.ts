@@ -1,6 +1,10 @@
 export interface DeviceInfo {
   board_name: string,
-  max_ports: number
+  max_ports: number,
+  protocol_version: number,
+  capabilities: string[],
+  device_uid?: string,
+  firmware_version?: string
 }
 
 export interface PortInfo {
@@ -14,10 +18,32 @@ export interface SerialInfo {
   baud_rate: number;
 }
 
+export interface DiscoveredDevice {
+  serial_info: SerialInfo;
+  device_info: DeviceInfo;
+}
+
+export type OutputMode = "pwm" | "on_off";
+
+export interface PortSettings {
+    pwm_frequency: number,
+    pwm_resolution: number,
+    inverted: boolean,
+    output_mode: OutputMode
+}
+
 export interface DeviceConfig {
     ports: number[],
     default_values: number[],
-    update_time: number
+    update_time: number,
+    tach_inputs?: (number | null)[],
+    heartbeat_timeout?: number,
+    port_settings?: PortSettings[]
+}
+
+export interface UpdateStats {
+  sent: number,
+  skipped: number
 }
 
 export enum CoreMessageKind {
@@ -31,8 +57,74 @@ export interface CoreMessage {
   message: string
 }
 
+// NTC curves, resistances in ohms and temperatures in °C
+export type ThermistorModel =
+  | { kind: "beta", beta: number, nominal_resistance: number, nominal_temperature: number }
+  | { kind: "steinhart_hart", a: number, b: number, c: number };
+
+export type TemperatureInput = { name: string } & (
+  | { source: "internal" }
+  | { source: "thermistor", gpio: number, series_resistance: number, model: ThermistorModel }
+);
+
+// Runs on the board while the host is away, values in permille
+export interface StandaloneCurve {
+  temperature_input: string,
+  curve: { temp: number, value: number }[],
+  min_value: number,
+  max_value: number
+}
+
+export type ControlMode = "host" | "standalone";
+
+export interface ControlStatus {
+  control_mode: ControlMode,
+  mode_time: number, // ms
+  standalone_time: number | null // ms the board ran standalone before the host last took over
+}
+
 export interface Device {
   serial_info: SerialInfo,
   device_info: DeviceInfo,
-  device_config: DeviceConfig
-}
\ No newline at end of file
+  device_config: DeviceConfig,
+  temperature_inputs?: TemperatureInput[],
+  standalone_curves?: (StandaloneCurve | null)[],
+  control_status?: ControlStatus | null
+}
+export type ErrorKind = "transport" | "protocol" | "firmware" | "validation" | "update" | "app";
+
+export type PwmLayout = "any" | "rp2040";
+
+export interface BoardProfile {
+  name: string,
+  board_names: string[],
+  gpios: number[], // empty allows any
+  led_gpio: number | null,
+  reset_button_gpio: number | null,
+  default_baud_rate: number,
+  max_ports: number | null,
+  pwm_layout: PwmLayout,
+  max_pwm_frequency: number,
+  max_pwm_resolution: number,
+  adc_gpios: number[], // empty allows any
+  internal_temperature: boolean
+}
+
+export interface ConfigProblem {
+  problem: string,
+  message: string,
+  [field: string]: unknown
+}
+
+export interface ConfigReport {
+  board: string,
+  problems: ConfigProblem[]
+}
+
+export interface AppError {
+  kind: ErrorKind,
+  code: string | null,
+  message: string,
+  retryable: boolean,
+  report?: ConfigReport
+}
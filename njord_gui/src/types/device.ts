@@ -1,4 +1,8 @@
+import { PortSettings } from "./api";
+
 export interface PlugSetting {
     port: number,
-    default_value: number
+    default_value: number,
+    tach_input: number | null,
+    settings: PortSettings
 }
@@ -1 +1,3 @@
-export type WrappedError<T> = { data: T | null; error: string | null }; 
\ No newline at end of file
+import { AppError } from "@/types/api";
+
+export type WrappedError<T> = { data: T | null; error: AppError | null };
@@ -1,17 +1,29 @@
 import { DeviceConfig } from "@/types/api";
 import { PlugSetting } from "@/types/device";
-import { DEFAULT_PLUG_VALUE } from "@/const";
+import { DEFAULT_PLUG_VALUE, DEFAULT_PORT_SETTINGS, PORT_VALUE_SCALE } from "@/const";
+
+export function valueToPercent(value: number): number {
+  return value / PORT_VALUE_SCALE;
+}
+
+export function percentToValue(percent: number): number {
+  return Math.round(percent * PORT_VALUE_SCALE);
+}
 
 export function deviceConfigToPlugSetting(
   deviceConfig: DeviceConfig
 ): PlugSetting[] {
   const plugs = deviceConfig.ports;
   const defaultValues = deviceConfig.default_values;
+  const tachInputs = deviceConfig.tach_inputs || [];
+  const portSettings = deviceConfig.port_settings || [];
   return plugs.map((el, index) => ({
     port: el,
     default_value: defaultValues[index] !== undefined
       ? defaultValues[index]
       : DEFAULT_PLUG_VALUE,
+    tach_input: tachInputs[index] ?? null,
+    settings: { ...DEFAULT_PORT_SETTINGS, ...portSettings[index] },
   }));
 }
 
@@ -21,15 +33,21 @@ export function plugSettingToDeviceConfig(
 ): DeviceConfig {
   let ports = [];
   let default_values = [];
+  let tach_inputs = [];
+  let port_settings = [];
 
   for (let plugSetting of plugSettings) {
     ports.push(plugSetting.port);
     default_values.push(plugSetting.default_value);
+    tach_inputs.push(plugSetting.tach_input);
+    port_settings.push(plugSetting.settings);
   }
 
   return {
     ...deviceConfig,
     ports,
     default_values,
+    tach_inputs,
+    port_settings,
   };
 }
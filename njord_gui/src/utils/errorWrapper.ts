@@ -1,6 +1,15 @@
 import { WrappedError } from "@/types/utils";
+import { AppError } from "@/types/api";
 import { toast } from "sonner";
 
+function toAppError(error: unknown): AppError {
+  if (typeof error === "object" && error !== null && "message" in error && "kind" in error) {
+    return error as AppError;
+  }
+  const message = error instanceof Error ? error.message : String(error);
+  return { kind: "app", code: null, message, retryable: false };
+}
+
 export async function errorWrapper<T>(
   fn: () => Promise<T>
 ): Promise<WrappedError<T>> {
@@ -8,14 +17,14 @@ export async function errorWrapper<T>(
     const data = await fn();
     return { data, error: null };
   } catch (error) {
-    const error_msg = error instanceof Error ? error.message : error as string;
+    const app_error = toAppError(error);
 
-    toast.error(error_msg)
-    console.error(error_msg)
+    toast.error(app_error.message)
+    console.error(app_error)
 
     return {
       data: null,
-      error: error_msg,
+      error: app_error,
     };
   }
 }